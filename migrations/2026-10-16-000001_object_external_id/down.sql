@@ -0,0 +1,33 @@
+DROP VIEW IF EXISTS object_closure_view;
+CREATE VIEW object_closure_view AS
+    SELECT 
+        oc.ancestor_object_id,
+        oc.descendant_object_id,
+        oc.depth,
+        oc.path,
+        aob.name AS ancestor_name,
+        dob.name AS descendant_name,
+        aob.namespace_id AS ancestor_namespace_id,
+        dob.namespace_id AS descendant_namespace_id,
+        aob.hubuum_class_id AS ancestor_class_id,
+        dob.hubuum_class_id AS descendant_class_id,
+        aob.description AS ancestor_description,
+        dob.description AS descendant_description,
+        aob.data AS ancestor_data,
+        dob.data AS descendant_data,
+        aob.created_at AS ancestor_created_at,
+        dob.created_at AS descendant_created_at,
+        aob.updated_at AS ancestor_updated_at,
+        dob.updated_at AS descendant_updated_at
+
+    FROM 
+        hubuumobject_closure oc
+    JOIN 
+        hubuumobject aob ON oc.ancestor_object_id = aob.id
+    JOIN 
+        hubuumobject dob ON oc.descendant_object_id = dob.id;
+
+DROP INDEX IF EXISTS idx_hubuumobject_external_id;
+DROP INDEX IF EXISTS idx_hubuumobject_namespace_external_id;
+
+ALTER TABLE hubuumobject DROP COLUMN IF EXISTS external_id;
@@ -0,0 +1,38 @@
+    -- An optional identifier for objects that is controlled by external systems. It is unique
+    -- per namespace, global uniqueness can be enforced by the application (see the
+    -- HUBUUM_OBJECT_EXTERNAL_ID_SCOPE configuration option).
+    ALTER TABLE hubuumobject ADD COLUMN external_id VARCHAR NULL;
+
+    CREATE UNIQUE INDEX idx_hubuumobject_namespace_external_id ON hubuumobject (namespace_id, external_id);
+    CREATE INDEX idx_hubuumobject_external_id ON hubuumobject (external_id);
+
+    -- Expose the external IDs through the object closure view as well.
+    CREATE OR REPLACE VIEW object_closure_view AS
+    SELECT 
+        oc.ancestor_object_id,
+        oc.descendant_object_id,
+        oc.depth,
+        oc.path,
+        aob.name AS ancestor_name,
+        dob.name AS descendant_name,
+        aob.namespace_id AS ancestor_namespace_id,
+        dob.namespace_id AS descendant_namespace_id,
+        aob.hubuum_class_id AS ancestor_class_id,
+        dob.hubuum_class_id AS descendant_class_id,
+        aob.description AS ancestor_description,
+        dob.description AS descendant_description,
+        aob.data AS ancestor_data,
+        dob.data AS descendant_data,
+        aob.created_at AS ancestor_created_at,
+        dob.created_at AS descendant_created_at,
+        aob.updated_at AS ancestor_updated_at,
+        dob.updated_at AS descendant_updated_at,
+        aob.external_id AS ancestor_external_id,
+        dob.external_id AS descendant_external_id
+
+    FROM 
+        hubuumobject_closure oc
+    JOIN 
+        hubuumobject aob ON oc.ancestor_object_id = aob.id
+    JOIN 
+        hubuumobject dob ON oc.descendant_object_id = dob.id;
@@ -0,0 +1,3 @@
+    -- Inactive groups keep their members and permission grants, but the grants are
+    -- ignored when checking permissions. This allows for reversible offboarding.
+    ALTER TABLE groups ADD COLUMN active BOOLEAN NOT NULL DEFAULT TRUE;
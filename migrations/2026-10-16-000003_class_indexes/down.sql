@@ -0,0 +1,10 @@
+    DO $$
+    DECLARE
+        r RECORD;
+    BEGIN
+        FOR r IN SELECT id FROM class_indexes LOOP
+            EXECUTE format('DROP INDEX IF EXISTS hubuumobject_data_class_index_%s', r.id);
+        END LOOP;
+    END $$;
+
+    DROP TABLE class_indexes;
@@ -0,0 +1,10 @@
+    -- Indexed JSON paths in object data, declared per class. Each row has a matching expression
+    -- index on hubuumobject named hubuumobject_data_class_index_<id>, which is created and dropped
+    -- by the application together with the row.
+    CREATE TABLE class_indexes (
+        id SERIAL PRIMARY KEY,
+        hubuum_class_id INT REFERENCES hubuumclass (id) ON DELETE CASCADE NOT NULL,
+        json_path TEXT NOT NULL,
+        created_at TIMESTAMP NOT NULL DEFAULT now(),
+        UNIQUE (hubuum_class_id, json_path)
+    );
@@ -0,0 +1,13 @@
+    -- Audit trail for permission changes. Rows are kept when the namespace or group is deleted,
+    -- so there are no foreign keys. A NULL permissions column means all permissions.
+    CREATE TABLE permission_audit (
+        id SERIAL PRIMARY KEY,
+        namespace_id INT NOT NULL,
+        group_id INT NOT NULL,
+        action VARCHAR NOT NULL,
+        permissions TEXT DEFAULT NULL,
+        reason TEXT DEFAULT NULL,
+        created_at TIMESTAMP NOT NULL DEFAULT now()
+    );
+
+    CREATE INDEX permission_audit_namespace_group_idx ON permission_audit (namespace_id, group_id);
@@ -0,0 +1,4 @@
+    DROP TABLE token_audit;
+
+    ALTER TABLE tokens DROP COLUMN ttl_hours;
+    ALTER TABLE tokens DROP COLUMN name;
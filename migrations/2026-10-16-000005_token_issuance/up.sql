@@ -0,0 +1,16 @@
+    -- Tokens may carry a name and a lifetime of their own. A NULL lifetime means the default.
+    ALTER TABLE tokens ADD COLUMN name VARCHAR DEFAULT NULL;
+    ALTER TABLE tokens ADD COLUMN ttl_hours INT DEFAULT NULL;
+
+    -- Audit trail for tokens issued on behalf of other users. Rows are kept when either user is
+    -- deleted, so there are no foreign keys. The token itself is never recorded.
+    CREATE TABLE token_audit (
+        id SERIAL PRIMARY KEY,
+        user_id INT NOT NULL,
+        issued_by INT NOT NULL,
+        name VARCHAR DEFAULT NULL,
+        ttl_hours INT DEFAULT NULL,
+        created_at TIMESTAMP NOT NULL DEFAULT now()
+    );
+
+    CREATE INDEX token_audit_user_idx ON token_audit (user_id);
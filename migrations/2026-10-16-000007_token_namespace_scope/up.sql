@@ -0,0 +1,3 @@
+    -- A token may be scoped to a single namespace, limiting it to that namespace regardless of
+    -- the permissions of its user. A NULL namespace means the token is not scoped.
+    ALTER TABLE tokens ADD COLUMN namespace_id INT DEFAULT NULL REFERENCES namespaces (id) ON DELETE CASCADE;
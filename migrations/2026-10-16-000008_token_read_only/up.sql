@@ -0,0 +1,2 @@
+    -- Read-only tokens may only be used for GET and HEAD requests.
+    ALTER TABLE tokens ADD COLUMN read_only BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1 @@
+    ALTER TABLE namespaces DROP COLUMN write_rate_limit;
@@ -0,0 +1,3 @@
+    -- Writes per minute allowed in the namespace, overriding HUBUUM_NAMESPACE_WRITE_RATE_LIMIT.
+    -- NULL uses the configured default, 0 disables the limit for the namespace.
+    ALTER TABLE namespaces ADD COLUMN write_rate_limit INT;
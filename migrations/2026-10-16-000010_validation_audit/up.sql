@@ -0,0 +1,12 @@
+    -- Audit trail for object writes where an admin skipped schema validation. Rows are kept when
+    -- the object, class or user is deleted, so there are no foreign keys.
+    CREATE TABLE validation_audit (
+        id SERIAL PRIMARY KEY,
+        object_id INT NOT NULL,
+        class_id INT NOT NULL,
+        user_id INT NOT NULL,
+        action VARCHAR NOT NULL,
+        created_at TIMESTAMP NOT NULL DEFAULT now()
+    );
+
+    CREATE INDEX validation_audit_object_idx ON validation_audit (object_id);
@@ -0,0 +1,11 @@
+    -- Audit trail for passwords reset by an admin. Rows are kept when either user is deleted, so
+    -- there are no foreign keys. The password itself is never recorded.
+    CREATE TABLE password_audit (
+        id SERIAL PRIMARY KEY,
+        user_id INT NOT NULL,
+        reset_by INT NOT NULL,
+        tokens_revoked BOOLEAN NOT NULL,
+        created_at TIMESTAMP NOT NULL DEFAULT now()
+    );
+
+    CREATE INDEX password_audit_user_idx ON password_audit (user_id);
@@ -0,0 +1,3 @@
+    ALTER TABLE namespaces DROP COLUMN attachment_size_limit;
+    ALTER TABLE namespaces DROP COLUMN default_class_permissions;
+    ALTER TABLE namespaces DROP COLUMN object_quota;
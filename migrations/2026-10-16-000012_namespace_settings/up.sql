@@ -0,0 +1,5 @@
+    -- Settings of the namespace, set through PATCH /api/v1/namespaces/{namespace_id}.
+    -- NULL means the setting is not set for the namespace.
+    ALTER TABLE namespaces ADD COLUMN object_quota INT;
+    ALTER TABLE namespaces ADD COLUMN default_class_permissions TEXT[];
+    ALTER TABLE namespaces ADD COLUMN attachment_size_limit BIGINT;
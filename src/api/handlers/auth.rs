@@ -1,6 +1,6 @@
 use crate::db::DbPool;
 use crate::errors::ApiError;
-use crate::extractors::{AdminAccess, UserAccess};
+use crate::extractors::{AdminAccess, MutatingGet, UserAccess};
 use crate::models::{LoginUser, Token, UserID};
 use crate::utilities::response::json_response;
 use actix_web::{get, http::StatusCode, post, web, Responder};
@@ -81,6 +81,7 @@ pub async fn logout(
 pub async fn logout_all(
     pool: web::Data<DbPool>,
     user_access: UserAccess,
+    _mutating: MutatingGet,
 ) -> Result<impl Responder, ApiError> {
     debug!(
         message = "Logging out all tokens for {}.",
@@ -112,6 +113,7 @@ pub async fn logout_all(
 pub async fn logout_token(
     pool: web::Data<DbPool>,
     user_access: AdminAccess,
+    _mutating: MutatingGet,
     token: web::Path<Token>,
 ) -> Result<impl Responder, ApiError> {
     debug!(message = "Logging out token {}.", token = token.obfuscate());
@@ -142,6 +144,7 @@ pub async fn logout_token(
 pub async fn logout_other(
     pool: web::Data<DbPool>,
     admin_access: AdminAccess,
+    _mutating: MutatingGet,
     user_id: web::Path<UserID>,
 ) -> Result<impl Responder, ApiError> {
     use crate::traits::SelfAccessors;
@@ -1,10 +1,23 @@
 use actix_web::web;
 
+use crate::middlewares::concurrency::ConcurrencyLimitMiddleware;
+use crate::middlewares::namespace_scope::NamespaceScopeMiddleware;
+
 pub mod handlers;
 pub mod routes;
 pub mod v1;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(web::scope("api/v1").configure(v1::routes::config))
-        .service(web::scope("api/v0").configure(routes::config));
+    cfg.service(
+        web::scope("api/v1")
+            .wrap(NamespaceScopeMiddleware)
+            .wrap(ConcurrencyLimitMiddleware)
+            .configure(v1::routes::config),
+    )
+    .service(
+        web::scope("api/v0")
+            .wrap(NamespaceScopeMiddleware)
+            .wrap(ConcurrencyLimitMiddleware)
+            .configure(routes::config),
+    );
 }
@@ -0,0 +1,50 @@
+use crate::config::get_config;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::extractors::AdminAccess;
+use crate::models::search::parse_query_parameter;
+use crate::models::stats::SystemStats;
+use crate::models::token::{TokenInfo, UserToken};
+use crate::utilities::response::json_response;
+use actix_web::{http::StatusCode, routes, web, HttpRequest, Responder};
+use tracing::debug;
+
+#[routes]
+#[get("/tokens")]
+#[get("/tokens/")]
+pub async fn get_tokens(
+    pool: web::Data<DbPool>,
+    requestor: AdminAccess,
+    req: HttpRequest,
+) -> Result<impl Responder, ApiError> {
+    let params = parse_query_parameter(req.query_string())?;
+
+    debug!(
+        message = "Token list requested",
+        requestor = requestor.user.id,
+        query = req.query_string()
+    );
+
+    let tokens = UserToken::search(&pool, params)
+        .await?
+        .into_iter()
+        .map(TokenInfo::from)
+        .collect::<Vec<_>>();
+
+    Ok(json_response(tokens, StatusCode::OK))
+}
+
+#[routes]
+#[get("/stats")]
+#[get("/stats/")]
+pub async fn get_stats(
+    pool: web::Data<DbPool>,
+    requestor: AdminAccess,
+) -> Result<impl Responder, ApiError> {
+    debug!(message = "Stats requested", requestor = requestor.user.id);
+
+    let approximate = get_config().await.approximate_stats;
+    let stats = SystemStats::get(&pool, approximate).await?;
+
+    Ok(json_response(stats, StatusCode::OK))
+}
@@ -1,24 +1,34 @@
-use actix_web::{delete, get, http::StatusCode, patch, post, routes, web, HttpRequest, Responder};
+use actix_web::{
+    delete, get,
+    http::header::{ETag, IfNoneMatch, IfUnmodifiedSince},
+    http::StatusCode,
+    patch, post, put, routes, web, HttpRequest, Responder,
+};
 
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use crate::can;
 use crate::db::traits::{ClassRelation, ObjectRelationMemberships, UserPermissions};
 use crate::db::DbPool;
 use crate::errors::ApiError;
-use crate::extractors::UserAccess;
+use crate::extractors::{AdminAccess, UserAccess};
 use crate::models::traits::{ExpandNamespace, ToHubuumObjects};
-use crate::utilities::response::{json_response, json_response_created};
+use crate::utilities::response::{
+    check_unmodified_since, entity_tag, json_response, json_response_created, private_response,
+};
+use crate::{can, limit_writes};
 
 use crate::models::{
-    HubuumClassID, HubuumClassRelationID, HubuumObjectID, NamespaceID, NewHubuumClass,
-    NewHubuumClassRelationFromClass, NewHubuumObject, NewHubuumObjectRelation, Permissions,
-    UpdateHubuumClass, UpdateHubuumObject,
+    BatchItemResult, BatchOptions, BatchUpdateHubuumObject, ClassRelationPathOptions,
+    CloneHubuumClass, CreateClassOptions, HubuumClass, HubuumClassID, HubuumClassRelationID,
+    HubuumObject, HubuumObjectID, HubuumObjectRelationWithDirection, NamespaceID, NewClassIndex,
+    NewClassIndexFromClass, NewHubuumClass, NewHubuumClassRelationFromClass, NewHubuumObject,
+    NewHubuumObjectRelation, NewValidationAudit, ObjectWriteOptions, Permissions,
+    UpdateHubuumClass, UpdateHubuumObject, UpsertHubuumObject, User,
 };
 use crate::traits::{CanDelete, CanSave, CanUpdate, NamespaceAccessors, Search, SelfAccessors};
 
 use super::check_if_object_in_class;
-use crate::models::search::{parse_query_parameter, FilterField, ParsedQueryParam};
+use crate::models::search::{parse_query_parameter, FilterField, ParsedQueryParam, QueryParamsExt};
 
 // GET /api/v1/classes, list all classes the user may see.
 #[routes]
@@ -44,12 +54,28 @@ async fn get_classes(
     Ok(json_response(classes, StatusCode::OK))
 }
 
+// GET /api/v1/classes/names, list the distinct names of the classes the user may read.
+#[get("/names")]
+async fn get_class_names(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+
+    debug!(message = "Listing class names", user_id = user.id());
+
+    let names = user.search_class_names(&pool).await?;
+
+    Ok(json_response(names, StatusCode::OK))
+}
+
 #[routes]
 #[post("")]
 #[post("/")]
 async fn create_class(
     pool: web::Data<DbPool>,
     requestor: UserAccess,
+    options: web::Query<CreateClassOptions>,
     class_data: web::Json<NewHubuumClass>,
 ) -> Result<impl Responder, ApiError> {
     let user = requestor.user;
@@ -58,12 +84,42 @@ async fn create_class(
     debug!(
         message = "Creating class",
         user_id = user.id(),
-        class_name = class_data.name
+        class_name = class_data.name,
+        if_not_exists = options.if_not_exists
     );
 
     let namespace = NamespaceID(class_data.namespace_id);
     can!(&pool, user, [Permissions::CreateClass], namespace);
 
+    if options.if_not_exists {
+        if let Some(existing) = HubuumClass::by_name(&pool, &class_data.name).await? {
+            if existing.namespace_id != class_data.namespace_id {
+                // Only tell where the class is to those who may read it.
+                let readable = match user
+                    .can(&pool, [Permissions::ReadClass], [existing.clone()])
+                    .await
+                {
+                    Ok(()) => true,
+                    Err(ApiError::Forbidden(_)) => false,
+                    Err(e) => return Err(e),
+                };
+                return Err(ApiError::Conflict(if readable {
+                    format!(
+                        "Class '{}' already exists in namespace {}",
+                        existing.name, existing.namespace_id
+                    )
+                } else {
+                    format!("Class '{}' already exists", class_data.name)
+                }));
+            }
+            can!(&pool, user, [Permissions::ReadClass], existing);
+            let existing = existing.expand_namespace(&pool).await?;
+            return Ok(json_response(existing, StatusCode::OK));
+        }
+    }
+
+    limit_writes!(&pool, namespace);
+
     let class = class_data
         .save(&pool)
         .await?
@@ -76,7 +132,9 @@ async fn create_class(
     ))
 }
 
+#[routes]
 #[get("/{class_id}")]
+#[head("/{class_id}")]
 async fn get_class(
     pool: web::Data<DbPool>,
     requestor: UserAccess,
@@ -98,6 +156,54 @@ async fn get_class(
     Ok(json_response(class, StatusCode::OK))
 }
 
+// GET /api/v1/classes/{class_id}/schema, the JSON schema of a class on its own.
+//
+// The response carries an ETag derived from when the class was last updated, and a request
+// with a matching If-None-Match gets a 304 without the schema.
+#[routes]
+#[get("/{class_id}/schema")]
+#[head("/{class_id}/schema")]
+async fn get_class_schema(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    class_id: web::Path<HubuumClassID>,
+    if_none_match: Option<web::Header<IfNoneMatch>>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let class = class_id.into_inner();
+
+    debug!(
+        message = "Getting class schema",
+        user_id = user.id(),
+        class_id = class.id()
+    );
+
+    let class = class.instance(&pool).await?;
+    can!(&pool, user, [Permissions::ReadClass], class);
+
+    let schema = class
+        .json_schema
+        .ok_or_else(|| ApiError::NotFound(format!("Class {} has no JSON schema", class.id)))?;
+
+    let etag = entity_tag(class.id, class.updated_at);
+    let not_modified = match if_none_match.map(|h| h.into_inner()) {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+
+    if not_modified {
+        return Ok(private_response(StatusCode::NOT_MODIFIED)
+            .insert_header(ETag(etag))
+            .finish());
+    }
+
+    Ok(private_response(StatusCode::OK)
+        .content_type("application/schema+json")
+        .insert_header(ETag(etag))
+        .body(schema.to_string()))
+}
+
 #[patch("/{class_id}")]
 async fn update_class(
     pool: web::Data<DbPool>,
@@ -117,6 +223,7 @@ async fn update_class(
 
     let class = class_id.instance(&pool).await?;
     can!(&pool, user, [Permissions::UpdateClass], class);
+    limit_writes!(&pool, class);
 
     let class = class_data
         .update(&pool, class.id)
@@ -126,11 +233,66 @@ async fn update_class(
     Ok(json_response(class, StatusCode::OK))
 }
 
+// POST /api/v1/classes/{class_id}/clone, create a new class from the schema of an existing one.
+//
+// Requires ReadClass on the source and CreateClass on the target namespace. Copying relations
+// also requires CreateClassRelation on the target namespace and on the namespaces of the related
+// classes, the same as creating the relations by hand.
+#[post("/{class_id}/clone")]
+async fn clone_class(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    class_id: web::Path<HubuumClassID>,
+    clone_data: web::Json<CloneHubuumClass>,
+) -> Result<impl Responder, ApiError> {
+    use crate::db::traits::SelfRelations;
+
+    let user = requestor.user;
+    let clone_data = clone_data.into_inner();
+    let source = class_id.into_inner().instance(&pool).await?;
+    let target_namespace = NamespaceID(clone_data.namespace_id.unwrap_or(source.namespace_id));
+
+    debug!(
+        message = "Cloning class",
+        user_id = user.id(),
+        class_id = source.id(),
+        target_namespace = target_namespace.id(),
+        include_relations = clone_data.include_relations
+    );
+
+    can!(&pool, user, [Permissions::ReadClass], source);
+    can!(&pool, user, [Permissions::CreateClass], target_namespace);
+
+    if clone_data.include_relations {
+        let mut namespaces = vec![target_namespace];
+        for relation in source.relations(&pool).await? {
+            let other = if relation.from_hubuum_class_id == source.id() {
+                relation.to_hubuum_class_id
+            } else {
+                relation.from_hubuum_class_id
+            };
+            let other = HubuumClassID(other).instance(&pool).await?;
+            namespaces.push(NamespaceID(other.namespace_id));
+        }
+        user.can(&pool, [Permissions::CreateClassRelation], namespaces)
+            .await?;
+    }
+    limit_writes!(&pool, target_namespace);
+
+    let clone = source.clone_class(&pool, &clone_data).await?;
+
+    let location = format!("/api/v1/classes/{}", clone.id);
+    Ok(json_response_created(clone, &location))
+}
+
+// DELETE /api/v1/classes/{class_id}. With If-Unmodified-Since, the class is only deleted if it
+// has not been updated after the given date.
 #[delete("/{class_id}")]
 async fn delete_class(
     pool: web::Data<DbPool>,
     requestor: UserAccess,
     class_id: web::Path<HubuumClassID>,
+    if_unmodified_since: Option<web::Header<IfUnmodifiedSince>>,
 ) -> Result<impl Responder, ApiError> {
     let user = requestor.user;
     let class_id = class_id.into_inner();
@@ -143,6 +305,8 @@ async fn delete_class(
 
     let class = class_id.instance(&pool).await?;
     can!(&pool, user, [Permissions::DeleteClass], class);
+    check_unmodified_since(if_unmodified_since.as_deref(), class.updated_at)?;
+    limit_writes!(&pool, class);
 
     class.delete(&pool).await?;
     Ok(json_response((), StatusCode::NO_CONTENT))
@@ -185,6 +349,128 @@ async fn get_class_permissions(
     Ok(json_response(permissions, StatusCode::OK))
 }
 
+// GET /api/v1/classes/{class_id}/namespaces, list the namespaces that objects of the class live
+// in, with object counts. Only namespaces where the user may read objects are included.
+#[get("/{class_id}/namespaces")]
+async fn get_class_namespaces(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    class_id: web::Path<HubuumClassID>,
+) -> Result<impl Responder, ApiError> {
+    use crate::traits::UserNamespaceAccessors;
+
+    let user = requestor.user;
+    let class_id = class_id.into_inner();
+
+    debug!(
+        message = "Getting class namespaces",
+        user_id = user.id(),
+        class_id = class_id.id()
+    );
+
+    let class = class_id.instance(&pool).await?;
+    can!(&pool, user, [Permissions::ReadClass], class);
+
+    let namespace_ids: Vec<i32> = user
+        .namespaces(
+            &pool,
+            &[Permissions::ReadCollection, Permissions::ReadObject],
+        )
+        .await?
+        .iter()
+        .map(|n| n.id)
+        .collect();
+
+    let namespaces = class.object_namespaces(&pool, &namespace_ids).await?;
+    Ok(json_response(namespaces, StatusCode::OK))
+}
+
+// GET /api/v1/classes/{class_id}/indexes/, list the indexed JSON paths declared on the class.
+#[get("/{class_id}/indexes/")]
+async fn get_class_indexes(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    class_id: web::Path<HubuumClassID>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let class_id = class_id.into_inner();
+
+    debug!(
+        message = "Getting class indexes",
+        user_id = user.id(),
+        class_id = class_id.id()
+    );
+
+    let class = class_id.instance(&pool).await?;
+    can!(&pool, user, [Permissions::ReadClass], class);
+
+    let indexes = class.indexes(&pool).await?;
+    Ok(json_response(indexes, StatusCode::OK))
+}
+
+// POST /api/v1/classes/{class_id}/indexes/, declare an indexed JSON path on the class and create
+// the backing expression index. Building indexes locks the object table, so this is admin only.
+#[post("/{class_id}/indexes/")]
+async fn create_class_index(
+    pool: web::Data<DbPool>,
+    requestor: AdminAccess,
+    class_id: web::Path<HubuumClassID>,
+    index_data: web::Json<NewClassIndexFromClass>,
+) -> Result<impl Responder, ApiError> {
+    let class = class_id.into_inner().instance(&pool).await?;
+    let index_data = index_data.into_inner();
+
+    info!(
+        message = "Creating class index",
+        user_id = requestor.user.id(),
+        class_id = class.id(),
+        json_path = index_data.json_path
+    );
+
+    let index = NewClassIndex {
+        hubuum_class_id: class.id(),
+        json_path: index_data.json_path,
+    }
+    .save(&pool)
+    .await?;
+
+    let location = format!("/api/v1/classes/{}/indexes/{}", class.id(), index.id);
+    Ok(json_response_created(index, &location))
+}
+
+#[delete("/{class_id}/indexes/{index_id}")]
+async fn delete_class_index(
+    pool: web::Data<DbPool>,
+    requestor: AdminAccess,
+    paths: web::Path<(HubuumClassID, i32)>,
+) -> Result<impl Responder, ApiError> {
+    let (class_id, index_id) = paths.into_inner();
+    let class = class_id.instance(&pool).await?;
+
+    info!(
+        message = "Deleting class index",
+        user_id = requestor.user.id(),
+        class_id = class.id(),
+        index_id = index_id
+    );
+
+    let index = class
+        .indexes(&pool)
+        .await?
+        .into_iter()
+        .find(|i| i.id == index_id)
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Index {} not found for class {}",
+                index_id,
+                class.id()
+            ))
+        })?;
+
+    index.delete(&pool).await?;
+    Ok(json_response((), StatusCode::NO_CONTENT))
+}
+
 fn ensure_class_filter(
     params: &mut Vec<ParsedQueryParam>,
     field: FilterField,
@@ -256,17 +542,16 @@ async fn create_class_relation(
         to_hubuum_class_id: partial_relation.to_hubuum_class_id,
     };
 
-    let ids = relation
-        .namespace_id(&pool)
-        .await
-        .map(|(id0, id1)| (NamespaceID(id0), NamespaceID(id1)))?;
+    // The classes may live in different namespaces, and the permission is required on both.
+    let namespaces = relation.namespace(&pool).await?;
     can!(
         &pool,
         user,
         [Permissions::CreateClassRelation],
-        ids.0,
-        ids.1
+        namespaces.0,
+        namespaces.1
     );
+    limit_writes!(&pool, namespaces.0, namespaces.1);
 
     let relation = relation.save(&pool).await?;
 
@@ -313,6 +598,7 @@ async fn delete_class_relation(
         ids.0,
         ids.1
     );
+    limit_writes!(&pool, ids.0, ids.1);
 
     if relation.from_hubuum_class_id == class_id.id() {
         relation.delete(&pool).await?;
@@ -355,6 +641,52 @@ async fn get_class_relations_transitive(
     Ok(json_response(relations, StatusCode::OK))
 }
 
+// GET /api/v1/classes/{class_id}/relations/path-to/{class_id_to}, the chains of relations that
+// connect two classes, shortest first. With `?shortest=true`, only a shortest chain is returned.
+// Requires ReadClassRelation on the namespaces of both classes.
+#[get("/{class_id}/relations/path-to/{class_id_to}")]
+async fn get_class_relation_paths(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    class_ids: web::Path<(HubuumClassID, HubuumClassID)>,
+    options: web::Query<ClassRelationPathOptions>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let (class_id, class_id_to) = class_ids.into_inner();
+
+    debug!(
+        message = "Getting class relation paths",
+        user_id = user.id(),
+        class_id_from = class_id.id(),
+        class_id_to = class_id_to.id(),
+        shortest = options.shortest
+    );
+
+    let from_class = class_id.instance(&pool).await?;
+    let to_class = class_id_to.instance(&pool).await?;
+    can!(
+        &pool,
+        user,
+        [Permissions::ReadClassRelation],
+        from_class,
+        to_class
+    );
+
+    let mut paths = from_class.paths_to(&pool, &to_class).await?;
+    if paths.is_empty() {
+        return Err(ApiError::NotFound(format!(
+            "Class {} is not related to class {}",
+            from_class.id, to_class.id
+        )));
+    }
+
+    if options.shortest {
+        paths.truncate(1);
+    }
+
+    Ok(json_response(paths, StatusCode::OK))
+}
+
 #[get("/{class_id}/relations/transitive/class/{class_id_to}")]
 async fn get_class_relations_transitive_to_class(
     pool: web::Data<DbPool>,
@@ -403,6 +735,9 @@ async fn get_objects_in_class(
         value: class.id().to_string(),
     };
     params.push(class_filter);
+    let shape = params.shape()?;
+    let expansion = params.relation_expansion()?;
+    params.retain(|p| !matches!(p.field, FilterField::Shape | FilterField::Expand));
 
     debug!(
         message = "Getting objects in class",
@@ -412,6 +747,38 @@ async fn get_objects_in_class(
     );
 
     let objects = user.search_objects(&pool, params).await?;
+    let objects = match expansion {
+        Some(expansion) => shape.apply(
+            user.expand_object_relations(&pool, objects, expansion)
+                .await?,
+        )?,
+        None => shape.apply(objects)?,
+    };
+
+    Ok(json_response(objects, StatusCode::OK))
+}
+
+// POST /api/v1/classes/{class_id}/objects/find-by-data, find the objects in a class whose data
+// equals the document in the body, eg to avoid creating duplicates.
+#[post("/{class_id}/objects/find-by-data")]
+async fn find_objects_in_class_by_data(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    class_id: web::Path<HubuumClassID>,
+    document: web::Json<serde_json::Value>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let class = class_id.into_inner().instance(&pool).await?;
+
+    debug!(
+        message = "Finding objects in class by data",
+        user_id = user.id(),
+        class_id = class.id(),
+    );
+
+    let objects = user
+        .search_objects_by_data(&pool, class.id, &document)
+        .await?;
 
     Ok(json_response(objects, StatusCode::OK))
 }
@@ -422,6 +789,7 @@ async fn create_object_in_class(
     requestor: UserAccess,
     class_id: web::Path<HubuumClassID>,
     object_data: web::Json<NewHubuumObject>,
+    options: web::Query<ObjectWriteOptions>,
 ) -> Result<impl Responder, ApiError> {
     let user = requestor.user;
     let class_id = class_id.into_inner();
@@ -435,8 +803,21 @@ async fn create_object_in_class(
     );
 
     can!(&pool, user, [Permissions::CreateObject], class_id);
+    let namespace = check_object_namespace(&pool, &user, object_data.namespace_id).await?;
+
+    let skip_validation = allow_skip_validation(&pool, &user, &options).await?;
+    if !skip_validation {
+        class_id
+            .instance(&pool)
+            .await?
+            .validate_data(&object_data.data)?;
+    }
+    limit_writes!(&pool, class_id, namespace);
 
     let object = object_data.save(&pool).await?;
+    if skip_validation {
+        audit_skipped_validation(&pool, &user, &object, "create").await?;
+    }
 
     Ok(json_response_created(
         &object,
@@ -444,7 +825,332 @@ async fn create_object_in_class(
     ))
 }
 
+// POST /api/v1/classes/{class_id}/batch, create several objects in a class.
+//
+// By default the batch is atomic and either all objects are created (201) or none are. With
+// `?atomic=false` each object is created on its own, and the result of each is reported in a
+// 207 Multi-Status response.
+#[post("/{class_id}/batch")]
+async fn create_objects_in_class(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    class_id: web::Path<HubuumClassID>,
+    objects: web::Json<Vec<NewHubuumObject>>,
+    options: web::Query<BatchOptions>,
+    write_options: web::Query<ObjectWriteOptions>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let class_id = class_id.into_inner();
+    let objects = objects.into_inner();
+
+    debug!(
+        message = "Creating objects in class",
+        user_id = user.id(),
+        class_id = class_id.id(),
+        count = objects.len(),
+        atomic = options.atomic,
+    );
+
+    can!(&pool, user, [Permissions::CreateObject], class_id);
+
+    let class = class_id.instance(&pool).await?;
+    let skip_validation = allow_skip_validation(&pool, &user, &write_options).await?;
+
+    let check_class = |object: &NewHubuumObject| {
+        if object.hubuum_class_id != class_id.id() {
+            return Err(ApiError::BadRequest(format!(
+                "Object '{}' is of class {}, not {}",
+                object.name,
+                object.hubuum_class_id,
+                class_id.id()
+            )));
+        }
+        if !skip_validation {
+            class.validate_data(&object.data)?;
+        }
+        Ok(())
+    };
+
+    if options.atomic {
+        for (index, object) in objects.iter().enumerate() {
+            check_class(object).map_err(|e| batch_element_error(index, e))?;
+            check_object_namespace(&pool, &user, object.namespace_id)
+                .await
+                .map_err(|e| batch_element_error(index, e))?;
+        }
+        limit_object_writes(&pool, &class, &objects).await?;
+
+        let created = NewHubuumObject::save_all(&pool, &objects).await?;
+        if skip_validation {
+            for object in &created {
+                audit_skipped_validation(&pool, &user, object, "create").await?;
+            }
+        }
+        return Ok(json_response(created, StatusCode::CREATED));
+    }
+
+    let mut results = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        let result = async {
+            check_class(object)?;
+            check_object_namespace(&pool, &user, object.namespace_id).await?;
+            limit_object_writes(&pool, &class, std::slice::from_ref(object)).await?;
+            object.save(&pool).await
+        }
+        .await;
+
+        if let (Ok(created), true) = (&result, skip_validation) {
+            audit_skipped_validation(&pool, &user, created, "create").await?;
+        }
+
+        results.push(match result {
+            Ok(created) => BatchItemResult::success(index, StatusCode::CREATED, created.id),
+            Err(e) => BatchItemResult::failure(index, &e),
+        });
+    }
+
+    Ok(json_response(results, StatusCode::MULTI_STATUS))
+}
+
+// PATCH /api/v1/classes/{class_id}/batch, update several objects in a class.
+//
+// Each element is an object ID with the changes to make, as for PATCH on the object itself. By
+// default the batch is atomic and either all objects are updated (200) or none are. With
+// `?atomic=false` each object is updated on its own, and the result of each is reported in a
+// 207 Multi-Status response.
+#[patch("/{class_id}/batch")]
+async fn update_objects_in_class(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    class_id: web::Path<HubuumClassID>,
+    updates: web::Json<Vec<BatchUpdateHubuumObject>>,
+    options: web::Query<BatchOptions>,
+    write_options: web::Query<ObjectWriteOptions>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let class_id = class_id.into_inner();
+    let updates = updates.into_inner();
+
+    debug!(
+        message = "Updating objects in class",
+        user_id = user.id(),
+        class_id = class_id.id(),
+        count = updates.len(),
+        atomic = options.atomic,
+    );
+
+    let skip_validation = allow_skip_validation(&pool, &user, &write_options).await?;
+
+    if options.atomic {
+        let mut checked = Vec::with_capacity(updates.len());
+        for (index, update) in updates.iter().enumerate() {
+            let object = check_batch_update(&pool, &user, &class_id, update, skip_validation)
+                .await
+                .map_err(|e| batch_element_error(index, e))?;
+            checked.push(object);
+        }
+        limit_batch_writes(&pool, checked.iter().map(|(object, _)| object.namespace_id)).await?;
+
+        let updated = UpdateHubuumObject::update_all(&pool, &updates).await?;
+        for (object, (_, validates)) in updated.iter().zip(&checked) {
+            if *validates && skip_validation {
+                audit_skipped_validation(&pool, &user, object, "update").await?;
+            }
+        }
+        return Ok(json_response(updated, StatusCode::OK));
+    }
+
+    let mut results = Vec::with_capacity(updates.len());
+    for (index, update) in updates.iter().enumerate() {
+        let result = async {
+            let (object, validates) =
+                check_batch_update(&pool, &user, &class_id, update, skip_validation).await?;
+            limit_batch_writes(&pool, [object.namespace_id]).await?;
+            let object = update.update.update(&pool, object.id).await?;
+            Ok::<_, ApiError>((object, validates))
+        }
+        .await;
+
+        if let Ok((updated, true)) = &result {
+            if skip_validation {
+                audit_skipped_validation(&pool, &user, updated, "update").await?;
+            }
+        }
+
+        results.push(match result {
+            Ok((updated, _)) => BatchItemResult::success(index, StatusCode::OK, updated.id),
+            Err(e) => BatchItemResult::failure(index, &e),
+        });
+    }
+
+    Ok(json_response(results, StatusCode::MULTI_STATUS))
+}
+
+// Check an element of a batch update like PATCH on the object itself would.
+//
+// Returns the object as it is before the update, and whether the update is validated.
+async fn check_batch_update(
+    pool: &DbPool,
+    user: &User,
+    class_id: &HubuumClassID,
+    update: &BatchUpdateHubuumObject,
+    skip_validation: bool,
+) -> Result<(HubuumObject, bool), ApiError> {
+    let object = HubuumObjectID(update.id).instance(pool).await?;
+    check_if_object_in_class(pool, class_id, &object).await?;
+    can!(pool, user, [Permissions::UpdateObject], object);
+    let validates =
+        check_object_update(pool, user, &object, &update.update, skip_validation).await?;
+    Ok((object, validates))
+}
+
+// DELETE /api/v1/classes/{class_id}/batch, delete several objects in a class.
+//
+// The body is a list of object IDs. By default the batch is atomic and either all objects are
+// deleted (204) or none are. With `?atomic=false` each object is deleted on its own, and the
+// result of each is reported in a 207 Multi-Status response.
+#[delete("/{class_id}/batch")]
+async fn delete_objects_in_class(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    class_id: web::Path<HubuumClassID>,
+    object_ids: web::Json<Vec<i32>>,
+    options: web::Query<BatchOptions>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let class_id = class_id.into_inner();
+    let object_ids = object_ids.into_inner();
+
+    debug!(
+        message = "Deleting objects in class",
+        user_id = user.id(),
+        class_id = class_id.id(),
+        count = object_ids.len(),
+        atomic = options.atomic,
+    );
+
+    if options.atomic {
+        let mut namespace_ids = Vec::with_capacity(object_ids.len());
+        for (index, object_id) in object_ids.iter().enumerate() {
+            let object = check_batch_delete(&pool, &user, &class_id, *object_id)
+                .await
+                .map_err(|e| batch_element_error(index, e))?;
+            namespace_ids.push(object.namespace_id);
+        }
+        limit_batch_writes(&pool, namespace_ids).await?;
+
+        HubuumObject::delete_all(&pool, &object_ids).await?;
+        return Ok(json_response((), StatusCode::NO_CONTENT));
+    }
+
+    let mut results = Vec::with_capacity(object_ids.len());
+    for (index, object_id) in object_ids.iter().enumerate() {
+        let result = async {
+            let object = check_batch_delete(&pool, &user, &class_id, *object_id).await?;
+            limit_batch_writes(&pool, [object.namespace_id]).await?;
+            object.delete(&pool).await
+        }
+        .await;
+
+        results.push(match result {
+            Ok(()) => BatchItemResult::success(index, StatusCode::NO_CONTENT, *object_id),
+            Err(e) => BatchItemResult::failure(index, &e),
+        });
+    }
+
+    Ok(json_response(results, StatusCode::MULTI_STATUS))
+}
+
+// Check an element of a batch delete, returning the object to delete.
+async fn check_batch_delete(
+    pool: &DbPool,
+    user: &User,
+    class_id: &HubuumClassID,
+    object_id: i32,
+) -> Result<HubuumObject, ApiError> {
+    let object = HubuumObjectID(object_id).instance(pool).await?;
+    check_if_object_in_class(pool, class_id, &object).await?;
+    can!(pool, user, [Permissions::DeleteObject], object);
+    Ok(object)
+}
+
+// PUT /api/v1/classes/{class_id}/objects/by-external-id/{external_id}, create or update an
+// object by its external ID. Creating requires CreateObject, updating requires UpdateObject.
+#[put("/{class_id}/objects/by-external-id/{external_id}")]
+async fn upsert_object_by_external_id(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    paths: web::Path<(HubuumClassID, String)>,
+    object_data: web::Json<UpsertHubuumObject>,
+    options: web::Query<ObjectWriteOptions>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let (class_id, external_id) = paths.into_inner();
+    let object_data = object_data.into_inner();
+
+    debug!(
+        message = "Upserting object in class by external ID",
+        user_id = user.id(),
+        class_id = class_id.id(),
+        namespace_id = object_data.namespace_id,
+        external_id = external_id
+    );
+
+    let class = class_id.instance(&pool).await?;
+    let namespace = NamespaceID(object_data.namespace_id);
+    let existing =
+        HubuumObject::by_external_id(&pool, object_data.namespace_id, &external_id).await?;
+
+    // The permissions are checked before anything is said about the existing object, and
+    // creating checks the namespace like POST on the class does.
+    match &existing {
+        Some(object) => {
+            can!(&pool, user, [Permissions::UpdateObject], object);
+            if object.hubuum_class_id != class.id {
+                return Err(ApiError::Conflict(format!(
+                    "Object with external ID '{}' exists in class {}, not class {}",
+                    external_id, object.hubuum_class_id, class.id
+                )));
+            }
+        }
+        None => {
+            can!(&pool, user, [Permissions::CreateObject], class);
+            check_object_namespace(&pool, &user, object_data.namespace_id).await?;
+        }
+    }
+
+    let skip_validation = allow_skip_validation(&pool, &user, &options).await?;
+    if !skip_validation {
+        class.validate_data(&object_data.data)?;
+    }
+    limit_writes!(&pool, namespace);
+
+    let (object, created) = object_data
+        .upsert(
+            &pool,
+            class.id,
+            &external_id,
+            existing.as_ref().map(|o| o.id),
+        )
+        .await?;
+
+    if skip_validation {
+        audit_skipped_validation(&pool, &user, &object, "upsert").await?;
+    }
+
+    if created {
+        Ok(json_response_created(
+            &object,
+            &format!("/api/v1/classes/{}/{}", class.id, object.id),
+        ))
+    } else {
+        Ok(json_response(object, StatusCode::OK))
+    }
+}
+
+#[routes]
 #[get("/{class_id}/{object_id}")]
+#[head("/{class_id}/{object_id}")]
 async fn get_object_in_class(
     pool: web::Data<DbPool>,
     requestor: UserAccess,
@@ -460,12 +1166,14 @@ async fn get_object_in_class(
         object_id = object_id.id()
     );
 
-    // Can you read objects in a class you can't read? Hm.
-    // let class = class_id.instance(&pool).await?;
-    // check_permissions!(class.namespace_id, pool, user, Permissions::ReadClass);
-
+    // Reading an object requires ReadObject on its namespace and ReadClass on the namespace of
+    // its class, see docs/permissions.md.
     let object = object_id.instance(&pool).await?;
+    let class = HubuumClassID(object.hubuum_class_id)
+        .instance(&pool)
+        .await?;
     can!(&pool, user, [Permissions::ReadObject], object);
+    can!(&pool, user, [Permissions::ReadClass], class);
 
     Ok(json_response(object, StatusCode::OK))
 }
@@ -476,6 +1184,7 @@ async fn patch_object_in_class(
     requestor: UserAccess,
     paths: web::Path<(HubuumClassID, HubuumObjectID)>,
     object_data: web::Json<UpdateHubuumObject>,
+    options: web::Query<ObjectWriteOptions>,
 ) -> Result<impl Responder, ApiError> {
     let user = requestor.user;
     let (class_id, object_id) = paths.into_inner();
@@ -489,17 +1198,198 @@ async fn patch_object_in_class(
     );
 
     let object = object_id.instance(&pool).await?;
+    check_if_object_in_class(&pool, &class_id, &object).await?;
     can!(&pool, user, [Permissions::UpdateObject], object);
+    let skip_validation = allow_skip_validation(&pool, &user, &options).await?;
+    let validates =
+        check_object_update(&pool, &user, &object, &object_data, skip_validation).await?;
+    limit_writes!(&pool, object);
 
     let object = object_data.update(&pool, object.id).await?;
+    if validates && skip_validation {
+        audit_skipped_validation(&pool, &user, &object, "update").await?;
+    }
     Ok(json_response(object, StatusCode::OK))
 }
 
+// Check the parts of an object update that go beyond the UpdateObject permission on the object.
+//
+// Moving an object to another class requires that we may create objects in the namespace of the
+// target class, and that the data validates against the schema of the target class. Object
+// relations are tied to the class relations of the current class, so objects that take part in
+// relations can not be moved.
+//
+// Returns whether the update is validated, ie whether it changes the data or the class, so
+// skipping the validation can be audited.
+async fn check_object_update(
+    pool: &DbPool,
+    user: &User,
+    object: &HubuumObject,
+    object_data: &UpdateHubuumObject,
+    skip_validation: bool,
+) -> Result<bool, ApiError> {
+    let mut new_class = None;
+    if let Some(new_class_id) = object_data.hubuum_class_id {
+        if new_class_id != object.hubuum_class_id {
+            let target = HubuumClassID(new_class_id).instance(pool).await?;
+            can!(pool, user, [Permissions::CreateObject], target);
+
+            if object.has_relations(pool).await? {
+                return Err(ApiError::Conflict(format!(
+                    "Object {} has relations and can not be moved to class {}",
+                    object.id, target.id
+                )));
+            }
+
+            info!(
+                message = "Moving object to new class",
+                user_id = user.id(),
+                object_id = object.id,
+                from_class_id = object.hubuum_class_id,
+                to_class_id = target.id
+            );
+            new_class = Some(target);
+        }
+    }
+
+    // The data is validated whenever it changes, or when the object moves to another class.
+    let validates = object_data.data.is_some() || new_class.is_some();
+    if validates && !skip_validation {
+        let class = match new_class {
+            Some(class) => class,
+            None => HubuumClassID(object.hubuum_class_id).instance(pool).await?,
+        };
+        class.validate_data(object_data.data.as_ref().unwrap_or(&object.data))?;
+    }
+
+    Ok(validates)
+}
+
+// Check that the namespace a new object is to be created in exists, and that the user may
+// create objects there. The class of the object may live in another namespace, so this is in
+// addition to the check against the class.
+async fn check_object_namespace(
+    pool: &DbPool,
+    user: &User,
+    namespace_id: i32,
+) -> Result<NamespaceID, ApiError> {
+    let namespace = match NamespaceID(namespace_id).instance(pool).await {
+        Ok(namespace) => namespace,
+        Err(ApiError::NotFound(_)) => {
+            return Err(ApiError::BadRequest(format!(
+                "Namespace {} does not exist",
+                namespace_id
+            )))
+        }
+        Err(e) => return Err(e),
+    };
+
+    can!(pool, user, [Permissions::CreateObject], namespace);
+    Ok(NamespaceID(namespace.id))
+}
+
+// Count creating objects in a class against the write rate limits. Each object is a write to
+// its own namespace and to the namespace of the class, as if it was created on its own.
+async fn limit_object_writes(
+    pool: &DbPool,
+    class: &HubuumClass,
+    objects: &[NewHubuumObject],
+) -> Result<(), ApiError> {
+    let namespace_ids = objects.iter().flat_map(|object| {
+        std::iter::once(class.namespace_id)
+            .chain((object.namespace_id != class.namespace_id).then_some(object.namespace_id))
+    });
+    limit_batch_writes(pool, namespace_ids).await
+}
+
+// Count a batch of writes against the write rate limits, one write per namespace ID given. All
+// the writes to a namespace are either counted or refused together.
+async fn limit_batch_writes(
+    pool: &DbPool,
+    namespace_ids: impl IntoIterator<Item = i32>,
+) -> Result<(), ApiError> {
+    use crate::utilities::rate_limit::check_namespace_writes;
+    use std::collections::BTreeMap;
+
+    let mut writes: BTreeMap<i32, u32> = BTreeMap::new();
+    for namespace_id in namespace_ids {
+        *writes.entry(namespace_id).or_default() += 1;
+    }
+
+    for (namespace_id, count) in writes {
+        check_namespace_writes(pool, namespace_id, count).await?;
+    }
+    Ok(())
+}
+
+// Name the failing element of an atomic batch in errors about the element itself.
+fn batch_element_error(index: usize, error: ApiError) -> ApiError {
+    match error {
+        ApiError::BadRequest(e) => {
+            ApiError::BadRequest(format!("Element {} is invalid: {}", index, e))
+        }
+        ApiError::ValidationError(e) => {
+            ApiError::ValidationError(format!("Element {} is invalid: {}", index, e))
+        }
+        ApiError::NotFound(e) => ApiError::NotFound(format!("Element {}: {}", index, e)),
+        ApiError::Conflict(e) => ApiError::Conflict(format!("Element {}: {}", index, e)),
+        e => e,
+    }
+}
+
+// Check whether a write may skip schema validation, see `ObjectWriteOptions`. Only admins may,
+// and every write that does is logged and audited with `audit_skipped_validation`.
+async fn allow_skip_validation(
+    pool: &DbPool,
+    user: &User,
+    options: &ObjectWriteOptions,
+) -> Result<bool, ApiError> {
+    if !options.skip_validation {
+        return Ok(false);
+    }
+
+    if !user.has_admin_access(pool).await {
+        return Err(ApiError::Forbidden(
+            "Only admins may skip schema validation".to_string(),
+        ));
+    }
+
+    Ok(true)
+}
+
+// Record a write that skipped schema validation in the validation audit trail.
+async fn audit_skipped_validation(
+    pool: &DbPool,
+    user: &User,
+    object: &HubuumObject,
+    action: &str,
+) -> Result<(), ApiError> {
+    warn!(
+        message = "Object written without schema validation",
+        user_id = user.id(),
+        object_id = object.id,
+        class_id = object.hubuum_class_id,
+        action = action
+    );
+
+    NewValidationAudit {
+        object_id: object.id,
+        class_id: object.hubuum_class_id,
+        user_id: user.id(),
+        action: action.to_string(),
+    }
+    .save(pool)
+    .await
+}
+
+// DELETE /api/v1/classes/{class_id}/{object_id}. With If-Unmodified-Since, the object is only
+// deleted if it has not been updated after the given date.
 #[delete("/{class_id}/{object_id}")]
 async fn delete_object_in_class(
     pool: web::Data<DbPool>,
     requestor: UserAccess,
     paths: web::Path<(HubuumClassID, HubuumObjectID)>,
+    if_unmodified_since: Option<web::Header<IfUnmodifiedSince>>,
 ) -> Result<impl Responder, ApiError> {
     let user = requestor.user;
     let (class_id, object_id) = paths.into_inner();
@@ -513,6 +1403,8 @@ async fn delete_object_in_class(
 
     let object = object_id.instance(&pool).await?;
     can!(&pool, user, [Permissions::DeleteObject], object);
+    check_unmodified_since(if_unmodified_since.as_deref(), object.updated_at)?;
+    limit_writes!(&pool, object);
 
     object.delete(&pool).await?;
     Ok(json_response((), StatusCode::NO_CONTENT))
@@ -587,7 +1479,10 @@ async fn get_object_relation_from_class_and_objects(
         .object_relation(&pool, &from_class, &to_object)
         .await
     {
-        Ok(relation) => Ok(json_response(relation, StatusCode::OK)),
+        Ok(relation) => Ok(json_response(
+            HubuumObjectRelationWithDirection::new(relation, from_object.id()),
+            StatusCode::OK,
+        )),
         Err(_) => Err(ApiError::NotFound(format!(
             "Object {} of class {} is not related to object {}",
             from_object.id(),
@@ -627,6 +1522,7 @@ async fn delete_object_relation(
         to_class,
         to_object
     );
+    limit_writes!(&pool, from_object, to_object);
 
     let relation = from_class.direct_relation_to(&pool, &to_class).await?;
 
@@ -686,6 +1582,7 @@ async fn create_object_relation(
         from_class,
         to_class
     );
+    limit_writes!(&pool, from_object, to_object);
 
     let is_related = from_class.direct_relation_to(&pool, &to_class).await?;
 
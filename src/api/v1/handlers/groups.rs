@@ -2,6 +2,7 @@ use crate::db::{with_connection, DbPool};
 use crate::errors::ApiError;
 use crate::extractors::{AdminAccess, UserAccess};
 use crate::models::group::{Group, GroupID, NewGroup, UpdateGroup};
+use crate::models::user::DeleteOptions;
 use crate::models::user_group::UserGroup;
 use crate::utilities::response::{json_response, json_response_created};
 use actix_web::{delete, get, http::StatusCode, patch, post, routes, web, Responder};
@@ -86,18 +87,30 @@ pub async fn update_group(
     Ok(json_response(updated, StatusCode::OK))
 }
 
+// DELETE /api/v1/iam/groups/{group_id}, delete a group. With `?dry_run=true`, report what
+// would be deleted along with the group instead.
 #[delete("/{group_id}")]
 pub async fn delete_group(
     pool: web::Data<DbPool>,
     group_id: web::Path<GroupID>,
+    options: web::Query<DeleteOptions>,
     requestor: AdminAccess,
 ) -> Result<impl Responder, ApiError> {
     debug!(
         message = "Group delete requested",
         target = group_id.0,
-        requestor = requestor.user.id
+        requestor = requestor.user.id,
+        dry_run = options.dry_run
     );
 
+    if options.dry_run {
+        let group = group_id.group(&pool).await?;
+        return Ok(json_response(
+            group.deletion_impact(&pool).await?,
+            StatusCode::OK,
+        ));
+    }
+
     group_id.delete(&pool).await?;
     Ok(json_response(json!({}), StatusCode::NO_CONTENT))
 }
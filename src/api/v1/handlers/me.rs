@@ -0,0 +1,20 @@
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::extractors::UserAccess;
+use crate::models::traits::GroupAccessors;
+use crate::utilities::response::json_response;
+use actix_web::{get, http::StatusCode, web, Responder};
+use tracing::debug;
+
+// GET /api/v1/me/groups, list the groups of the authenticated user.
+#[get("/groups")]
+pub async fn get_my_groups(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    debug!(message = "Own groups requested", requestor = user.id);
+
+    let groups = user.groups(&pool).await?;
+    Ok(json_response(groups, StatusCode::OK))
+}
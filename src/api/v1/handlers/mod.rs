@@ -1,7 +1,11 @@
+pub mod admin;
 pub mod classes;
 pub mod groups;
+pub mod me;
 pub mod namespaces;
+pub mod objects;
 pub mod relations;
+pub mod schemas;
 pub mod users;
 
 use tracing::debug;
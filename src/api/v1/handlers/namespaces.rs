@@ -2,11 +2,12 @@ use crate::db::DbPool;
 use crate::errors::ApiError;
 use crate::extractors::{AdminAccess, UserAccess};
 use crate::models::{
-    GroupID, NamespaceID, NewNamespaceWithAssignee, Permissions, PermissionsList, UpdateNamespace,
-    UserID,
+    ClassOrObject, GroupID, Namespace, NamespaceID, NamespacePatch, NamespaceSearchOptions,
+    NamespaceTransfer, NewNamespaceWithAssignee, Permissions, PermissionsList,
+    RevokePermissionOptions, User, UserID,
 };
 
-use crate::models::search::parse_query_parameter;
+use crate::models::search::{parse_query_parameter, FilterField, ParsedQueryParam, SearchOperator};
 
 use crate::utilities::response::{json_response, json_response_created};
 use actix_web::{delete, get, http::StatusCode, patch, post, routes, web, HttpRequest, Responder};
@@ -17,7 +18,7 @@ use crate::can;
 
 use crate::db::traits::UserPermissions;
 use crate::traits::{
-    CanDelete, CanSave, CanUpdate, NamespaceAccessors, PermissionController, Search, SelfAccessors,
+    CanDelete, CanSave, NamespaceAccessors, PermissionController, Search, SelfAccessors,
 };
 
 #[routes]
@@ -92,29 +93,180 @@ pub async fn get_namespace(
     Ok(json_response(namespace, StatusCode::OK))
 }
 
+// GET /api/v1/namespaces/{namespace_id}/classes, list the classes in a namespace.
+//
+// This is a class search restricted to the namespace, so the usual search filters apply.
+// Namespaces the user can't read are reported as not found.
+#[get("/{namespace_id}/classes")]
+pub async fn get_namespace_classes(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    namespace_id: web::Path<NamespaceID>,
+    req: HttpRequest,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let query_string = req.query_string();
+
+    debug!(
+        message = "Namespace classes requested",
+        requestor = user.username,
+        namespace_id = namespace_id.id(),
+        query = query_string
+    );
+
+    let namespace = readable_namespace(&pool, &user, &namespace_id).await?;
+
+    let mut params = parse_query_parameter(query_string)?;
+    params.push(ParsedQueryParam {
+        field: FilterField::Namespaces,
+        operator: SearchOperator::Equals { is_negated: false },
+        value: namespace.id.to_string(),
+    });
+
+    let classes = user.search_classes(&pool, params).await?;
+
+    Ok(json_response(classes, StatusCode::OK))
+}
+
+// GET /api/v1/namespaces/{namespace_id}/search?q=..., search the classes and objects in a
+// namespace by name.
+//
+// The classes and objects whose name contains the text, case-insensitively, are returned as one
+// list tagged with their kind, classes first and each sorted by ID. Namespaces the user can't
+// read are reported as not found.
+#[get("/{namespace_id}/search")]
+pub async fn search_namespace(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    namespace_id: web::Path<NamespaceID>,
+    options: web::Query<NamespaceSearchOptions>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let text = options.into_inner().q;
+
+    debug!(
+        message = "Namespace search requested",
+        requestor = user.username,
+        namespace_id = namespace_id.id(),
+        q = text
+    );
+
+    if text.trim().is_empty() {
+        return Err(ApiError::BadRequest(
+            "The search text (q) can not be empty".to_string(),
+        ));
+    }
+
+    let namespace = readable_namespace(&pool, &user, &namespace_id).await?;
+
+    let params = vec![
+        ParsedQueryParam {
+            field: FilterField::Namespaces,
+            operator: SearchOperator::Equals { is_negated: false },
+            value: namespace.id.to_string(),
+        },
+        ParsedQueryParam {
+            field: FilterField::Name,
+            operator: SearchOperator::IContains { is_negated: false },
+            value: text,
+        },
+    ];
+
+    let (mut classes, mut objects) = futures::future::try_join(
+        user.search_classes(&pool, params.clone()),
+        user.search_objects(&pool, params),
+    )
+    .await?;
+    classes.sort_by_key(|class| class.id);
+    objects.sort_by_key(|object| object.id);
+
+    let results: Vec<ClassOrObject> = classes
+        .into_iter()
+        .map(ClassOrObject::Class)
+        .chain(objects.into_iter().map(ClassOrObject::Object))
+        .collect();
+
+    Ok(json_response(results, StatusCode::OK))
+}
+
+/// Look up a namespace the user may read, reporting namespaces they can't read as not found.
+async fn readable_namespace(
+    pool: &DbPool,
+    user: &User,
+    namespace_id: &NamespaceID,
+) -> Result<Namespace, ApiError> {
+    let namespace = namespace_id.instance(pool).await?;
+
+    match user
+        .can(pool, [Permissions::ReadCollection], [namespace.clone()])
+        .await
+    {
+        Ok(()) => Ok(namespace),
+        Err(ApiError::Forbidden(_)) => Err(ApiError::NotFound(format!(
+            "Namespace {} not found",
+            namespace.id
+        ))),
+        Err(e) => Err(e),
+    }
+}
+
+/// Update a namespace, along with its settings
+///
+/// The name and description require `UpdateCollection`, while the settings (`object_quota`,
+/// `default_class_permissions` and `attachment_size_limit`) require `DelegateCollection`. The
+/// `write_rate_limit` setting is admin only, as the limit protects the instance rather than the
+/// namespace. A setting given as `null` is cleared. The body may look like:
+/// ```json
+/// {
+///   "description": "Hosts in Oslo",
+///   "object_quota": 1000,
+///   "attachment_size_limit": null
+/// }
+/// ```
 #[patch("/{namespace_id}")]
 pub async fn update_namespace(
     pool: web::Data<DbPool>,
     requestor: UserAccess,
     namespace_id: web::Path<NamespaceID>,
-    update_data: web::Json<UpdateNamespace>,
+    update_data: web::Json<NamespacePatch>,
 ) -> Result<impl Responder, ApiError> {
+    let patch = update_data.into_inner();
+
     debug!(
         message = "Namespace update requested",
         requestor = requestor.user.username,
-        namespace_id = namespace_id.id()
+        namespace_id = namespace_id.id(),
+        settings = ?patch.settings
     );
 
     let namespace = namespace_id.instance(&pool).await?;
 
-    can!(
-        &pool,
-        requestor.user,
-        [Permissions::UpdateCollection],
-        namespace
-    );
+    let updates_namespace = patch.namespace.name.is_some() || patch.namespace.description.is_some();
+    if updates_namespace || patch.settings.is_empty() {
+        can!(
+            &pool,
+            requestor.user,
+            [Permissions::UpdateCollection],
+            namespace
+        );
+    }
+
+    if patch.settings.changes_delegated() {
+        can!(
+            &pool,
+            requestor.user,
+            [Permissions::DelegateCollection],
+            namespace
+        );
+    }
+
+    if patch.settings.write_rate_limit.is_some() && !requestor.user.has_admin_access(&pool).await {
+        return Err(ApiError::Forbidden(
+            "Only admins may set the write rate limit of a namespace".to_string(),
+        ));
+    }
 
-    let updated_namespace = update_data.into_inner().update(&pool, namespace.id).await?;
+    let updated_namespace = namespace.patch(&pool, &patch).await?;
     Ok(json_response(updated_namespace, StatusCode::ACCEPTED))
 }
 
@@ -142,6 +294,49 @@ pub async fn delete_namespace(
     Ok(json_response(json!(()), StatusCode::NO_CONTENT))
 }
 
+/// Transfer ownership of a namespace to another group
+/// The new owner is granted all permissions, and unless `keep_access` is set the previous
+/// owner loses theirs. The body should look like:
+/// ```json
+/// {
+///   "from_group_id": 1,
+///   "to_group_id": 2,
+///   "keep_access": false
+/// }
+/// ```
+#[post("/{namespace_id}/transfer")]
+pub async fn transfer_namespace(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    namespace_id: web::Path<NamespaceID>,
+    transfer: web::Json<NamespaceTransfer>,
+) -> Result<impl Responder, ApiError> {
+    let transfer = transfer.into_inner();
+
+    info!(
+        message = "Namespace transfer requested",
+        requestor = requestor.user.username,
+        namespace_id = namespace_id.id(),
+        from_group_id = transfer.from_group_id,
+        to_group_id = transfer.to_group_id,
+        keep_access = transfer.keep_access
+    );
+
+    let namespace = namespace_id.instance(&pool).await?;
+    can!(
+        &pool,
+        requestor.user,
+        [Permissions::DelegateCollection],
+        namespace
+    );
+
+    GroupID(transfer.to_group_id).instance(&pool).await?;
+
+    let permissions = namespace.transfer(&pool, &transfer).await?;
+
+    Ok(json_response(permissions, StatusCode::OK))
+}
+
 /// List all groups who have permissions for a namespace
 #[get("/{namespace_id}/permissions")]
 pub async fn get_namespace_permissions(
@@ -257,6 +452,7 @@ pub async fn revoke_namespace_group_permissions(
     pool: web::Data<DbPool>,
     requestor: UserAccess,
     params: web::Path<(NamespaceID, GroupID)>,
+    options: web::Query<RevokePermissionOptions>,
 ) -> Result<impl Responder, ApiError> {
     let (namespace_id, group_id) = params.into_inner();
 
@@ -264,7 +460,8 @@ pub async fn revoke_namespace_group_permissions(
         message = "Namespace group permissions revoke requested",
         requestor = requestor.user.username,
         namespace_id = namespace_id.id(),
-        group_id = group_id.id()
+        group_id = group_id.id(),
+        reason = options.reason
     );
 
     let namespace = namespace_id.instance(&pool).await?;
@@ -275,7 +472,9 @@ pub async fn revoke_namespace_group_permissions(
         namespace
     );
 
-    namespace.revoke_all(&pool, group_id.id()).await?;
+    namespace
+        .revoke_all(&pool, group_id.id(), options.reason.as_deref())
+        .await?;
 
     Ok(json_response((), StatusCode::NO_CONTENT))
 }
@@ -352,6 +551,7 @@ pub async fn revoke_namespace_group_permission(
     pool: web::Data<DbPool>,
     requestor: UserAccess,
     params: web::Path<(NamespaceID, GroupID, Permissions)>,
+    options: web::Query<RevokePermissionOptions>,
 ) -> Result<impl Responder, ApiError> {
     let (namespace_id, group_id, permission) = params.into_inner();
 
@@ -360,7 +560,8 @@ pub async fn revoke_namespace_group_permission(
         requestor = requestor.user.username,
         namespace_id = namespace_id.id(),
         group_id = group_id.id(),
-        permission = ?permission
+        permission = ?permission,
+        reason = options.reason
     );
 
     let namespace = namespace_id.instance(&pool).await?;
@@ -372,7 +573,7 @@ pub async fn revoke_namespace_group_permission(
     );
 
     namespace
-        .revoke(&pool, group_id.id(), PermissionsList::new([permission]))
+        .revoke_one(&pool, group_id.id(), permission, options.reason.as_deref())
         .await?;
 
     Ok(json_response((), StatusCode::NO_CONTENT))
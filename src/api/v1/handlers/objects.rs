@@ -0,0 +1,199 @@
+use actix_web::{get, http::StatusCode, post, routes, web, HttpRequest, Responder};
+use serde_json::json;
+
+use tracing::debug;
+
+use crate::can;
+use crate::db::traits::UserPermissions;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::extractors::UserAccess;
+use crate::models::search::{
+    parse_query_body, parse_query_parameter, FilterField, ParsedQueryParam, QueryParamsExt,
+    SearchFilter, SearchOperator,
+};
+use crate::models::{HubuumClassID, HubuumObjectID, NamespaceID, Permissions};
+use crate::traits::{NamespaceAccessors, Search, SelfAccessors};
+use crate::utilities::json_diff;
+use crate::utilities::response::json_response;
+
+// GET /api/v1/objects/, list the objects the user can read across all classes.
+//
+// With `?diagnostics=true` (admins only), the objects are wrapped together with the classes
+// requested via `classes=` or `class_id=` that were filtered out because they are not readable,
+// to tell "no matching objects" apart from "no access".
+#[routes]
+#[get("")]
+#[get("/")]
+async fn get_objects(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    req: HttpRequest,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let query_string = req.query_string();
+
+    let mut params = parse_query_parameter(query_string)?;
+    let mut diagnostics = false;
+    for param in params
+        .iter()
+        .filter(|p| p.field == FilterField::Diagnostics)
+    {
+        if param.operator != (SearchOperator::Equals { is_negated: false }) {
+            return Err(ApiError::OperatorMismatch(
+                "Field 'diagnostics' only supports the equals operator".to_string(),
+            ));
+        }
+        diagnostics = param.value_as_boolean()?;
+    }
+    let shape = params.shape()?;
+    let expansion = params.relation_expansion()?;
+    params.retain(|p| {
+        !matches!(
+            p.field,
+            FilterField::Diagnostics | FilterField::Shape | FilterField::Expand
+        )
+    });
+
+    debug!(
+        message = "Listing objects",
+        user_id = user.id(),
+        query = query_string,
+        diagnostics = diagnostics
+    );
+
+    if diagnostics && !user.has_admin_access(&pool).await {
+        return Err(ApiError::Forbidden(
+            "Search diagnostics are only available to admins".to_string(),
+        ));
+    }
+
+    let requested_classes = params.requested_classes()?;
+    let objects = user.search_objects(&pool, params).await?;
+    let objects = match expansion {
+        Some(expansion) => shape.apply(
+            user.expand_object_relations(&pool, objects, expansion)
+                .await?,
+        )?,
+        None => shape.apply(objects)?,
+    };
+
+    if !diagnostics {
+        return Ok(json_response(objects, StatusCode::OK));
+    }
+
+    let unreadable_classes = user.unreadable_classes(&pool, &requested_classes).await?;
+    Ok(json_response(
+        json!({
+            "objects": objects,
+            "diagnostics": {
+                "unreadable_classes": unreadable_classes,
+            },
+        }),
+        StatusCode::OK,
+    ))
+}
+
+// GET /api/v1/objects/by-external-id/{external_id}, fetch a single object by its external ID.
+//
+// External IDs are only guaranteed to be unique within a namespace (unless configured to be
+// globally unique), so if the ID matches objects in more than one namespace the caller has to
+// narrow the search with `?namespaces=`.
+#[get("/by-external-id/{external_id}")]
+async fn get_object_by_external_id(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    external_id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let external_id = external_id.into_inner();
+    let query_string = req.query_string();
+
+    let mut params = parse_query_parameter(query_string)?;
+    params.push(ParsedQueryParam {
+        field: FilterField::ExternalId,
+        operator: SearchOperator::Equals { is_negated: false },
+        value: external_id.clone(),
+    });
+
+    debug!(
+        message = "Getting object by external ID",
+        user_id = user.id(),
+        external_id = external_id,
+        query = query_string
+    );
+
+    let mut objects = user.search_objects(&pool, params).await?;
+
+    match objects.len() {
+        0 => Err(ApiError::NotFound(format!(
+            "No object with external ID '{}' found",
+            external_id
+        ))),
+        1 => Ok(json_response(objects.remove(0), StatusCode::OK)),
+        n => Err(ApiError::Conflict(format!(
+            "External ID '{}' matches {} objects, narrow the search with ?namespaces=",
+            external_id, n
+        ))),
+    }
+}
+
+// POST /api/v1/objects/search, search objects with a JSON filter body.
+//
+// This is the counterpart to the query string searches for filters that are too long for a URL or
+// that need nested AND/OR groups. See `SearchFilter` for the format of the body.
+#[post("/search")]
+async fn search_objects(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    filter: web::Json<SearchFilter>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let filter = parse_query_body(&filter.into_inner())?;
+
+    debug!(
+        message = "Searching objects by filter",
+        user_id = user.id(),
+        filter = ?filter
+    );
+
+    let objects = user.search_objects_by_filter(&pool, &filter).await?;
+    Ok(json_response(objects, StatusCode::OK))
+}
+
+// GET /api/v1/objects/{object_id}/diff/{other_object_id}, the differences between the data of
+// two objects, as the keys added, removed and changed going from the first to the second.
+//
+// Reading the objects requires ReadObject on their namespaces and ReadClass on the namespaces of
+// their classes, the same as getting them one by one.
+#[get("/{object_id}/diff/{other_object_id}")]
+async fn diff_objects(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    paths: web::Path<(HubuumObjectID, HubuumObjectID)>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let (object_id, other_object_id) = paths.into_inner();
+
+    debug!(
+        message = "Diffing objects",
+        user_id = user.id(),
+        object_id = object_id.id(),
+        other_object_id = other_object_id.id()
+    );
+
+    let object = object_id.instance(&pool).await?;
+    let other = other_object_id.instance(&pool).await?;
+    let class = HubuumClassID(object.hubuum_class_id)
+        .instance(&pool)
+        .await?;
+    let other_class = HubuumClassID(other.hubuum_class_id).instance(&pool).await?;
+    can!(&pool, user, [Permissions::ReadObject], object, other);
+    can!(&pool, user, [Permissions::ReadClass], class, other_class);
+
+    Ok(json_response(
+        json_diff::diff(&object.data, &other.data),
+        StatusCode::OK,
+    ))
+}
@@ -4,17 +4,33 @@ use crate::extractors::UserAccess;
 use crate::models::search::parse_query_parameter;
 use crate::models::{HubuumClassRelationID, HubuumObjectRelationID, NamespaceID, Permissions};
 
-use crate::can;
 use crate::db::traits::UserPermissions;
 use crate::traits::{CanDelete, CanSave, NamespaceAccessors, SelfAccessors};
+use crate::{can, limit_writes};
 
 use crate::utilities::response::json_response;
-use actix_web::delete;
+use actix_web::{delete, get};
 use tracing::debug;
 
 use crate::traits::Search;
 
-use actix_web::{get, http::StatusCode, routes, web, HttpRequest, Responder};
+use actix_web::{http::StatusCode, routes, web, HttpRequest, Responder};
+
+#[get("search")]
+async fn search_relations(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    req: HttpRequest,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let params = parse_query_parameter(req.query_string())?;
+
+    debug!(message = "Searching relations", user_id = user.id());
+
+    let relations = user.search_relations(&pool, params).await?;
+
+    Ok(json_response(relations, StatusCode::OK))
+}
 
 #[routes]
 #[get("classes")]
@@ -39,7 +55,9 @@ async fn get_class_relations(
     Ok(json_response(classes, StatusCode::OK))
 }
 
+#[routes]
 #[get("classes/{relation_id}")]
+#[head("classes/{relation_id}")]
 async fn get_class_relation(
     pool: web::Data<DbPool>,
     requestor: UserAccess,
@@ -94,6 +112,7 @@ async fn create_class_relation(
         namespaces.0,
         namespaces.1
     );
+    limit_writes!(&pool, namespaces.0, namespaces.1);
 
     let relation = relation.save(&pool).await?;
 
@@ -123,6 +142,7 @@ async fn delete_class_relation(
         namespaces.0,
         namespaces.1
     );
+    limit_writes!(&pool, namespaces.0, namespaces.1);
 
     relation_id.delete(&pool).await?;
 
@@ -152,7 +172,9 @@ async fn get_object_relations(
     Ok(json_response(object_relations, StatusCode::OK))
 }
 
+#[routes]
 #[get("objects/{relation_id}")]
+#[head("objects/{relation_id}")]
 async fn get_object_relation(
     pool: web::Data<DbPool>,
     requestor: UserAccess,
@@ -207,6 +229,7 @@ async fn create_object_relation(
         namespaces.0,
         namespaces.1
     );
+    limit_writes!(&pool, namespaces.0, namespaces.1);
 
     let relation = relation.save(&pool).await?;
 
@@ -236,6 +259,7 @@ async fn delete_object_relation(
         namespaces.0,
         namespaces.1
     );
+    limit_writes!(&pool, namespaces.0, namespaces.1);
 
     relation_id.delete(&pool).await?;
 
@@ -0,0 +1,148 @@
+use actix_web::{
+    http::header::{self, Accept, ContentDisposition, DispositionParam, DispositionType},
+    http::StatusCode,
+    routes, web, HttpRequest, Responder,
+};
+use std::collections::HashMap;
+use tracing::debug;
+
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::extractors::UserAccess;
+use crate::models::search::{parse_query_parameter, FilterField, ParsedQueryParam};
+use crate::models::HubuumClassSchema;
+use crate::traits::{Search, SelfAccessors};
+use crate::utilities::response::{json_response_with_header, private_response};
+use crate::utilities::zip::zip_stored;
+
+/// How many schemas an export returns when no `limit` is given.
+pub const SCHEMA_EXPORT_DEFAULT_LIMIT: i64 = 100;
+
+/// The largest `limit` a schema export accepts.
+pub const SCHEMA_EXPORT_MAX_LIMIT: i64 = 1000;
+
+// GET /api/v1/schemas, the JSON schemas of all classes the user may read.
+//
+// The classes are searched like GET /api/v1/classes, so the same filters and limit/offset
+// paging apply, except that pages are capped at SCHEMA_EXPORT_MAX_LIMIT schemas and default to
+// SCHEMA_EXPORT_DEFAULT_LIMIT. With `Accept: application/zip` the schemas are returned as a zip archive of
+// `{namespace}/{class}.schema.json` files instead, leaving out classes without a schema.
+#[routes]
+#[get("")]
+#[get("/")]
+async fn get_schemas(
+    pool: web::Data<DbPool>,
+    requestor: UserAccess,
+    req: HttpRequest,
+    accept: Option<web::Header<Accept>>,
+) -> Result<impl Responder, ApiError> {
+    let user = requestor.user;
+    let query_string = req.query_string();
+    let mut params = parse_query_parameter(query_string)?;
+
+    let mut has_limit = false;
+    for param in params.iter().filter(|p| p.field == FilterField::Limit) {
+        let limit = param.value_as_count()?;
+        if limit > SCHEMA_EXPORT_MAX_LIMIT {
+            return Err(ApiError::BadRequest(format!(
+                "limit can be at most {}, got {}",
+                SCHEMA_EXPORT_MAX_LIMIT, limit
+            )));
+        }
+        has_limit = true;
+    }
+    if !has_limit {
+        params.push(ParsedQueryParam::new(
+            "limit",
+            None,
+            &SCHEMA_EXPORT_DEFAULT_LIMIT.to_string(),
+        )?);
+    }
+
+    let as_zip = accept
+        .map(|accept| accept.into_inner().preference() == "application/zip")
+        .unwrap_or(false);
+
+    debug!(
+        message = "Exporting class schemas",
+        user_id = user.id(),
+        query = query_string,
+        zip = as_zip
+    );
+
+    let schemas: Vec<HubuumClassSchema> = user
+        .search_classes(&pool, params)
+        .await?
+        .into_iter()
+        .map(HubuumClassSchema::from)
+        .collect();
+
+    // The response depends on Accept, which caches need to know.
+    if !as_zip {
+        let headers = HashMap::from([(header::VARY.as_str(), "Accept")]);
+        return Ok(json_response_with_header(
+            schemas,
+            StatusCode::OK,
+            Some(headers),
+        ));
+    }
+
+    let files = schemas
+        .iter()
+        .filter_map(|schema| {
+            let json_schema = schema.json_schema.as_ref()?;
+            let name = format!(
+                "{}/{}.schema.json",
+                schema_file_name(&schema.namespace),
+                schema_file_name(&schema.name)
+            );
+            Some(serde_json::to_vec_pretty(json_schema).map(|content| (name, content)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(private_response(StatusCode::OK)
+        .content_type("application/zip")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename("schemas.zip".to_string())],
+        })
+        .append_header((header::VARY, "Accept"))
+        .body(zip_stored(&files)?))
+}
+
+/// Make a namespace or class name safe to use as a path component in an archive.
+///
+/// Separators and `%` are percent-encoded, as are the dots of `.` and `..`, so distinct names
+/// always give distinct file names.
+fn schema_file_name(name: &str) -> String {
+    if name == "." || name == ".." {
+        return name.replace('.', "%2E");
+    }
+
+    let mut file_name = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '%' => file_name.push_str("%25"),
+            '/' => file_name.push_str("%2F"),
+            '\\' => file_name.push_str("%5C"),
+            c => file_name.push(c),
+        }
+    }
+    file_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_file_name() {
+        assert_eq!(schema_file_name("servers"), "servers");
+        assert_eq!(schema_file_name("a/b\\c"), "a%2Fb%5Cc");
+        assert_eq!(schema_file_name("a_b"), "a_b");
+        assert_eq!(schema_file_name("a%2Fb"), "a%252Fb");
+        assert_eq!(schema_file_name(".."), "%2E%2E");
+        assert_eq!(schema_file_name("."), "%2E");
+        assert_eq!(schema_file_name("v1.2"), "v1.2");
+    }
+}
@@ -2,11 +2,12 @@ use crate::db::DbPool;
 use crate::errors::ApiError;
 use crate::extractors::{AdminAccess, AdminOrSelfAccess, UserAccess};
 use crate::models::search::parse_query_parameter;
-use crate::models::user::{NewUser, UpdateUser, UserID};
+use crate::models::token::NewTokenRequest;
+use crate::models::user::{DeleteOptions, NewUser, PasswordReset, UpdateUser, UserID};
 use crate::utilities::response::{json_response, json_response_created};
-use actix_web::{delete, get, http::StatusCode, patch, routes, web, HttpRequest, Responder};
+use actix_web::{delete, get, http::StatusCode, patch, post, routes, web, HttpRequest, Responder};
 use serde_json::json;
-use tracing::debug;
+use tracing::{debug, info};
 
 #[routes]
 #[get("")]
@@ -71,7 +72,37 @@ pub async fn get_user_tokens(
     Ok(json_response(valid_tokens, StatusCode::OK))
 }
 
+#[post("/{user_id}/tokens")]
+pub async fn issue_user_token(
+    pool: web::Data<DbPool>,
+    user_id: web::Path<UserID>,
+    token_request: web::Json<NewTokenRequest>,
+    requestor: AdminAccess,
+) -> Result<impl Responder, ApiError> {
+    let user = user_id.into_inner().user(&pool).await?;
+    let token_request = token_request.into_inner();
+
+    let token = user
+        .issue_token(&pool, requestor.user.id, &token_request)
+        .await?;
+
+    info!(
+        message = "Token issued on behalf of user",
+        target = user.id,
+        requestor = requestor.user.id,
+        name = token_request.name,
+        ttl_hours = token_request.ttl_hours
+    );
+
+    Ok(json_response_created(
+        &token,
+        format!("/api/v1/iam/users/{}/tokens", user.id).as_str(),
+    ))
+}
+
+#[routes]
 #[get("/{user_id}")]
+#[head("/{user_id}")]
 pub async fn get_user(
     pool: web::Data<DbPool>,
     user_id: web::Path<UserID>,
@@ -128,18 +159,55 @@ pub async fn update_user(
     Ok(json_response(user, StatusCode::OK))
 }
 
+// POST /api/v1/iam/users/{user_id}/reset-password, set a new password for a user, eg one who
+// is locked out. Optionally revokes all their tokens.
+#[post("/{user_id}/reset-password")]
+pub async fn reset_user_password(
+    pool: web::Data<DbPool>,
+    user_id: web::Path<UserID>,
+    reset: web::Json<PasswordReset>,
+    requestor: AdminAccess,
+) -> Result<impl Responder, ApiError> {
+    let user = user_id.into_inner().user(&pool).await?;
+    let reset = reset.into_inner();
+
+    user.reset_password(&pool, requestor.user.id, &reset)
+        .await?;
+
+    info!(
+        message = "Password reset on behalf of user",
+        target = user.id,
+        requestor = requestor.user.id,
+        tokens_revoked = reset.revoke_tokens
+    );
+
+    Ok(json_response((), StatusCode::NO_CONTENT))
+}
+
+// DELETE /api/v1/iam/users/{user_id}, delete a user. With `?dry_run=true`, report what would
+// be deleted along with the user instead.
 #[delete("/{user_id}")]
 pub async fn delete_user(
     pool: web::Data<DbPool>,
     user_id: web::Path<UserID>,
+    options: web::Query<DeleteOptions>,
     requestor: AdminAccess,
 ) -> Result<impl Responder, ApiError> {
     debug!(
         message = "User delete requested",
         target = user_id.0,
-        requestor = requestor.user.id
+        requestor = requestor.user.id,
+        dry_run = options.dry_run
     );
 
+    if options.dry_run {
+        let user = user_id.user(&pool).await?;
+        return Ok(json_response(
+            user.deletion_impact(&pool).await?,
+            StatusCode::OK,
+        ));
+    }
+
     let delete_result = user_id.delete(&pool).await;
 
     match delete_result {
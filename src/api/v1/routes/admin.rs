@@ -0,0 +1,6 @@
+use actix_web::web;
+
+use crate::api::v1::handlers::admin;
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(admin::get_tokens).service(admin::get_stats);
+}
@@ -3,18 +3,31 @@ use actix_web::web;
 use crate::api::v1::handlers::classes;
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(classes::get_classes)
+        .service(classes::get_class_names)
         .service(classes::get_class)
+        .service(classes::get_class_schema)
         .service(classes::create_class)
         .service(classes::update_class)
         .service(classes::delete_class)
+        .service(classes::clone_class)
         .service(classes::get_class_permissions)
+        .service(classes::get_class_namespaces)
+        .service(classes::get_class_indexes)
+        .service(classes::create_class_index)
+        .service(classes::delete_class_index)
         .service(classes::get_object_in_class)
         .service(classes::get_objects_in_class)
+        .service(classes::find_objects_in_class_by_data)
+        .service(classes::update_objects_in_class)
+        .service(classes::delete_objects_in_class)
         .service(classes::patch_object_in_class)
         .service(classes::delete_object_in_class)
         .service(classes::create_object_in_class)
+        .service(classes::create_objects_in_class)
+        .service(classes::upsert_object_by_external_id)
         .service(classes::get_class_relations)
         .service(classes::get_class_relations_transitive)
+        .service(classes::get_class_relation_paths)
         .service(classes::delete_class_relation)
         .service(classes::create_class_relation)
         .service(classes::list_related_objects)
@@ -1,15 +1,23 @@
 use actix_web::web;
 
+pub mod admin;
 pub mod classes;
 pub mod groups;
+pub mod me;
 pub mod namespaces;
+pub mod objects;
 pub mod relations;
+pub mod schemas;
 pub mod users;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(web::scope("/iam/users").configure(users::config))
         .service(web::scope("/iam/groups").configure(groups::config))
+        .service(web::scope("/me").configure(me::config))
         .service(web::scope("/namespaces").configure(namespaces::config))
         .service(web::scope("/classes").configure(classes::config))
-        .service(web::scope("/relations").configure(relations::config));
+        .service(web::scope("/objects").configure(objects::config))
+        .service(web::scope("/relations").configure(relations::config))
+        .service(web::scope("/schemas").configure(schemas::config))
+        .service(web::scope("/admin").configure(admin::config));
 }
@@ -5,8 +5,11 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(namespaces_handlers::get_namespaces)
         .service(namespaces_handlers::create_namespace)
         .service(namespaces_handlers::get_namespace)
+        .service(namespaces_handlers::get_namespace_classes)
+        .service(namespaces_handlers::search_namespace)
         .service(namespaces_handlers::update_namespace)
         .service(namespaces_handlers::delete_namespace)
+        .service(namespaces_handlers::transfer_namespace)
         .service(namespaces_handlers::get_namespace_permissions)
         .service(namespaces_handlers::get_namespace_group_permissions)
         .service(namespaces_handlers::get_namespace_group_permission)
@@ -0,0 +1,9 @@
+use actix_web::web;
+
+use crate::api::v1::handlers::objects as objects_handlers;
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(objects_handlers::get_objects)
+        .service(objects_handlers::get_object_by_external_id)
+        .service(objects_handlers::search_objects)
+        .service(objects_handlers::diff_objects);
+}
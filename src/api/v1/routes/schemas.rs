@@ -0,0 +1,6 @@
+use actix_web::web;
+
+use crate::api::v1::handlers::schemas;
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(schemas::get_schemas);
+}
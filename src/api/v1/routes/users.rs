@@ -6,6 +6,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         .service(users::get_users)
         .service(users::get_user)
         .service(users::get_user_tokens)
+        .service(users::issue_user_token)
+        .service(users::get_user_groups)
+        .service(users::reset_user_password)
         .service(users::update_user)
         .service(users::delete_user);
 }
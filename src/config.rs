@@ -5,6 +5,24 @@ use serde::{Deserialize, Serialize};
 #[cfg(not(test))]
 use tokio::sync::Mutex;
 
+/// The scope in which object external IDs must be unique.
+#[derive(clap::ValueEnum, Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum ExternalIdScope {
+    /// External IDs are unique within a namespace (enforced by the database)
+    Namespace,
+    /// External IDs are unique across all namespaces
+    Global,
+}
+
+/// The format of the log output.
+#[derive(clap::ValueEnum, Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// One JSON object per line, with the fields of each event
+    Json,
+    /// Human-readable lines
+    Text,
+}
+
 #[derive(Parser, Debug, Deserialize, Serialize, Clone)]
 pub struct AppConfig {
     /// IP address to bind to
@@ -19,6 +37,10 @@ pub struct AppConfig {
     #[clap(long, env = "HUBUUM_LOG_LEVEL", default_value = "info")]
     pub log_level: String,
 
+    /// Logging format
+    #[clap(long, env = "HUBUUM_LOG_FORMAT", value_enum, default_value = "json")]
+    pub log_format: LogFormat,
+
     /// Database URL
     #[clap(
         long,
@@ -34,6 +56,72 @@ pub struct AppConfig {
     /// Number of DB connections in the pool
     #[clap(long, env = "HUBUUM_DB_POOL_SIZE", default_value_t = 10)]
     pub db_pool_size: u32,
+
+    /// Scope in which object external IDs must be unique
+    #[clap(
+        long,
+        env = "HUBUUM_OBJECT_EXTERNAL_ID_SCOPE",
+        value_enum,
+        default_value = "namespace"
+    )]
+    pub object_external_id_scope: ExternalIdScope,
+
+    /// Emit one access log entry per request
+    #[clap(
+        long,
+        env = "HUBUUM_ACCESS_LOG",
+        default_value_t = false,
+        action = clap::ArgAction::Set
+    )]
+    pub access_log: bool,
+
+    /// Report the seconds left on the token in an X-Token-Expires-In response header
+    #[clap(
+        long,
+        env = "HUBUUM_TOKEN_EXPIRY_HEADER",
+        default_value_t = false,
+        action = clap::ArgAction::Set
+    )]
+    pub token_expiry_header: bool,
+
+    /// Maximum number of parameters in a search query string
+    #[clap(long, env = "HUBUUM_MAX_QUERY_PARAMS", default_value_t = 100)]
+    pub max_query_params: usize,
+
+    /// Use the estimates from pg_class for the large tables in the admin stats
+    #[clap(
+        long,
+        env = "HUBUUM_APPROXIMATE_STATS",
+        default_value_t = false,
+        action = clap::ArgAction::Set
+    )]
+    pub approximate_stats: bool,
+
+    /// Default maximum number of writes per minute in a namespace, 0 for no limit
+    #[clap(long, env = "HUBUUM_NAMESPACE_WRITE_RATE_LIMIT", default_value_t = 0)]
+    pub namespace_write_rate_limit: u32,
+
+    /// Require user emails to be unique, ignoring case
+    #[clap(
+        long,
+        env = "HUBUUM_REQUIRE_UNIQUE_EMAIL",
+        default_value_t = false,
+        action = clap::ArgAction::Set
+    )]
+    pub require_unique_email: bool,
+
+    /// Include the full error text in error responses, for development
+    #[clap(
+        long,
+        env = "HUBUUM_VERBOSE_ERRORS",
+        default_value_t = false,
+        action = clap::ArgAction::Set
+    )]
+    pub verbose_errors: bool,
+
+    /// Maximum number of concurrent requests per user, 0 for no limit
+    #[clap(long, env = "HUBUUM_USER_CONCURRENCY_LIMIT", default_value_t = 0)]
+    pub user_concurrency_limit: usize,
 }
 
 #[cfg(not(test))]
@@ -59,6 +147,8 @@ pub async fn get_config() -> AppConfig {
             .parse()
             .unwrap_or(8080),
         log_level: env_or_default("HUBUUM_LOG_LEVEL", "debug"),
+        log_format: clap::ValueEnum::from_str(&env_or_default("HUBUUM_LOG_FORMAT", "json"), true)
+            .unwrap_or(LogFormat::Json),
         database_url: env_or_default("HUBUUM_DATABASE_URL", "postgres://test"),
         actix_workers: env_or_default("HUBUUM_ACTIX_WORKERS", "2")
             .parse()
@@ -66,5 +156,34 @@ pub async fn get_config() -> AppConfig {
         db_pool_size: env_or_default("HUBUUM_DB_POOL_SIZE", "2")
             .parse()
             .unwrap_or(5),
+        object_external_id_scope: clap::ValueEnum::from_str(
+            &env_or_default("HUBUUM_OBJECT_EXTERNAL_ID_SCOPE", "namespace"),
+            true,
+        )
+        .unwrap_or(ExternalIdScope::Namespace),
+        access_log: env_or_default("HUBUUM_ACCESS_LOG", "false")
+            .parse()
+            .unwrap_or(false),
+        token_expiry_header: env_or_default("HUBUUM_TOKEN_EXPIRY_HEADER", "false")
+            .parse()
+            .unwrap_or(false),
+        max_query_params: env_or_default("HUBUUM_MAX_QUERY_PARAMS", "100")
+            .parse()
+            .unwrap_or(100),
+        approximate_stats: env_or_default("HUBUUM_APPROXIMATE_STATS", "false")
+            .parse()
+            .unwrap_or(false),
+        namespace_write_rate_limit: env_or_default("HUBUUM_NAMESPACE_WRITE_RATE_LIMIT", "0")
+            .parse()
+            .unwrap_or(0),
+        require_unique_email: env_or_default("HUBUUM_REQUIRE_UNIQUE_EMAIL", "false")
+            .parse()
+            .unwrap_or(false),
+        verbose_errors: env_or_default("HUBUUM_VERBOSE_ERRORS", "false")
+            .parse()
+            .unwrap_or(false),
+        user_concurrency_limit: env_or_default("HUBUUM_USER_CONCURRENCY_LIMIT", "0")
+            .parse()
+            .unwrap_or(0),
     }
 }
@@ -1,6 +1,9 @@
+pub mod scope;
 pub mod traits;
 
+use diesel::connection::SimpleConnection;
 use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::CustomizeConnection;
 use diesel::r2d2::Pool;
 use diesel::PgConnection;
 
@@ -44,6 +47,21 @@ where
     Err(ApiError::from(last_error.unwrap()))
 }
 
+/// Sets the session time zone of every pooled connection to UTC.
+///
+/// Our timestamp columns are `TIMESTAMP` (without time zone) filled in by `now()`, so they are
+/// stored in the session time zone. Forcing UTC means that they can be read back with
+/// `.and_utc()` whatever the time zone of the database server is.
+#[derive(Debug)]
+struct UtcSession;
+
+impl CustomizeConnection<PgConnection, diesel::r2d2::Error> for UtcSession {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.batch_execute("SET TIME ZONE 'UTC'")
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
 pub fn init_pool(database_url: &str, max_size: u32) -> DbPool {
     let database_url_components = DatabaseUrlComponents::new(database_url);
 
@@ -65,6 +83,7 @@ pub fn init_pool(database_url: &str, max_size: u32) -> DbPool {
 
     Pool::builder()
         .max_size(max_size)
+        .connection_customizer(Box::new(UtcSession))
         .build(manager)
         .expect("Failed to create pool")
 }
@@ -80,4 +99,25 @@ mod tests {
         let pool = super::init_pool(&database_url, pool_size);
         assert_eq!(pool.max_size(), pool_size);
     }
+
+    #[test]
+    fn test_init_pool_uses_utc() {
+        use diesel::prelude::*;
+        use diesel::sql_types::Text;
+
+        #[derive(QueryableByName)]
+        struct TimeZone {
+            #[diesel(sql_type = Text)]
+            #[diesel(column_name = "TimeZone")]
+            time_zone: String,
+        }
+
+        let database_url = get_config_sync().database_url.clone();
+        let pool = super::init_pool(&database_url, 1);
+        let mut conn = pool.get().unwrap();
+        let result = diesel::sql_query("SHOW TIME ZONE")
+            .get_result::<TimeZone>(&mut conn)
+            .unwrap();
+        assert_eq!(result.time_zone, "UTC");
+    }
 }
@@ -0,0 +1,52 @@
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use std::cell::Cell;
+use std::future::Future;
+
+use crate::errors::ApiError;
+use crate::schema::permissions;
+
+tokio::task_local! {
+    // The namespace the token of the current request is scoped to, set by the extractors.
+    static NAMESPACE_SCOPE: Cell<Option<i32>>;
+}
+
+/// Run a request with its own namespace scope, initially unscoped.
+///
+/// This is set up for every API request by `NamespaceScopeMiddleware`. Outside of it there is no
+/// scope, and recording a scoped token fails, see [`set_namespace_scope`].
+pub fn with_namespace_scope<F: Future>(fut: F) -> impl Future<Output = F::Output> {
+    NAMESPACE_SCOPE.scope(Cell::new(None), fut)
+}
+
+/// Record the namespace scope of the token used for the current request.
+///
+/// Fails if the request is not run with [`with_namespace_scope`] and the token is scoped, as
+/// the scope would otherwise be silently ignored.
+pub fn set_namespace_scope(namespace_id: Option<i32>) -> Result<(), ApiError> {
+    match NAMESPACE_SCOPE.try_with(|scope| scope.set(namespace_id)) {
+        Ok(()) => Ok(()),
+        Err(_) if namespace_id.is_none() => Ok(()),
+        Err(_) => Err(ApiError::InternalServerError(
+            "Namespace scope used outside of a scoped request".to_string(),
+        )),
+    }
+}
+
+/// The namespace the token of the current request is scoped to, if any.
+pub fn namespace_scope() -> Option<i32> {
+    NAMESPACE_SCOPE.try_with(|scope| scope.get()).ok().flatten()
+}
+
+/// Restrict a permissions query to the namespace scope of the current request.
+///
+/// Every lookup of the permissions of a user goes through this, so a scoped token never sees
+/// grants outside its namespace.
+pub fn scope_permissions(
+    query: permissions::BoxedQuery<'_, Pg>,
+) -> permissions::BoxedQuery<'_, Pg> {
+    match namespace_scope() {
+        Some(scope) => query.filter(permissions::namespace_id.eq(scope)),
+        None => query,
+    }
+}
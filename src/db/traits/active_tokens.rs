@@ -1,7 +1,7 @@
 use crate::db::traits::ActiveTokens;
 use crate::db::{with_connection, DbPool};
 use crate::errors::ApiError;
-use crate::models::{User, UserToken};
+use crate::models::{User, UserToken, TOKEN_LIFETIME_HOURS};
 use crate::traits::SelfAccessors;
 use diesel::prelude::*;
 use diesel::sql_types::Integer;
@@ -16,10 +16,10 @@ where
 }
 
 async fn active_tokens_by_user_id(user_id: i32, pool: &DbPool) -> Result<Vec<UserToken>, ApiError> {
-    let hours = 24; // FIXME: Make this configurable
+    let hours = TOKEN_LIFETIME_HOURS;
 
     with_connection(pool, |conn| {
-        Ok(diesel::sql_query("SELECT * FROM tokens WHERE user_id = $1 AND issued > (CURRENT_TIMESTAMP - ($2 || ' hours')::INTERVAL)")
+        Ok(diesel::sql_query("SELECT * FROM tokens WHERE user_id = $1 AND issued > (CURRENT_TIMESTAMP - (COALESCE(ttl_hours, $2) || ' hours')::INTERVAL)")
             .bind::<Integer, _>(user_id)
             .bind::<Integer, _>(hours)
             .load::<UserToken>(conn)
@@ -4,8 +4,154 @@ use crate::db::traits::GetClass;
 use crate::db::{with_connection, DbPool};
 use crate::errors::ApiError;
 use crate::models::{
-    HubuumClass, HubuumClassID, HubuumClassRelation, HubuumClassRelationID, NewHubuumClassRelation,
+    ClassIndex, CloneHubuumClass, HubuumClass, HubuumClassID, HubuumClassRelation,
+    HubuumClassRelationID, Namespace, NamespaceObjectCount, NewHubuumClass, NewHubuumClassRelation,
 };
+use crate::traits::SelfAccessors;
+
+/// Drop the expression indexes declared on a set of classes.
+///
+/// The declarations themselves are removed by the database when the classes are deleted, but the
+/// indexes are not, so this has to be called in the same transaction as the delete.
+pub(crate) fn drop_class_indexes(
+    conn: &mut PgConnection,
+    class_ids: &[i32],
+) -> Result<(), ApiError> {
+    use crate::schema::class_indexes::dsl::{class_indexes, hubuum_class_id};
+
+    let declared = class_indexes
+        .filter(hubuum_class_id.eq_any(class_ids))
+        .load::<ClassIndex>(conn)?;
+
+    for index in declared {
+        diesel::sql_query(format!("DROP INDEX IF EXISTS {}", index.index_name())).execute(conn)?;
+    }
+
+    Ok(())
+}
+
+impl HubuumClass {
+    /// Get the distinct namespaces that objects of this class live in, with object counts.
+    ///
+    /// Only namespaces in `namespace_ids` are considered, so the caller can limit the
+    /// result to namespaces the requestor may read. The result is ordered by namespace ID.
+    pub async fn object_namespaces(
+        &self,
+        pool: &DbPool,
+        namespace_ids: &[i32],
+    ) -> Result<Vec<NamespaceObjectCount>, ApiError> {
+        use crate::schema::hubuumobject::dsl as obj;
+        use crate::schema::namespaces::dsl as ns;
+        use diesel::dsl::count_star;
+
+        let result = with_connection(pool, |conn| {
+            obj::hubuumobject
+                .inner_join(ns::namespaces.on(ns::id.eq(obj::namespace_id)))
+                .filter(obj::hubuum_class_id.eq(self.id()))
+                .filter(obj::namespace_id.eq_any(namespace_ids))
+                .group_by(ns::id)
+                .select((ns::namespaces::all_columns(), count_star()))
+                .order_by(ns::id)
+                .load::<(Namespace, i64)>(conn)
+        })?;
+
+        Ok(result
+            .into_iter()
+            .map(|(namespace, object_count)| NamespaceObjectCount {
+                namespace,
+                object_count,
+            })
+            .collect())
+    }
+}
+
+impl HubuumClass {
+    /// Find the class with the given name, if any. Class names are globally unique.
+    pub async fn by_name(pool: &DbPool, class_name: &str) -> Result<Option<HubuumClass>, ApiError> {
+        use crate::schema::hubuumclass::dsl::{hubuumclass, name};
+
+        with_connection(pool, |conn| {
+            hubuumclass
+                .filter(name.eq(class_name))
+                .first::<HubuumClass>(conn)
+                .optional()
+        })
+    }
+}
+
+impl HubuumClass {
+    /// Get the indexed JSON paths declared on this class, ordered by ID.
+    pub async fn indexes(&self, pool: &DbPool) -> Result<Vec<ClassIndex>, ApiError> {
+        use crate::schema::class_indexes::dsl::{class_indexes, hubuum_class_id, id};
+
+        with_connection(pool, |conn| {
+            class_indexes
+                .filter(hubuum_class_id.eq(self.id))
+                .order_by(id)
+                .load::<ClassIndex>(conn)
+        })
+    }
+}
+
+impl HubuumClass {
+    /// Clone the class, see [`CloneHubuumClass`].
+    ///
+    /// Objects are not copied. The clone and its relations are created in one transaction.
+    pub async fn clone_class(
+        &self,
+        pool: &DbPool,
+        clone: &CloneHubuumClass,
+    ) -> Result<HubuumClass, ApiError> {
+        use crate::schema::hubuumclass::dsl::hubuumclass;
+        use crate::schema::hubuumclass_relation::dsl::{
+            from_hubuum_class_id, hubuumclass_relation, to_hubuum_class_id,
+        };
+
+        let new_class = NewHubuumClass {
+            name: clone.name.clone(),
+            namespace_id: clone.namespace_id.unwrap_or(self.namespace_id),
+            json_schema: self.json_schema.clone(),
+            validate_schema: Some(self.validate_schema),
+            description: self.description.clone(),
+        };
+
+        let mut conn = pool.get()?;
+        conn.transaction::<_, ApiError, _>(|conn| {
+            let cloned = diesel::insert_into(hubuumclass)
+                .values(&new_class)
+                .get_result::<HubuumClass>(conn)?;
+
+            if clone.include_relations {
+                let relations = hubuumclass_relation
+                    .filter(from_hubuum_class_id.eq(self.id))
+                    .or_filter(to_hubuum_class_id.eq(self.id))
+                    .load::<HubuumClassRelation>(conn)?;
+
+                let remap = |class_id: i32| {
+                    if class_id == self.id {
+                        cloned.id
+                    } else {
+                        class_id
+                    }
+                };
+
+                let new_relations = relations
+                    .iter()
+                    .map(|r| NewHubuumClassRelation {
+                        from_hubuum_class_id: remap(r.from_hubuum_class_id),
+                        to_hubuum_class_id: remap(r.to_hubuum_class_id),
+                    })
+                    .collect::<Vec<_>>();
+
+                diesel::insert_into(hubuumclass_relation)
+                    .values(&new_relations)
+                    .execute(conn)?;
+            }
+
+            Ok(cloned)
+        })
+    }
+}
 
 impl GetClass for HubuumClass {
     async fn class_from_backend(&self, pool: &DbPool) -> Result<HubuumClass, ApiError> {
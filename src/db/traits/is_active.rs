@@ -6,15 +6,22 @@ use crate::db::traits::Status;
 
 use crate::db::{with_connection, DbPool};
 use crate::errors::ApiError;
-use crate::models::{Token, UserToken};
+use crate::models::{Token, UserToken, TOKEN_LIFETIME_HOURS};
+
+/// How stale `last_used` may get before validating the token updates it.
+///
+/// Updating it on every request would turn every authenticated request into a write.
+const LAST_USED_RESOLUTION_SECONDS: i64 = 60;
 
 impl Status<UserToken> for Token {
+    /// A valid token is marked as used, so validating a token also updates its last use, at most
+    /// once every `LAST_USED_RESOLUTION_SECONDS`.
     async fn is_valid(&self, pool: &DbPool) -> Result<UserToken, ApiError> {
         let token = self.get_token();
-        let hours = 24; // FIXME: Make this configurable
+        let hours = TOKEN_LIFETIME_HOURS;
 
         let token_result = with_connection(pool, |conn| {
-            Ok(diesel::sql_query("SELECT * FROM tokens WHERE token = $1 AND issued > (CURRENT_TIMESTAMP - ($2 || ' hours')::INTERVAL)")
+            Ok(diesel::sql_query("SELECT * FROM tokens WHERE token = $1 AND issued > (CURRENT_TIMESTAMP - (COALESCE(ttl_hours, $2) || ' hours')::INTERVAL)")
                 .bind::<Text, _>(&token)
                 .bind::<Integer, _>(hours)
                 .load::<UserToken>(conn))
@@ -23,6 +30,9 @@ impl Status<UserToken> for Token {
         match token_result {
             Ok(token_list) => {
                 if let Some(token) = token_list.first() {
+                    if is_stale(token.last_used) {
+                        mark_used(pool, &token.token)?;
+                    }
                     Ok(token.clone())
                 } else {
                     warn!("Invalid token {}: Not found.", token);
@@ -36,3 +46,41 @@ impl Status<UserToken> for Token {
         }
     }
 }
+
+fn is_stale(last_used: Option<chrono::NaiveDateTime>) -> bool {
+    match last_used {
+        Some(last_used) => {
+            let age = chrono::Utc::now().naive_utc() - last_used;
+            age.num_seconds() >= LAST_USED_RESOLUTION_SECONDS
+        }
+        None => true,
+    }
+}
+
+fn mark_used(pool: &DbPool, token_value: &str) -> Result<(), ApiError> {
+    use crate::schema::tokens::dsl::{last_used, token, tokens};
+
+    with_connection(pool, |conn| {
+        diesel::update(tokens.filter(token.eq(token_value)))
+            .set(last_used.eq(diesel::dsl::now))
+            .execute(conn)
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_is_stale() {
+        let now = Utc::now().naive_utc();
+        assert!(is_stale(None));
+        assert!(!is_stale(Some(now)));
+        assert!(!is_stale(Some(now - Duration::seconds(10))));
+        assert!(is_stale(Some(
+            now - Duration::seconds(LAST_USED_RESOLUTION_SECONDS)
+        )));
+    }
+}
@@ -6,14 +6,16 @@ mod object;
 mod relations;
 mod user;
 
+pub(crate) use class::drop_class_indexes;
 #[allow(unused_imports)]
 pub use user::UserPermissions;
 
 use crate::errors::ApiError;
 use crate::models::search::{FilterField, ParsedQueryParam};
 use crate::models::{
-    HubuumClass, HubuumClassRelation, HubuumClassRelationTransitive, HubuumObject, HubuumObjectID,
-    HubuumObjectRelation, HubuumObjectTransitiveLink, Namespace, User, UserToken,
+    HubuumClass, HubuumClassRelation, HubuumClassRelationPath, HubuumClassRelationTransitive,
+    HubuumObject, HubuumObjectID, HubuumObjectRelation, HubuumObjectTransitiveLink, Namespace, User,
+    UserToken,
 };
 use crate::traits::{GroupAccessors, SelfAccessors};
 use crate::{date_search, numeric_search, string_search, trace_query};
@@ -75,6 +77,7 @@ where
     C2: SelfAccessors<HubuumClass> + Clone + Send + Sync,
 {
     /// Check if a relation exists between two classes.
+    #[allow(dead_code)]
     async fn relations_between(
         pool: &DbPool,
         from: &C1,
@@ -90,12 +93,20 @@ where
     Self: SelfAccessors<HubuumClass>,
 {
     /// Check if a relation exists between self and another class
+    #[allow(dead_code)]
     async fn relations_to(
         &self,
         pool: &DbPool,
         other: &C2,
     ) -> Result<Vec<HubuumClassRelationTransitive>, ApiError>;
 
+    /// Get the chains of relations connecting self to another class, shortest first
+    async fn paths_to(
+        &self,
+        pool: &DbPool,
+        other: &C2,
+    ) -> Result<Vec<HubuumClassRelationPath>, ApiError>;
+
     /// Check if a direct relation exists between self and another class
     async fn direct_relation_to(
         &self,
@@ -125,7 +136,6 @@ where
     }
 
     // We typically end up searching, so this interface is rarely used.
-    #[allow(dead_code)]
     async fn relations(&self, pool: &DbPool) -> Result<Vec<HubuumClassRelation>, ApiError> {
         use crate::schema::hubuumclass_relation::dsl::*;
         use diesel::prelude::*;
@@ -295,10 +305,10 @@ where
                     numeric_search!(base_query, param, operator, obj::namespace_id)
                 }
                 FilterField::Description => {
-                    string_search!(base_query, param, operator, obj::description)
+                    string_search!(base_query, query_params, param, operator, obj::description)
                 }
                 FilterField::Name => {
-                    string_search!(base_query, param, operator, obj::name)
+                    string_search!(base_query, query_params, param, operator, obj::name)
                 }
                 _ => {
                     return Err(ApiError::BadRequest(format!(
@@ -7,6 +7,44 @@ use crate::models::{
     HubuumObject, HubuumObjectRelation, HubuumObjectRelationID, NewHubuumObjectRelation,
 };
 
+impl HubuumObject {
+    /// Check if the object takes part in any object relations, in either direction.
+    pub async fn has_relations(&self, pool: &DbPool) -> Result<bool, ApiError> {
+        use crate::schema::hubuumobject_relation::dsl::{
+            from_hubuum_object_id, hubuumobject_relation, to_hubuum_object_id,
+        };
+        use diesel::dsl::exists;
+
+        with_connection(pool, |conn| {
+            diesel::select(exists(
+                hubuumobject_relation.filter(
+                    from_hubuum_object_id
+                        .eq(self.id)
+                        .or(to_hubuum_object_id.eq(self.id)),
+                ),
+            ))
+            .get_result::<bool>(conn)
+        })
+    }
+
+    /// Find the object with the given external ID in a namespace, if any.
+    pub async fn by_external_id(
+        pool: &DbPool,
+        namespace: i32,
+        eid: &str,
+    ) -> Result<Option<HubuumObject>, ApiError> {
+        use crate::schema::hubuumobject::dsl::{external_id, hubuumobject, namespace_id};
+
+        with_connection(pool, |conn| {
+            hubuumobject
+                .filter(namespace_id.eq(namespace))
+                .filter(external_id.eq(eid))
+                .first::<HubuumObject>(conn)
+                .optional()
+        })
+    }
+}
+
 impl GetObject<(HubuumObject, HubuumObject)> for HubuumObjectRelationID {
     async fn object_from_backend(
         &self,
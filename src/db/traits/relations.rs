@@ -3,8 +3,8 @@ use crate::db::traits::ClassRelation;
 use crate::db::{with_connection, DbPool};
 use crate::errors::ApiError;
 use crate::models::{
-    user_can_on_any, HubuumClass, HubuumClassRelation, HubuumClassRelationTransitive, HubuumObject,
-    HubuumObjectTransitiveLink, User,
+    user_can_on_any, HubuumClass, HubuumClassRelation, HubuumClassRelationPath,
+    HubuumClassRelationTransitive, HubuumObject, HubuumObjectTransitiveLink, User,
 };
 
 use crate::traits::{GroupAccessors, SelfAccessors};
@@ -41,6 +41,33 @@ where
         <C1 as Relations<C1, C2>>::relations_between(pool, self, other).await
     }
 
+    async fn paths_to(
+        &self,
+        pool: &DbPool,
+        other: &C2,
+    ) -> Result<Vec<HubuumClassRelationPath>, ApiError> {
+        // The closure is stored from the lowest class ID to the highest, so the paths are
+        // reversed when asked for the other way around.
+        let reverse = self.id() > other.id();
+        let mut paths: Vec<HubuumClassRelationPath> = fetch_relations(pool, self, other)
+            .await?
+            .into_iter()
+            .map(|relation| {
+                let mut path: Vec<i32> = relation.path.into_iter().flatten().collect();
+                if reverse {
+                    path.reverse();
+                }
+                HubuumClassRelationPath {
+                    depth: relation.depth,
+                    path,
+                }
+            })
+            .collect();
+
+        paths.sort_by(|a, b| a.depth.cmp(&b.depth).then_with(|| a.path.cmp(&b.path)));
+        Ok(paths)
+    }
+
     async fn direct_relation_to(
         &self,
         pool: &DbPool,
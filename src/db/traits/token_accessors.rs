@@ -1,6 +1,6 @@
 use crate::db::traits::TokenAccessors;
 use crate::errors::ApiError;
-use crate::models::{Token, User, UserToken};
+use crate::models::{Token, User, UserToken, TOKEN_LIFETIME_HOURS};
 use crate::traits::SelfAccessors;
 use diesel::prelude::*;
 use diesel::sql_types::{Integer, Text};
@@ -9,8 +9,8 @@ use tracing::warn;
 impl TokenAccessors for Token {
     async fn is_valid(&self, conn: &mut PgConnection) -> Result<UserToken, ApiError> {
         let token = self.get_token();
-        let hours = 24;
-        let token_result = diesel::sql_query("SELECT * FROM tokens WHERE token = $1 AND issued > (CURRENT_TIMESTAMP - ($2 || ' hours')::INTERVAL)")
+        let hours = TOKEN_LIFETIME_HOURS;
+        let token_result = diesel::sql_query("SELECT * FROM tokens WHERE token = $1 AND issued > (CURRENT_TIMESTAMP - (COALESCE(ttl_hours, $2) || ' hours')::INTERVAL)")
             .bind::<Text, _>(&token)
             .bind::<Integer, _>(hours)
             .load::<UserToken>(conn);
@@ -60,8 +60,8 @@ async fn active_tokens_by_user_id(
     user_id: i32,
     conn: &mut PgConnection,
 ) -> Result<Vec<UserToken>, ApiError> {
-    let hours = 24;
-    diesel::sql_query("SELECT * FROM tokens WHERE user_id = $1 AND issued > (CURRENT_TIMESTAMP - ($2 || ' hours')::INTERVAL)")
+    let hours = TOKEN_LIFETIME_HOURS;
+    diesel::sql_query("SELECT * FROM tokens WHERE user_id = $1 AND issued > (CURRENT_TIMESTAMP - (COALESCE(ttl_hours, $2) || ' hours')::INTERVAL)")
         .bind::<Integer, _>(user_id)
         .bind::<Integer, _>(hours)
         .load::<UserToken>(conn)
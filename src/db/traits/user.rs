@@ -10,6 +10,7 @@ use crate::traits::{GroupAccessors, NamespaceAccessors, SelfAccessors};
 use crate::db::{with_connection, DbPool};
 use crate::errors::ApiError;
 
+use crate::db::scope::scope_permissions;
 use crate::models::search::{FilterField, ParsedQueryParam};
 
 use crate::{date_search, numeric_search, string_search, trace_query};
@@ -64,7 +65,6 @@ pub trait UserPermissions: SelfAccessors<User> + GroupAccessors {
     /// ### Returns
     ///
     /// * Nothing if the user has the required permissions, or an ApiError::Forbidden if they do not.
-    
     async fn can<P, N, I>(
         &self,
         pool: &DbPool,
@@ -92,8 +92,7 @@ pub trait UserPermissions: SelfAccessors<User> + GroupAccessors {
             .try_collect()
             .await?;
 
-        let mut base_query = lookup_table
-            .into_boxed()
+        let mut base_query = scope_permissions(lookup_table.into_boxed())
             .filter(namespace_id_field.eq_any(&namespace_ids))
             .filter(group_id_field.eq_any(group_id_subquery));
     
@@ -137,12 +136,16 @@ impl User {
 
         let mut base_query = users.into_boxed();
 
-        for param in query_params {
+        for param in &query_params {
             let operator = param.operator.clone();
             match param.field {
                 FilterField::Id => numeric_search!(base_query, param, operator, id),
-                FilterField::Username => string_search!(base_query, param, operator, username),
-                FilterField::Email => string_search!(base_query, param, operator, email),
+                FilterField::Username => {
+                    string_search!(base_query, query_params, param, operator, username)
+                }
+                FilterField::Email => {
+                    string_search!(base_query, query_params, param, operator, email)
+                }
                 FilterField::CreatedAt => date_search!(base_query, param, operator, created_at),
                 FilterField::UpdatedAt => date_search!(base_query, param, operator, updated_at),
                 _ => {
@@ -234,7 +237,7 @@ mod tests {
                 .unwrap()];
         let groups = [create_test_group(&pool).await,
             create_test_group(&pool).await];
-        let users = vec![
+        let users = [
             create_user_with_params(&pool, &format!("test_user_can_u1_{}", suffix), "foo").await,
             create_user_with_params(&pool, &format!("test_user_can_u2_{}", suffix), "foo").await,
         ];
@@ -1,5 +1,6 @@
 use actix_web::{
-    error::JsonPayloadError, http::StatusCode, HttpRequest, HttpResponse, ResponseError,
+    error::JsonPayloadError, http::header, http::StatusCode, HttpRequest, HttpResponse,
+    ResponseError,
 };
 use diesel::r2d2::PoolError;
 use diesel::result::{DatabaseErrorKind, Error as DieselError};
@@ -7,8 +8,24 @@ use serde::Serialize;
 use serde_json::json;
 use std::fmt;
 use std::num::ParseIntError;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::utilities::response::private_response;
+
+/// The message returned for server errors unless verbose errors are enabled.
+pub const INTERNAL_ERROR_MESSAGE: &str = "An internal error occurred";
+
+static VERBOSE_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Set whether error responses include the full error text as `detail`.
+///
+/// This is set from `HUBUUM_VERBOSE_ERRORS` at startup.
+pub fn set_verbose_errors(verbose: bool) {
+    VERBOSE_ERRORS.store(verbose, Ordering::Relaxed);
+}
 
 #[derive(Debug, Serialize, PartialEq)]
 pub enum ApiError {
@@ -23,6 +40,10 @@ pub enum ApiError {
     BadRequest(String),
     OperatorMismatch(String),
     InvalidIntegerRange(String),
+    ValidationError(String),
+    PreconditionFailed(String),
+    /// Too many requests, with the number of seconds until the client may retry.
+    TooManyRequests(String, u64),
 }
 
 impl fmt::Display for ApiError {
@@ -39,39 +60,104 @@ impl fmt::Display for ApiError {
             ApiError::BadRequest(ref message) => write!(f, "{}", message),
             ApiError::OperatorMismatch(ref message) => write!(f, "{}", message),
             ApiError::InvalidIntegerRange(ref message) => write!(f, "{}", message),
+            ApiError::ValidationError(ref message) => write!(f, "{}", message),
+            ApiError::PreconditionFailed(ref message) => write!(f, "{}", message),
+            ApiError::TooManyRequests(ref message, _) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl ApiError {
+    /// A stable, machine readable code for the error, returned as `code` in error responses.
+    ///
+    /// Clients should branch on this rather than on the message. See docs/errors.md for the
+    /// catalog, and keep it in sync when adding variants.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::InternalServerError(_) => "internal_error",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::DatabaseError(_) => "database_error",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::DbConnectionError(_) => "database_unavailable",
+            ApiError::HashError(_) => "hash_error",
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::OperatorMismatch(_) => "operator_mismatch",
+            ApiError::InvalidIntegerRange(_) => "invalid_integer_range",
+            ApiError::ValidationError(_) => "schema_validation_failed",
+            ApiError::PreconditionFailed(_) => "precondition_failed",
+            ApiError::TooManyRequests(_, _) => "too_many_requests",
+        }
+    }
+
+    /// The human readable summary of the error, returned as `error` in error responses.
+    fn title(&self) -> &'static str {
+        match self {
+            ApiError::Unauthorized(_) => "Unauthorized",
+            ApiError::InternalServerError(_) => "Internal Server Error",
+            ApiError::Forbidden(_) => "Forbidden",
+            ApiError::DatabaseError(_) => "Database Error",
+            ApiError::Conflict(_) => "Conflict",
+            ApiError::NotFound(_) => "Not Found",
+            ApiError::DbConnectionError(_) => "Database Connection Error",
+            ApiError::HashError(_) => "Hash Error",
+            ApiError::BadRequest(_) => "Bad Request",
+            ApiError::OperatorMismatch(_) => "Operator Mismatch",
+            ApiError::InvalidIntegerRange(_) => "Invalid Integer Range",
+            ApiError::ValidationError(_) => "Bad Request",
+            ApiError::PreconditionFailed(_) => "Precondition Failed",
+            ApiError::TooManyRequests(_, _) => "Too Many Requests",
         }
     }
 }
 
+impl ApiError {
+    /// The JSON body of the error, as returned in error responses and per element in batch
+    /// results.
+    pub fn to_json(&self) -> serde_json::Value {
+        self.to_json_with(VERBOSE_ERRORS.load(Ordering::Relaxed))
+    }
+
+    /// The JSON body of the error, see [`set_verbose_errors`].
+    ///
+    /// The messages of server errors may hold internals such as database errors, so they are
+    /// replaced by [`INTERNAL_ERROR_MESSAGE`] and a `correlation_id`. The full error is logged
+    /// with the same ID, so it can be found from a report by the client. In verbose mode the
+    /// full text of any error is returned as `detail`.
+    fn to_json_with(&self, verbose: bool) -> serde_json::Value {
+        let mut body = json!({
+            "error": self.title(),
+            "code": self.code(),
+            "message": self.to_string(),
+        });
+
+        if self.status_code().is_server_error() {
+            let correlation_id = Uuid::new_v4().to_string();
+            error!(
+                message = "Server error",
+                correlation_id = %correlation_id,
+                code = self.code(),
+                error = %self
+            );
+            body["message"] = json!(INTERNAL_ERROR_MESSAGE);
+            body["correlation_id"] = json!(correlation_id);
+        }
+
+        if verbose {
+            body["detail"] = json!(self.to_string());
+        }
+        body
+    }
+}
+
 impl ResponseError for ApiError {
     fn error_response(&self) -> HttpResponse {
-        match self {
-            ApiError::Conflict(ref message) => {
-                HttpResponse::Conflict().json(json!({ "error": "Conflict", "message": message}))
-            }
-            ApiError::Forbidden(ref message) => {
-                HttpResponse::Forbidden().json(json!({ "error": "Forbidden", "message": message }))
-            }
-            ApiError::InternalServerError(ref message) => HttpResponse::InternalServerError()
-                .json(json!({ "error": "Internal Server Error", "message": message })),
-            ApiError::Unauthorized(ref message) => HttpResponse::Unauthorized()
-                .json(json!({ "error": "Unauthorized", "message": message })),
-            ApiError::DbConnectionError(ref message) => HttpResponse::InternalServerError()
-                .json(json!({ "error": "Database Connection Error", "message": message })),
-            ApiError::DatabaseError(ref message) => HttpResponse::InternalServerError()
-                .json(json!({ "error": "Database Error", "message": message })),
-            ApiError::HashError(ref message) => HttpResponse::InternalServerError()
-                .json(json!({ "error": "Hash Error", "message": message })),
-            ApiError::NotFound(ref message) => {
-                HttpResponse::NotFound().json(json!({ "error": "Not Found", "message": message }))
-            }
-            ApiError::BadRequest(ref message) => HttpResponse::BadRequest()
-                .json(json!({ "error": "Bad Request", "message": message })),
-            ApiError::OperatorMismatch(ref message) => HttpResponse::BadRequest()
-                .json(json!({ "error": "Operator Mismatch", "message": message })),
-            ApiError::InvalidIntegerRange(ref message) => HttpResponse::BadRequest()
-                .json(json!({ "error": "Invalid Integer Range", "message": message })),
+        let mut response = private_response(self.status_code());
+        if let ApiError::TooManyRequests(_, retry_after) = self {
+            response.insert_header((header::RETRY_AFTER, retry_after.to_string()));
         }
+        response.json(self.to_json())
     }
 
     fn status_code(&self) -> StatusCode {
@@ -87,6 +173,9 @@ impl ResponseError for ApiError {
             ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
             ApiError::OperatorMismatch(_) => StatusCode::BAD_REQUEST,
             ApiError::InvalidIntegerRange(_) => StatusCode::BAD_REQUEST,
+            ApiError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ApiError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            ApiError::TooManyRequests(_, _) => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
@@ -172,3 +261,82 @@ pub fn json_error_handler(err: JsonPayloadError, _: &HttpRequest) -> actix_web::
     let error_message = format!("Json deserialize error: {}", err);
     ApiError::BadRequest(error_message).into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use yare::parameterized;
+
+    #[parameterized(
+        not_found = { ApiError::NotFound("gone".to_string()), StatusCode::NOT_FOUND, "Not Found", "not_found", "gone" },
+        conflict = { ApiError::Conflict("taken".to_string()), StatusCode::CONFLICT, "Conflict", "conflict", "taken" },
+        validation = { ApiError::ValidationError("bad data".to_string()), StatusCode::BAD_REQUEST, "Bad Request", "schema_validation_failed", "bad data" },
+        precondition = { ApiError::PreconditionFailed("changed".to_string()), StatusCode::PRECONDITION_FAILED, "Precondition Failed", "precondition_failed", "changed" },
+        db_connection = { ApiError::DbConnectionError("down".to_string()), StatusCode::INTERNAL_SERVER_ERROR, "Database Connection Error", "database_unavailable", INTERNAL_ERROR_MESSAGE },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn test_error_response_body(
+        error: ApiError,
+        status: StatusCode,
+        title: &str,
+        code: &str,
+        message: &str,
+    ) {
+        let response = error.error_response();
+        assert_eq!(response.status(), status);
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let mut body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        if status.is_server_error() {
+            let correlation_id = body.as_object_mut().unwrap().remove("correlation_id");
+            assert!(Uuid::parse_str(correlation_id.unwrap().as_str().unwrap()).is_ok());
+        }
+        assert_eq!(
+            body,
+            json!({ "error": title, "code": code, "message": message })
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_database_error_is_not_leaked() {
+        let sql = "syntax error at or near \"SELECT\": SELECT * FROM users WHERE password = $1";
+        let error = ApiError::from(DieselError::DatabaseError(
+            DatabaseErrorKind::Unknown,
+            Box::new(sql.to_string()),
+        ));
+
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let text = std::str::from_utf8(&body).unwrap();
+        assert!(!text.contains("SELECT"), "Body leaks SQL: {}", text);
+        assert!(!text.contains("users"), "Body leaks SQL: {}", text);
+
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "database_error");
+        assert_eq!(body["message"], INTERNAL_ERROR_MESSAGE);
+        assert!(body["correlation_id"].is_string());
+    }
+
+    #[test]
+    fn test_verbose_error_body() {
+        let error = ApiError::DatabaseError("relation \"secret\" does not exist".to_string());
+
+        let body = error.to_json_with(false);
+        assert_eq!(body["message"], INTERNAL_ERROR_MESSAGE);
+        assert!(body.get("detail").is_none());
+
+        let body = error.to_json_with(true);
+        assert_eq!(body["message"], INTERNAL_ERROR_MESSAGE);
+        assert_eq!(body["detail"], error.to_string());
+        assert!(body["correlation_id"].is_string());
+
+        let error = ApiError::NotFound("gone".to_string());
+        assert_eq!(
+            error.to_json_with(true),
+            json!({ "error": "Not Found", "code": "not_found", "message": "gone", "detail": "gone" })
+        );
+    }
+}
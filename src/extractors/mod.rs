@@ -1,9 +1,12 @@
+use crate::db::scope::set_namespace_scope;
 use crate::db::DbPool;
 use crate::errors::ApiError;
-use crate::models::token::Token;
+use crate::middlewares::access_log::AuthenticatedUserId;
+use crate::middlewares::token_expiry::TokenExpiry;
+use crate::models::token::{Token, UserToken};
 use crate::models::user::User;
 use crate::utilities::iam::get_user_by_id;
-use actix_web::{dev::Payload, web::Data, FromRequest, HttpRequest};
+use actix_web::{dev::Payload, http::Method, web::Data, FromRequest, HttpMessage, HttpRequest};
 use futures_util::future::{self, FutureExt};
 use std::pin::Pin;
 use tracing::debug;
@@ -28,6 +31,50 @@ pub struct UserAccess {
     pub user: User,
 }
 
+/// A marker for GET handlers that change state, so read-only tokens are refused for them like
+/// for other writes.
+///
+/// Take it as an argument of the handler, next to the extractor authenticating the user. The
+/// two can be extracted in any order, as both record what they know in the request extensions
+/// and the one extracted last does the check.
+pub struct MutatingGet;
+
+// Inserted into the request extensions by MutatingGet.
+#[derive(Clone, Copy)]
+struct MutatingGetMarker;
+
+// Inserted into the request extensions when the request is made with a read-only token.
+#[derive(Clone, Copy)]
+struct ReadOnlyToken;
+
+/// Whether a request changes state, ie anything but GET and HEAD, along with the GET handlers
+/// marked with [`MutatingGet`].
+fn is_mutating(req: &HttpRequest) -> bool {
+    !matches!(*req.method(), Method::GET | Method::HEAD)
+        || req.extensions().contains::<MutatingGetMarker>()
+}
+
+fn read_only_error(req: &HttpRequest) -> ApiError {
+    ApiError::Forbidden(format!(
+        "Read-only tokens can't be used for {} {}",
+        req.method(),
+        req.path()
+    ))
+}
+
+impl FromRequest for MutatingGet {
+    type Error = ApiError;
+    type Future = future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        req.extensions_mut().insert(MutatingGetMarker);
+        if req.extensions().contains::<ReadOnlyToken>() {
+            return future::ready(Err(read_only_error(req)));
+        }
+        future::ready(Ok(MutatingGet))
+    }
+}
+
 fn extract_token(req: &HttpRequest) -> Result<Token, ApiError> {
     req.headers()
         .get("Authorization")
@@ -41,12 +88,39 @@ fn extract_token(req: &HttpRequest) -> Result<Token, ApiError> {
         .ok_or_else(|| ApiError::Unauthorized("No token provided".to_string()))
 }
 
-async fn extract_user_from_token(pool: &DbPool, token: &Token) -> Result<User, ApiError> {
+async fn extract_user_from_token(
+    pool: &DbPool,
+    token: &Token,
+) -> Result<(User, UserToken), ApiError> {
     use crate::db::traits::Status;
     let user_token = token.is_valid(pool).await?;
 
-    get_user_by_id(pool, user_token.user_id)
-        .map_err(|_| ApiError::Unauthorized("Invalid token".to_string()))
+    let user = get_user_by_id(pool, user_token.user_id)
+        .map_err(|_| ApiError::Unauthorized("Invalid token".to_string()))?;
+
+    Ok((user, user_token))
+}
+
+/// Record the authenticated user and the expiry of their token in the request extensions, for
+/// the access log and the token expiry header, and apply the restrictions of the token.
+///
+/// Read-only tokens are refused for requests that change state (see [`is_mutating`]).
+fn record_identity(req: &HttpRequest, user: &User, user_token: &UserToken) -> Result<(), ApiError> {
+    {
+        let mut extensions = req.extensions_mut();
+        extensions.insert(AuthenticatedUserId(user.id));
+        extensions.insert(TokenExpiry(user_token.expires()));
+
+        if user_token.read_only {
+            extensions.insert(ReadOnlyToken);
+        }
+    }
+
+    if user_token.read_only && is_mutating(req) {
+        return Err(read_only_error(req));
+    }
+
+    set_namespace_scope(user_token.namespace_id)
 }
 
 async fn get_user_and_path(
@@ -85,10 +159,12 @@ impl FromRequest for UserAccess {
         };
 
         let token_result = extract_token(req);
+        let req = req.clone();
 
         async move {
             let token = token_result?;
-            let user = extract_user_from_token(&pool, &token).await?;
+            let (user, user_token) = extract_user_from_token(&pool, &token).await?;
+            record_identity(&req, &user, &user_token)?;
 
             Ok(UserAccess { token, user })
         }
@@ -112,12 +188,14 @@ impl FromRequest for AdminAccess {
         };
 
         let token_result = extract_token(req);
+        let req = req.clone();
 
         async move {
             let token = token_result?;
-            let user = extract_user_from_token(&pool, &token).await?;
+            let (user, user_token) = extract_user_from_token(&pool, &token).await?;
+            record_identity(&req, &user, &user_token)?;
 
-            if user.is_admin(&pool).await {
+            if user.has_admin_access(&pool).await {
                 Ok(AdminAccess { token, user })
             } else {
                 Err(ApiError::Forbidden("Permission denied".to_string()))
@@ -146,15 +224,17 @@ impl FromRequest for AdminOrSelfAccess {
 
         // Extract necessary information from `req` here
         let path_info = req.match_info().clone();
+        let req = req.clone();
 
         async move {
             let token = token_result?;
-            let user = extract_user_from_token(&pool, &token).await?;
+            let (user, user_token) = extract_user_from_token(&pool, &token).await?;
+            record_identity(&req, &user, &user_token)?;
 
             // Use the extracted information instead of `req`
             let (user_from_path, path) = get_user_and_path(&path_info, &pool).await?;
 
-            if user.is_admin(&pool).await || user.id == user_from_path.id {
+            if user.has_admin_access(&pool).await || user.id == user_from_path.id {
                 Ok(AdminOrSelfAccess { token, user })
             } else {
                 debug! {
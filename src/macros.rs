@@ -74,6 +74,44 @@ macro_rules! can {
     }};
 }
 
+#[macro_export]
+/// ## Count a write against the write rate limit of a set of namespaces.
+///
+/// Use this in handlers that create, update or delete entities, after the permission check so
+/// forbidden requests are not counted.
+///
+/// ### Arguments
+///
+/// * `pool` - A database connection pool.
+/// * `objects+`- Objects whose namespaces are written to (impl [`NamespaceAccessors`]).
+///
+/// ### Returns
+///
+/// * Nothing if the write is within the limits, or an [`ApiError::TooManyRequests`] if a limit
+///   is exceeded.
+///
+/// ### Example
+///
+/// ```ignore
+/// limit_writes!(pool, class);
+/// limit_writes!(pool, from_class, to_class);
+/// ```
+///
+/// [`NamespaceAccessors`]: crate::traits::NamespaceAccessors
+/// [`ApiError::TooManyRequests`]: crate::errors::ApiError::TooManyRequests
+macro_rules! limit_writes {
+    ($pool:expr, $($namespace:expr),+) => {{
+        // A write touching the same namespace twice, eg a relation within a namespace, is
+        // counted once.
+        let mut namespace_ids = vec![$($namespace.namespace_id($pool).await?),+];
+        namespace_ids.sort_unstable();
+        namespace_ids.dedup();
+        for namespace_id in namespace_ids {
+            $crate::utilities::rate_limit::check_namespace_write_rate($pool, namespace_id).await?;
+        }
+    }};
+}
+
 #[macro_export]
 /// Check permissions for a user on a namespace, class, or object.
 ///
@@ -137,7 +175,7 @@ macro_rules! json_search {
         if !json_data_queries.is_empty() {
             // Get the object IDs that match the JSON data queries. This is a complexly built
             // query that is executed and we fish out the IDs from the result.
-            let json_data_integers = $me.json_data_subquery($pool, json_data_queries)?;
+            let json_data_integers = $me.json_data_subquery($pool, json_data_queries, None)?;
             if !json_data_integers.is_empty() {
                 // If we get any object IDs, filter the database field we requested on these values.
                 $query = $query.filter($dbfield.eq_any(json_data_integers))
@@ -319,6 +357,38 @@ macro_rules! date_search {
     }};
 }
 
+#[macro_export]
+/// A macro to search for entries created or updated at or after a point in time
+///
+/// Matches if either timestamp is at or after the given time, so it does not rely on
+/// `created_at <= updated_at`. Only the plain form (`changed_since=<time>`) is supported.
+macro_rules! changed_since_search {
+    ($base_query:expr, $parsed_query_param:expr, $operator:expr, $created_field:expr, $updated_field:expr) => {{
+        use diesel::prelude::*;
+        use $crate::errors::ApiError;
+        use $crate::models::search::Operator;
+
+        let values = $parsed_query_param.value_as_date()?;
+
+        if $operator.op_and_neg() != (Operator::Equals, false) {
+            return Err(ApiError::OperatorMismatch(format!(
+                "Operator '{:?}' is not applicable to field '{}'",
+                $operator, $parsed_query_param.field
+            )));
+        }
+
+        if values.len() != 1 {
+            return Err(ApiError::BadRequest(format!(
+                "Field '{}' requires exactly one value",
+                $parsed_query_param.field
+            )));
+        }
+
+        let since = values[0];
+        $base_query = $base_query.filter($created_field.ge(since).or($updated_field.ge(since)));
+    }};
+}
+
 #[macro_export]
 /// A macro to search on a field with a list of values
 macro_rules! array_search {
@@ -370,66 +440,73 @@ macro_rules! array_search {
 
 #[macro_export]
 /// A string search macro
+///
+/// A non-negated string operator repeated on the same field is OR'ed together, so that
+/// `name__like=web-%&name__like=db-%` matches either pattern, while different fields (or
+/// different operators) are still AND'ed. The first parameter in such a group applies the
+/// combined filter and the remaining ones are no-ops. Negated operators are always applied
+/// (AND'ed) one by one.
+///
+/// `$query_params` is the full list of parsed query parameters that `$param` was taken from.
 macro_rules! string_search {
-    ($base_query:expr, $param:expr, $operator:expr, $diesel_field:expr) => {{
+    ($base_query:expr, $query_params:expr, $param:expr, $operator:expr, $diesel_field:expr) => {{
         use diesel::dsl::not;
+        use diesel::expression::BoxableExpression;
+        use diesel::pg::Pg;
         use diesel::prelude::*;
         use $crate::errors::ApiError;
-        use $crate::models::search::{DataType, Operator};
-
-        let value = $param.value.clone();
-
-        if !$operator.is_applicable_to(DataType::String) {
-            return Err(ApiError::OperatorMismatch(format!(
-                "Operator '{:?}' is not applicable to field '{}'",
-                $operator, $param.field
-            )));
-        }
-
-        // The value shouldn't be empty at this point, but we can make sure.
-        if value.is_empty() {
-            return Err(ApiError::BadRequest(format!(
-                "Searching on field '{}' requires a value",
-                $param.field
-            )));
-        }
+        use $crate::models::search::{DataType, Operator, ParsedQueryParam, QueryParamsExt};
 
-        let (op, negated) = $operator.op_and_neg();
+        let string_expression = |param: &ParsedQueryParam| -> Result<
+            Box<dyn BoxableExpression<_, Pg, SqlType = _>>,
+            ApiError,
+        > {
+            let value = param.value.clone();
 
-        match (op, negated) {
-            (Operator::Equals, false) => $base_query = $base_query.filter($diesel_field.eq(value)),
-            (Operator::Equals, true) => {
-                $base_query = $base_query.filter(not($diesel_field.eq(value)))
-            }
-            (Operator::Contains, false) => {
-                $base_query = $base_query.filter($diesel_field.like(format!("%{}%", value)))
-            }
-            (Operator::Contains, true) => {
-                $base_query = $base_query.filter(not($diesel_field.like(format!("%{}%", value))))
-            }
-            (Operator::StartsWith, false) => {
-                $base_query = $base_query.filter($diesel_field.like(format!("{}%", value)))
-            }
-            (Operator::StartsWith, true) => {
-                $base_query = $base_query.filter(not($diesel_field.like(format!("{}%", value))))
-            }
-            (Operator::EndsWith, false) => {
-                $base_query = $base_query.filter($diesel_field.like(format!("%{}", value)))
-            }
-            (Operator::EndsWith, true) => {
-                $base_query = $base_query.filter(not($diesel_field.like(format!("%{}", value))))
-            }
-            (Operator::IContains, false) => {
-                $base_query = $base_query.filter($diesel_field.ilike(format!("%{}%", value)))
-            }
-            (Operator::IContains, true) => {
-                $base_query = $base_query.filter(not($diesel_field.ilike(format!("%{}%", value))))
-            }
-            _ => {
+            if !param.operator.is_applicable_to(DataType::String) {
                 return Err(ApiError::OperatorMismatch(format!(
+                    "Operator '{:?}' is not applicable to field '{}'",
+                    param.operator, param.field
+                )));
+            }
+
+            // The value shouldn't be empty at this point, but we can make sure.
+            if value.is_empty() {
+                return Err(ApiError::BadRequest(format!(
+                    "Searching on field '{}' requires a value",
+                    param.field
+                )));
+            }
+
+            match param.operator.op_and_neg().0 {
+                Operator::Equals => Ok(Box::new($diesel_field.eq(value))),
+                Operator::Contains => Ok(Box::new($diesel_field.like(format!("%{}%", value)))),
+                Operator::StartsWith => Ok(Box::new($diesel_field.like(format!("{}%", value)))),
+                Operator::EndsWith => Ok(Box::new($diesel_field.like(format!("%{}", value)))),
+                Operator::IContains => Ok(Box::new($diesel_field.ilike(format!("%{}%", value)))),
+                Operator::Like => Ok(Box::new($diesel_field.like(value))),
+                _ => Err(ApiError::OperatorMismatch(format!(
                     "Operator '{:?}' not implemented for field '{}' (type: string)",
-                    $operator, $param.field
-                )))
+                    param.operator, param.field
+                ))),
+            }
+        };
+
+        let (_, negated) = $operator.op_and_neg();
+
+        if negated {
+            $base_query = $base_query.filter(not(string_expression(&*$param)?));
+        } else {
+            let alternatives = $query_params.string_alternatives(&$param);
+            if alternatives
+                .first()
+                .is_some_and(|first| std::ptr::eq(*first, &*$param))
+            {
+                let mut combined = string_expression(alternatives[0])?;
+                for alternative in alternatives.iter().skip(1) {
+                    combined = Box::new(combined.or(string_expression(alternative)?));
+                }
+                $base_query = $base_query.filter(combined);
             }
         }
     }};
@@ -15,14 +15,19 @@ mod tests;
 mod traits;
 mod utilities;
 
-use actix_web::{middleware::Logger, web::Data, web::JsonConfig, App, HttpServer};
+use actix_web::{
+    middleware::{Condition, Logger},
+    web::Data,
+    web::JsonConfig,
+    App, HttpServer,
+};
 use db::init_pool;
 use tracing::{debug, warn};
 use tracing_subscriber::{
     filter::EnvFilter, fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt,
 };
 
-use crate::config::get_config;
+use crate::config::{get_config, LogFormat};
 use crate::errors::json_error_handler;
 use crate::utilities::is_valid_log_level;
 
@@ -41,34 +46,67 @@ async fn main() -> std::io::Result<()> {
         std::process::exit(1);
     };
 
-    tracing_subscriber::registry()
-        .with(filter)
-        .with(
-            tracing_subscriber::fmt::layer()
-                .json()
-                .with_span_events(FmtSpan::CLOSE)
-                .event_format(logger::HubuumLoggingFormat),
-        )
-        .init();
+    let registry = tracing_subscriber::registry().with(filter);
+    match config.log_format {
+        LogFormat::Json => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_span_events(FmtSpan::CLOSE)
+                    .event_format(logger::HubuumLoggingFormat),
+            )
+            .init(),
+        LogFormat::Text => registry
+            .with(tracing_subscriber::fmt::layer().with_span_events(FmtSpan::CLOSE))
+            .init(),
+    }
 
     debug!(
         message = "Starting server",
         bind_ip = %config.bind_ip,
         port = config.port,
         log_level = %config.log_level,
+        log_format = ?config.log_format,
         actix_workers = config.actix_workers,
         db_pool_size = config.db_pool_size,
+        access_log = config.access_log,
+        token_expiry_header = config.token_expiry_header,
+        max_query_params = config.max_query_params,
+        approximate_stats = config.approximate_stats,
+        namespace_write_rate_limit = config.namespace_write_rate_limit,
+        require_unique_email = config.require_unique_email,
+        verbose_errors = config.verbose_errors,
+        user_concurrency_limit = config.user_concurrency_limit,
     );
 
+    models::search::set_max_query_params(config.max_query_params);
+    errors::set_verbose_errors(config.verbose_errors);
+
     let pool = init_pool(&config.database_url.clone(), config.db_pool_size);
 
     utilities::init::init(pool.clone()).await;
 
+    let access_log = config.access_log;
+    let token_expiry_header = config.token_expiry_header;
+    // Shared by all workers, so the limit is per instance.
+    let concurrency_limiter = Data::new(middlewares::concurrency::ConcurrencyLimiter::new(
+        config.user_concurrency_limit,
+    ));
+
     HttpServer::new(move || {
         App::new()
             .wrap(middlewares::tracing::TracingMiddleware)
             .wrap(Logger::default())
+            .wrap(Condition::new(
+                access_log,
+                middlewares::access_log::AccessLogMiddleware,
+            ))
+            .wrap(Condition::new(
+                token_expiry_header,
+                middlewares::token_expiry::TokenExpiryMiddleware,
+            ))
             .app_data(Data::new(pool.clone()))
+            .app_data(concurrency_limiter.clone())
             .app_data(JsonConfig::default().error_handler(json_error_handler))
             .configure(api::config)
     })
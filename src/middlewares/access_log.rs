@@ -0,0 +1,151 @@
+use actix_service::{Service, Transform};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error, HttpMessage};
+use futures_util::future::{self, LocalBoxFuture, Ready};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::middlewares::tracing::RequestId;
+
+/// The ID of the authenticated user, inserted into the request extensions by the extractors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AuthenticatedUserId(pub i32);
+
+/// A single access log entry.
+#[derive(Debug, PartialEq)]
+pub struct AccessLogEntry {
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub latency: Duration,
+    pub user_id: Option<i32>,
+    pub request_id: Option<String>,
+}
+
+impl AccessLogEntry {
+    /// Build the entry from a response. The identity and request ID are read from the
+    /// request extensions, so this has to be called after the handler has run.
+    pub fn from_response<B>(res: &ServiceResponse<B>, latency: Duration) -> Self {
+        let req = res.request();
+        let extensions = req.extensions();
+
+        AccessLogEntry {
+            method: req.method().to_string(),
+            path: req.path().to_string(),
+            status: res.status().as_u16(),
+            latency,
+            user_id: extensions.get::<AuthenticatedUserId>().map(|u| u.0),
+            request_id: extensions.get::<RequestId>().map(|r| r.0.clone()),
+        }
+    }
+
+    pub fn log(&self) {
+        info!(
+            message = "Access",
+            method = &self.method,
+            path = &self.path,
+            status = self.status,
+            latency_ms = self.latency.as_secs_f64() * 1000.0,
+            user_id = self.user_id,
+            request_id = self.request_id.as_deref(),
+        );
+    }
+}
+
+// Middleware factory, enabled by HUBUUM_ACCESS_LOG.
+pub struct AccessLogMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for AccessLogMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = AccessLogMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ready(Ok(AccessLogMiddlewareService { service }))
+    }
+}
+
+pub struct AccessLogMiddlewareService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessLogMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let start_time = Instant::now();
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+            AccessLogEntry::from_response(&res, start_time.elapsed()).log();
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api as prod_api;
+    use crate::middlewares::tracing::TracingMiddleware;
+    use crate::tests::{ensure_admin_user, setup_pool_and_tokens};
+    use actix_web::{http, test, web::Data, App};
+
+    async fn entry_for(token: &str, uri: &str) -> AccessLogEntry {
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let app = test::init_service(
+            App::new()
+                .wrap(TracingMiddleware)
+                .wrap(AccessLogMiddleware)
+                .app_data(Data::new(pool.get_ref().clone()))
+                .configure(prod_api::config),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .insert_header((http::header::AUTHORIZATION, format!("Bearer {}", token)))
+            .uri(uri)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        AccessLogEntry::from_response(&res, Duration::from_millis(5))
+    }
+
+    #[actix_web::test]
+    async fn test_access_log_entry() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let admin = ensure_admin_user(&pool).await;
+
+        let entry = entry_for(&admin_token, "/api/v1/me/groups").await;
+        assert_eq!(entry.method, "GET");
+        assert_eq!(entry.path, "/api/v1/me/groups");
+        assert_eq!(entry.status, 200);
+        assert_eq!(entry.latency, Duration::from_millis(5));
+        assert_eq!(entry.user_id, Some(admin.id));
+        assert!(entry.request_id.is_some());
+
+        // Unauthenticated requests are logged without a user
+        let entry = entry_for("invalid", "/api/v1/me/groups").await;
+        assert_eq!(entry.status, 401);
+        assert_eq!(entry.user_id, None);
+        assert!(entry.request_id.is_some());
+    }
+}
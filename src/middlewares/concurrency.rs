@@ -0,0 +1,272 @@
+use actix_service::{Service, Transform};
+use actix_web::body::EitherBody;
+use actix_web::web::Data;
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error, HttpMessage};
+use futures_util::future::{self, LocalBoxFuture, Ready};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::db::traits::Status;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::models::token::Token;
+
+/// A cap on the number of requests each user may have in flight at the same time.
+///
+/// The limiter is shared between the workers as app data, and applied by
+/// [`ConcurrencyLimitMiddleware`], which takes a slot for every authenticated request ahead of
+/// the extractors. Tokens the limiter has seen before are mapped to their user without touching
+/// the database, so requests over the limit are rejected cheaply. Other tokens are looked up
+/// once, and registered with [`ConcurrencyLimiter::acquire`].
+///
+/// Requests over the limit are rejected rather than queued, so a noisy client can't tie up the
+/// workers and the database pool waiting for its own requests. Users are forgotten, along with
+/// their tokens, as soon as they have no requests in flight.
+pub struct ConcurrencyLimiter {
+    limit: usize,
+    state: Arc<Mutex<LimiterState>>,
+}
+
+#[derive(Default)]
+struct LimiterState {
+    // The users with requests in flight, with their slots and the tokens they have used.
+    users: HashMap<i32, (Arc<Semaphore>, HashSet<String>)>,
+    // The user of every token in `users`.
+    tokens: HashMap<String, i32>,
+}
+
+/// A slot taken by a request, released when the request is dropped.
+pub struct RequestSlot {
+    permit: Option<OwnedSemaphorePermit>,
+    user_id: i32,
+    limit: usize,
+    state: Arc<Mutex<LimiterState>>,
+}
+
+impl Drop for RequestSlot {
+    fn drop(&mut self) {
+        drop(self.permit.take());
+
+        // Slots are only taken with the state locked, so a user with every slot free has no
+        // requests in flight and can be forgotten.
+        let mut state = self.state.lock().unwrap();
+        let idle = state
+            .users
+            .get(&self.user_id)
+            .is_some_and(|(semaphore, _)| semaphore.available_permits() == self.limit);
+        if idle {
+            if let Some((_, tokens)) = state.users.remove(&self.user_id) {
+                for token in tokens {
+                    state.tokens.remove(&token);
+                }
+            }
+        }
+    }
+}
+
+impl ConcurrencyLimiter {
+    /// Create a limiter allowing `limit` concurrent requests per user, 0 for no limit.
+    pub fn new(limit: usize) -> Self {
+        ConcurrencyLimiter {
+            limit,
+            state: Arc::new(Mutex::new(LimiterState::default())),
+        }
+    }
+
+    /// Take a slot for a request by the given user, made with the given token.
+    ///
+    /// The token is remembered for as long as the user has requests in flight, so further
+    /// requests with it can be limited by [`ConcurrencyLimiter::acquire_for_token`].
+    ///
+    /// ## Returns
+    /// * Ok(Some(RequestSlot)) - The slot, to be held until the request is done.
+    /// * Ok(None) - There is no limit.
+    /// * Err(ApiError::TooManyRequests) - The user has `limit` requests in flight already.
+    pub fn acquire(&self, user_id: i32, token: &str) -> Result<Option<RequestSlot>, ApiError> {
+        if self.limit == 0 {
+            return Ok(None);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let (semaphore, tokens) = state
+            .users
+            .entry(user_id)
+            .or_insert_with(|| (Arc::new(Semaphore::new(self.limit)), HashSet::new()));
+        let semaphore = semaphore.clone();
+        if tokens.insert(token.to_string()) {
+            state.tokens.insert(token.to_string(), user_id);
+        }
+
+        self.take_slot(semaphore, user_id).map(Some)
+    }
+
+    /// Take a slot for a request made with a token the limiter has seen before.
+    ///
+    /// ## Returns
+    /// * Some(Ok(RequestSlot)) - The slot, to be held until the request is done.
+    /// * Some(Err(ApiError::TooManyRequests)) - The user of the token has `limit` requests in
+    ///   flight already.
+    /// * None - The token is not known, or there is no limit.
+    pub fn acquire_for_token(&self, token: &str) -> Option<Result<RequestSlot, ApiError>> {
+        if self.limit == 0 {
+            return None;
+        }
+
+        let state = self.state.lock().unwrap();
+        let user_id = *state.tokens.get(token)?;
+        let semaphore = state.users.get(&user_id)?.0.clone();
+
+        Some(self.take_slot(semaphore, user_id))
+    }
+
+    fn take_slot(&self, semaphore: Arc<Semaphore>, user_id: i32) -> Result<RequestSlot, ApiError> {
+        match semaphore.try_acquire_owned() {
+            Ok(permit) => Ok(RequestSlot {
+                permit: Some(permit),
+                user_id,
+                limit: self.limit,
+                state: self.state.clone(),
+            }),
+            Err(_) => Err(ApiError::TooManyRequests(
+                format!(
+                    "Too many concurrent requests, at most {} are allowed per user",
+                    self.limit
+                ),
+                1,
+            )),
+        }
+    }
+}
+
+// Middleware factory, applying the ConcurrencyLimiter in the app data to requests with a valid
+// token. Requests pass through untouched if there is no limiter, and requests without a valid
+// token are left for the extractors to reject.
+pub struct ConcurrencyLimitMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for ConcurrencyLimitMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = ConcurrencyLimitMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ready(Ok(ConcurrencyLimitMiddlewareService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct ConcurrencyLimitMiddlewareService<S> {
+    service: Rc<S>,
+}
+
+// Take a slot for the request, if there is a limit and the request has a valid token.
+async fn slot_for_request(req: &ServiceRequest) -> Result<Option<RequestSlot>, ApiError> {
+    let limiter = match req.app_data::<Data<ConcurrencyLimiter>>() {
+        Some(limiter) if limiter.limit > 0 => limiter.clone(),
+        _ => return Ok(None),
+    };
+    let token = match req
+        .headers()
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+    {
+        Some(token) => token.to_string(),
+        None => return Ok(None),
+    };
+
+    if let Some(slot) = limiter.acquire_for_token(&token) {
+        return slot.map(Some);
+    }
+
+    let pool = match req.app_data::<Data<DbPool>>() {
+        Some(pool) => pool.clone(),
+        None => return Ok(None),
+    };
+    match Token(token.clone()).is_valid(&pool).await {
+        Ok(user_token) => limiter.acquire(user_token.user_id, &token),
+        Err(_) => Ok(None),
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for ConcurrencyLimitMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            match slot_for_request(&req).await {
+                // The slot is kept with the request, so it is released once the request is done.
+                Ok(Some(slot)) => {
+                    req.extensions_mut().insert(slot);
+                }
+                Ok(None) => {}
+                Err(e) => return Ok(req.error_response(e).map_into_right_body()),
+            }
+
+            Ok(service.call(req).await?.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire() {
+        let limiter = ConcurrencyLimiter::new(2);
+
+        let first = limiter.acquire(1, "a").unwrap();
+        let second = limiter.acquire(1, "b").unwrap();
+        assert!(first.is_some() && second.is_some());
+        match limiter.acquire(1, "a") {
+            Err(ApiError::TooManyRequests(_, retry_after)) => assert_eq!(retry_after, 1),
+            other => panic!("Expected too many requests, got {:?}", other.map(|_| ())),
+        }
+        // Both tokens are known, and share the slots of their user.
+        assert!(limiter.acquire_for_token("b").unwrap().is_err());
+        assert!(limiter.acquire_for_token("c").is_none());
+
+        // Other users have their own slots, and finished requests free theirs.
+        assert!(limiter.acquire(2, "c").is_ok());
+        drop(first);
+        let third = limiter.acquire_for_token("a").unwrap().unwrap();
+
+        // Users without requests in flight are forgotten, along with their tokens.
+        drop(second);
+        drop(third);
+        let state = limiter.state.lock().unwrap();
+        assert!(state.users.is_empty() && state.tokens.is_empty());
+        drop(state);
+        assert!(limiter.acquire_for_token("a").is_none());
+
+        let unlimited = ConcurrencyLimiter::new(0);
+        let slots = (0..10)
+            .map(|_| unlimited.acquire(1, "a").unwrap())
+            .collect::<Vec<_>>();
+        assert!(slots.iter().all(|slot| slot.is_none()));
+        assert!(unlimited.acquire_for_token("a").is_none());
+    }
+}
@@ -1 +1,5 @@
+pub mod access_log;
+pub mod concurrency;
+pub mod namespace_scope;
+pub mod token_expiry;
 pub mod tracing;
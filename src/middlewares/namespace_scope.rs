@@ -0,0 +1,49 @@
+use actix_service::{Service, Transform};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error};
+use futures_util::future::{self, LocalBoxFuture, Ready};
+use std::task::{Context, Poll};
+
+use crate::db::scope::with_namespace_scope;
+
+// Middleware factory, giving every request its own namespace scope, see `db::scope`.
+pub struct NamespaceScopeMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for NamespaceScopeMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = NamespaceScopeMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ready(Ok(NamespaceScopeMiddlewareService { service }))
+    }
+}
+
+pub struct NamespaceScopeMiddlewareService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for NamespaceScopeMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let fut = self.service.call(req);
+        Box::pin(with_namespace_scope(fut))
+    }
+}
@@ -0,0 +1,137 @@
+use actix_service::{Service, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error, HttpMessage};
+use chrono::{NaiveDateTime, Utc};
+use futures_util::future::{self, LocalBoxFuture, Ready};
+use std::task::{Context, Poll};
+
+/// The response header carrying the number of seconds until the token expires.
+pub const TOKEN_EXPIRES_IN_HEADER: &str = "x-token-expires-in";
+
+/// The expiry of the token used for the request, inserted into the request extensions by the
+/// extractors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TokenExpiry(pub NaiveDateTime);
+
+impl TokenExpiry {
+    /// Seconds until the token expires, never negative.
+    pub fn expires_in(&self) -> i64 {
+        (self.0 - Utc::now().naive_utc()).num_seconds().max(0)
+    }
+}
+
+// Middleware factory, enabled by HUBUUM_TOKEN_EXPIRY_HEADER.
+pub struct TokenExpiryMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for TokenExpiryMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = TokenExpiryMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ready(Ok(TokenExpiryMiddlewareService { service }))
+    }
+}
+
+pub struct TokenExpiryMiddlewareService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for TokenExpiryMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            // Only requests that were authenticated have an expiry to report.
+            let expiry = res.request().extensions().get::<TokenExpiry>().copied();
+            if let Some(expiry) = expiry {
+                res.headers_mut().insert(
+                    HeaderName::from_static(TOKEN_EXPIRES_IN_HEADER),
+                    HeaderValue::from(expiry.expires_in()),
+                );
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api as prod_api;
+    use crate::models::TOKEN_LIFETIME_HOURS;
+    use crate::tests::{create_test_user, setup_pool_and_tokens};
+    use actix_web::{http, test, web::Data, App};
+    use diesel::prelude::*;
+
+    async fn expires_in(token: &str) -> Option<i64> {
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let app = test::init_service(
+            App::new()
+                .wrap(TokenExpiryMiddleware)
+                .app_data(Data::new(pool.get_ref().clone()))
+                .configure(prod_api::config),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .insert_header((http::header::AUTHORIZATION, format!("Bearer {}", token)))
+            .uri("/api/v1/me/groups")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        res.headers()
+            .get(TOKEN_EXPIRES_IN_HEADER)
+            .map(|v| v.to_str().unwrap().parse().unwrap())
+    }
+
+    #[actix_web::test]
+    async fn test_token_expires_in_header() {
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let user = create_test_user(&pool).await;
+        let token = user.create_token(&pool).await.unwrap().get_token();
+
+        let lifetime = i64::from(TOKEN_LIFETIME_HOURS) * 3600;
+        let seconds = expires_in(&token).await.unwrap();
+        assert!(seconds <= lifetime && seconds > lifetime - 60);
+
+        // A token issued almost a lifetime ago is about to expire
+        {
+            use crate::schema::tokens::dsl::{issued, token as token_column, tokens};
+            let mut conn = pool.get().unwrap();
+            diesel::update(tokens.filter(token_column.eq(&token)))
+                .set(issued.eq(issued - diesel::dsl::IntervalDsl::hours(TOKEN_LIFETIME_HOURS - 1)))
+                .execute(&mut conn)
+                .unwrap();
+        }
+        let seconds = expires_in(&token).await.unwrap();
+        assert!(seconds <= 3600 && seconds > 3600 - 60);
+
+        // Unauthenticated requests get no header
+        assert_eq!(expires_in("invalid").await, None);
+
+        user.delete(&pool).await.unwrap();
+    }
+}
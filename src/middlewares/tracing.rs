@@ -1,11 +1,15 @@
 use actix_service::{Service, Transform};
-use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error, HttpMessage};
 use futures_util::future::{self, LocalBoxFuture, Ready};
 use std::task::{Context, Poll};
 use std::time::Instant;
 use tracing::{info, span, Instrument, Level};
 use uuid::Uuid;
 
+/// The ID of the current request, available from the request extensions.
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
 // Middleware factory
 pub struct TracingMiddleware;
 
@@ -46,6 +50,7 @@ where
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let request_id = Uuid::new_v4().to_string(); // Generate a new UUID
         let span = span!(Level::INFO, "request", request_id = %request_id);
+        req.extensions_mut().insert(RequestId(request_id.clone()));
 
         let method = req.method().to_string();
         let path = req.path().to_string();
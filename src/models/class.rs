@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::db::{with_connection, DbPool};
 use crate::errors::ApiError;
-use crate::schema::hubuumclass;
+use crate::schema::{class_indexes, hubuumclass};
 
 #[derive(Serialize, Deserialize, Queryable, Clone, PartialEq, Debug)]
 #[diesel(table_name = hubuumclass )]
@@ -47,6 +47,30 @@ pub struct UpdateHubuumClass {
     pub description: Option<String>,
 }
 
+/// The request body for cloning a class.
+///
+/// The clone gets the schema, schema validation setting and description of the source class. It
+/// is created in the namespace of the source unless `namespace_id` is given. If
+/// `include_relations` is set, the relations of the source class are copied as well, with the
+/// source replaced by the clone.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CloneHubuumClass {
+    pub name: String,
+    pub namespace_id: Option<i32>,
+    #[serde(default)]
+    pub include_relations: bool,
+}
+
+/// Query parameters for creating a class.
+///
+/// With `if_not_exists` set, creating a class whose name is already taken by a class in the
+/// same namespace returns the existing class instead of a conflict.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CreateClassOptions {
+    #[serde(default)]
+    pub if_not_exists: bool,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct HubuumClassWithPath {
     pub id: i32,
@@ -63,6 +87,71 @@ pub struct HubuumClassWithPath {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct HubuumClassID(pub i32);
 
+/// An indexed JSON path in the data of the objects of a class.
+///
+/// Each declaration is backed by a partial expression index on `hubuumobject`, see
+/// [`ClassIndex::index_name`].
+#[derive(Serialize, Deserialize, Queryable, Clone, PartialEq, Debug)]
+#[diesel(table_name = class_indexes)]
+pub struct ClassIndex {
+    pub id: i32,
+    pub hubuum_class_id: i32,
+    pub json_path: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl ClassIndex {
+    /// The name of the expression index on `hubuumobject` that backs this declaration.
+    pub fn index_name(&self) -> String {
+        format!("hubuumobject_data_class_index_{}", self.id)
+    }
+}
+
+#[derive(Serialize, Deserialize, Insertable, Clone, Debug)]
+#[diesel(table_name = class_indexes)]
+pub struct NewClassIndex {
+    pub hubuum_class_id: i32,
+    pub json_path: String,
+}
+
+/// To declare an index from within a class we only need the JSON path.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NewClassIndexFromClass {
+    pub json_path: String,
+}
+
+impl HubuumClass {
+    /// Validate object data against the JSON schema of the class.
+    ///
+    /// Validation only happens if the class has a schema and `validate_schema` is set,
+    /// otherwise any data is accepted.
+    ///
+    /// ### Returns
+    ///
+    /// * Nothing if the data is valid, or an ApiError::ValidationError listing the validation errors.
+    pub fn validate_data(&self, data: &serde_json::Value) -> Result<(), ApiError> {
+        let schema = match (&self.json_schema, self.validate_schema) {
+            (Some(schema), true) => schema,
+            _ => return Ok(()),
+        };
+
+        let compiled = jsonschema::JSONSchema::compile(schema).map_err(|e| {
+            ApiError::BadRequest(format!("Class {} has an invalid schema: {}", self.id, e))
+        })?;
+
+        if let Err(errors) = compiled.validate(data) {
+            let errors: Vec<String> = errors.map(|e| e.to_string()).collect();
+            return Err(ApiError::ValidationError(format!(
+                "Data does not validate against the schema of class {}: {}",
+                self.id,
+                errors.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+}
+
 pub async fn total_class_count(pool: &DbPool) -> Result<i64, ApiError> {
     use crate::schema::hubuumclass::dsl::*;
 
@@ -47,6 +47,8 @@ pub struct Group {
     pub description: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    /// Permissions granted to inactive groups are ignored.
+    pub active: bool,
 }
 
 impl SelfAccessors<Group> for Group {
@@ -59,7 +61,31 @@ impl SelfAccessors<Group> for Group {
     }
 }
 
+/// The data that deleting a group would remove, see [`Group::deletion_impact`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GroupDeletionImpact {
+    pub members: i64,
+    pub permission_grants: i64,
+}
+
 impl Group {
+    /// Count the data that deleting this group would remove, without deleting anything.
+    pub async fn deletion_impact(&self, pool: &DbPool) -> Result<GroupDeletionImpact, ApiError> {
+        use crate::schema::{permissions, user_groups};
+
+        let mut conn = pool.get()?;
+        Ok(GroupDeletionImpact {
+            members: user_groups::table
+                .filter(user_groups::group_id.eq(self.id))
+                .count()
+                .get_result(&mut conn)?,
+            permission_grants: permissions::table
+                .filter(permissions::group_id.eq(self.id))
+                .count()
+                .get_result(&mut conn)?,
+        })
+    }
+
     pub async fn members(&self, pool: &DbPool) -> Result<Vec<User>, ApiError> {
         use crate::schema::user_groups::dsl::{group_id, user_groups, user_id};
         use crate::schema::users::dsl::*;
@@ -133,6 +159,8 @@ impl NewGroup {
 #[diesel(table_name = groups)]
 pub struct UpdateGroup {
     pub groupname: Option<String>,
+    /// Deactivate (or reactivate) the group without deleting its grants.
+    pub active: Option<bool>,
 }
 
 impl UpdateGroup {
@@ -8,6 +8,7 @@ pub mod output;
 pub mod permissions;
 pub mod relation;
 pub mod search;
+pub mod stats;
 pub mod token;
 pub mod user;
 pub mod user_group;
@@ -21,6 +22,7 @@ pub use crate::models::object::*;
 pub use crate::models::output::*;
 pub use crate::models::permissions::*;
 pub use crate::models::relation::*;
+pub use crate::models::stats::*;
 pub use crate::models::token::*;
 pub use crate::models::user::*;
 pub use crate::models::user_group::*;
@@ -13,6 +13,7 @@ use crate::db::DbPool;
 use crate::schema::namespaces;
 
 use crate::errors::ApiError;
+use crate::db::scope::{namespace_scope, scope_permissions};
 
 use crate::models::output::GroupPermission;
 use crate::models::{Permission, Permissions};
@@ -32,6 +33,10 @@ pub struct Namespace {
     pub description: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    pub write_rate_limit: Option<i32>,
+    pub object_quota: Option<i32>,
+    pub default_class_permissions: Option<Vec<String>>,
+    pub attachment_size_limit: Option<i64>,
 }
 
 #[derive(Serialize, Debug, Deserialize, Copy, Clone)]
@@ -44,6 +49,115 @@ pub struct UpdateNamespace {
     pub description: Option<String>,
 }
 
+/// The body of a namespace update, the name and description along with the settings.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NamespacePatch {
+    #[serde(flatten)]
+    pub namespace: UpdateNamespace,
+    #[serde(flatten)]
+    pub settings: UpdateNamespaceSettings,
+}
+
+/// Changes to the settings of a namespace.
+///
+/// A setting that is left out is kept as is, and a setting given as `null` is cleared.
+///
+/// The object quota, the default class permissions and the attachment size limit are stored for
+/// the features that will enforce them, and have no effect yet. The write rate limit overrides
+/// `HUBUUM_NAMESPACE_WRITE_RATE_LIMIT` for the namespace, where 0 disables the limit and `null`
+/// goes back to the configured default.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, AsChangeset)]
+#[diesel(table_name = namespaces)]
+pub struct UpdateNamespaceSettings {
+    /// The number of objects the namespace may hold. Not enforced yet.
+    #[serde(default, deserialize_with = "nullable")]
+    pub object_quota: Option<Option<i32>>,
+    /// The permissions granted on new classes in the namespace. Not applied yet.
+    #[serde(default, deserialize_with = "nullable")]
+    pub default_class_permissions: Option<Option<Vec<String>>>,
+    /// The size of attachments in bytes. Not enforced yet.
+    #[serde(default, deserialize_with = "nullable")]
+    pub attachment_size_limit: Option<Option<i64>>,
+    /// The writes per minute allowed in the namespace. Only admins may change it, as the limit
+    /// protects the instance rather than the namespace.
+    #[serde(default, deserialize_with = "nullable")]
+    pub write_rate_limit: Option<Option<i32>>,
+}
+
+impl UpdateNamespaceSettings {
+    /// Whether any setting is to be changed.
+    pub fn is_empty(&self) -> bool {
+        self == &UpdateNamespaceSettings::default()
+    }
+
+    /// Whether any of the settings delegated to the namespace, ie all but the write rate
+    /// limit, is to be changed.
+    pub fn changes_delegated(&self) -> bool {
+        self.object_quota.is_some()
+            || self.default_class_permissions.is_some()
+            || self.attachment_size_limit.is_some()
+    }
+
+    /// Check that the settings are within their ranges.
+    ///
+    /// The object quota, the attachment size limit and the write rate limit can not be negative,
+    /// and the default class permissions must be class, object or relation permissions, as the
+    /// collection permissions do not apply to classes.
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if let Some(Some(quota)) = self.object_quota {
+            if quota < 0 {
+                return Err(ApiError::BadRequest(
+                    "The object quota can not be negative".to_string(),
+                ));
+            }
+        }
+
+        if let Some(Some(limit)) = self.attachment_size_limit {
+            if limit < 0 {
+                return Err(ApiError::BadRequest(
+                    "The attachment size limit can not be negative".to_string(),
+                ));
+            }
+        }
+
+        if let Some(Some(limit)) = self.write_rate_limit {
+            if limit < 0 {
+                return Err(ApiError::BadRequest(
+                    "The write rate limit can not be negative".to_string(),
+                ));
+            }
+        }
+
+        if let Some(Some(permissions)) = &self.default_class_permissions {
+            for permission in permissions {
+                match Permissions::from_string(permission)? {
+                    Permissions::ReadCollection
+                    | Permissions::UpdateCollection
+                    | Permissions::DeleteCollection
+                    | Permissions::DelegateCollection => {
+                        return Err(ApiError::BadRequest(format!(
+                            "'{}' is not a class permission",
+                            permission
+                        )))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserialize a field that may be absent, `null` or set, so that `null` is `Some(None)`.
+pub(crate) fn nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
 /// A new namespace, with an assignee. Used for creating new namespace entries
 /// into the database and assign all permissions to the group given as group_id.
 ///
@@ -56,6 +170,24 @@ pub struct NewNamespaceWithAssignee {
     pub group_id: i32,
 }
 
+/// A transfer of namespace ownership from one group to another.
+///
+/// The new owner is granted every permission on the namespace. Unless `keep_access` is set,
+/// all permissions of the previous owner are revoked.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NamespaceTransfer {
+    pub from_group_id: i32,
+    pub to_group_id: i32,
+    #[serde(default)]
+    pub keep_access: bool,
+}
+
+/// The query of a search within a namespace, see `GET /api/v1/namespaces/{namespace_id}/search`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NamespaceSearchOptions {
+    pub q: String,
+}
+
 /// A new namespace, without an assignee. Used for creating new namespace entries
 /// into the database.
 ///
@@ -90,6 +222,10 @@ pub async fn user_on<T: NamespaceAccessors>(
     let mut conn = pool.get()?;
     let namespace_target_id = namespace_ref.namespace_id(pool).await?;
 
+    if namespace_scope().is_some_and(|scope| scope != namespace_target_id) {
+        return Ok(vec![]);
+    }
+
     let group_ids_subquery = user_id.group_ids_subquery();
 
     let query = groups
@@ -114,7 +250,7 @@ pub async fn user_on<T: NamespaceAccessors>(
 ///
 /// ## Returns
 /// * Ok(Vec<Namespace>) - List of namespaces the user has the requested permission for.
-///                        If no matching namespaces are found, an empty list is returned
+///   If no matching namespaces are found, an empty list is returned
 /// * Err(ApiError) - On query errors only.
 pub async fn user_can_on_any<U: SelfAccessors<User> + GroupAccessors>(
     pool: &DbPool,
@@ -138,7 +274,7 @@ pub async fn user_can_on_any<U: SelfAccessors<User> + GroupAccessors>(
             .filter(group_id.eq_any(group_ids_subquery))
     };
 
-    let filtered_query = permission_type.create_boxed_filter(base_query, true);
+    let filtered_query = permission_type.create_boxed_filter(scope_permissions(base_query), true);
 
     let accessible_namespace_ids = filtered_query.select(namespace_id).load::<i32>(&mut conn)?;
 
@@ -540,7 +676,7 @@ mod tests {
 
             // Revoke this subset of permissions
             namespace
-                .revoke(&pool, group_id, PermissionsList::new(subset.clone()))
+                .revoke(&pool, group_id, PermissionsList::new(subset.clone()), None)
                 .await
                 .unwrap();
 
@@ -636,7 +772,7 @@ mod tests {
         }
 
         namespace
-            .revoke_one(&pool, group_id, NP::UpdateCollection)
+            .revoke_one(&pool, group_id, NP::UpdateCollection, None)
             .await
             .unwrap();
 
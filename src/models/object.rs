@@ -1,11 +1,11 @@
 use diesel::prelude::*;
 use diesel::sql_query;
-use diesel::sql_types::{BigInt, Integer, Jsonb, Text, Timestamp};
+use diesel::sql_types::{BigInt, Integer, Jsonb, Nullable, Text, Timestamp};
 use serde::{Deserialize, Serialize};
 
 use crate::db::DbPool;
 use crate::errors::ApiError;
-use crate::schema::hubuumobject;
+use crate::schema::{hubuumobject, validation_audit};
 
 #[derive(Serialize, Deserialize, Queryable, Clone, PartialEq, Debug, QueryableByName)]
 #[diesel(table_name = hubuumobject)]
@@ -27,6 +27,8 @@ pub struct HubuumObject {
     pub created_at: chrono::NaiveDateTime,
     #[diesel(sql_type = Timestamp)]
     pub updated_at: chrono::NaiveDateTime,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub external_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Insertable)]
@@ -37,7 +39,47 @@ pub struct NewHubuumObject {
     pub hubuum_class_id: i32,
     pub data: serde_json::Value,
     pub description: String,
+    pub external_id: Option<String>,
 }
+/// Options for batch requests on objects.
+///
+/// By default a batch is atomic, and fails as a whole if any element fails. With `atomic=false`
+/// every element is processed on its own and the result of each is reported with a 207.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BatchOptions {
+    #[serde(default = "default_atomic")]
+    pub atomic: bool,
+}
+
+fn default_atomic() -> bool {
+    true
+}
+
+/// Options for writing an object.
+///
+/// With `skip_validation` set, the data is not validated against the schema of the class. This
+/// is an escape hatch for data migrations, and is only allowed for admins.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ObjectWriteOptions {
+    #[serde(default)]
+    pub skip_validation: bool,
+}
+
+/// An entry in the validation audit trail, recording an object written by an admin without
+/// schema validation.
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = validation_audit)]
+pub struct NewValidationAudit {
+    pub object_id: i32,
+    pub class_id: i32,
+    pub user_id: i32,
+    pub action: String,
+}
+
+/// Changes to an object.
+///
+/// A field that is left out is kept as is. The external ID can also be cleared by giving it as
+/// `null`.
 #[derive(Serialize, Deserialize, Clone, AsChangeset)]
 #[diesel(table_name = hubuumobject)]
 pub struct UpdateHubuumObject {
@@ -46,6 +88,32 @@ pub struct UpdateHubuumObject {
     pub hubuum_class_id: Option<i32>,
     pub data: Option<serde_json::Value>,
     pub description: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "crate::models::namespace::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub external_id: Option<Option<String>>,
+}
+
+/// Changes to an object in a batch update, which is identified by its ID.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BatchUpdateHubuumObject {
+    pub id: i32,
+    #[serde(flatten)]
+    pub update: UpdateHubuumObject,
+}
+
+/// The payload for creating or updating an object by its external ID.
+///
+/// The class and the external ID are given by the path, the namespace is used to look up
+/// any existing object with the same external ID.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UpsertHubuumObject {
+    pub name: String,
+    pub namespace_id: i32,
+    pub data: serde_json::Value,
+    pub description: String,
 }
 
 // For retruning the IDs in raw sql queries, which is used
@@ -79,6 +147,7 @@ pub struct HubuumObjectWithPath {
     pub description: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    pub external_id: Option<String>,
     pub path: Vec<i32>,
 }
 
@@ -174,6 +243,7 @@ pub mod tests {
             hubuum_class_id,
             data: object_data,
             description: "Test object".to_string(),
+            external_id: None,
         };
         object.save(pool).await
     }
@@ -212,4 +282,50 @@ pub mod tests {
 
         namespace.delete(&pool).await.unwrap();
     }
+
+    #[actix_rt::test]
+    async fn test_external_id_scope() {
+        use crate::config::ExternalIdScope;
+        use crate::models::traits::object::ensure_external_id_available;
+
+        let (pool, _) = get_pool_and_config().await;
+        let namespace = create_namespace(&pool, "object_external_id_scope")
+            .await
+            .unwrap();
+        let class = create_class(&pool, &namespace, "test external id scope").await;
+
+        let object = NewHubuumObject {
+            name: "external id scope object".to_string(),
+            namespace_id: namespace.id,
+            hubuum_class_id: class.id,
+            data: serde_json::json!({}),
+            description: "Test object".to_string(),
+            external_id: Some("object_external_id_scope".to_string()),
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let mut conn = pool.get().unwrap();
+        let eid = "object_external_id_scope";
+
+        // Namespace scoped IDs are left to the database to enforce
+        assert!(
+            ensure_external_id_available(&mut conn, ExternalIdScope::Namespace, eid, None).is_ok()
+        );
+
+        // Globally scoped IDs may not be reused by other objects...
+        match ensure_external_id_available(&mut conn, ExternalIdScope::Global, eid, None) {
+            Err(ApiError::Conflict(_)) => (),
+            other => panic!("Expected conflict, got {:?}", other),
+        }
+
+        // ...but the object itself may keep its ID
+        assert!(
+            ensure_external_id_available(&mut conn, ExternalIdScope::Global, eid, Some(object.id))
+                .is_ok()
+        );
+
+        namespace.delete(&pool).await.unwrap();
+    }
 }
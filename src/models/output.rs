@@ -3,7 +3,9 @@
 
 // A typical use is to combine the output of multiple models into a single response
 
-use crate::models::{Group, HubuumClass, Namespace, Permission};
+use crate::errors::ApiError;
+use crate::models::{Group, HubuumClass, HubuumObject, Namespace, Permission};
+use actix_web::{http::StatusCode, ResponseError};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -12,6 +14,13 @@ pub struct GroupPermission {
     pub permission: Permission,
 }
 
+/// A namespace holding objects of a given class, and how many of them it holds.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NamespaceObjectCount {
+    pub namespace: Namespace,
+    pub object_count: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct HubuumClassExpanded {
     pub id: i32,
@@ -24,6 +33,50 @@ pub struct HubuumClassExpanded {
     pub updated_at: chrono::NaiveDateTime,
 }
 
+/// The JSON schema of a class, with just enough of the class to tell where it belongs.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HubuumClassSchema {
+    pub id: i32,
+    pub name: String,
+    pub namespace_id: i32,
+    pub namespace: String,
+    pub json_schema: Option<serde_json::Value>,
+}
+
+impl From<HubuumClassExpanded> for HubuumClassSchema {
+    fn from(class: HubuumClassExpanded) -> Self {
+        HubuumClassSchema {
+            id: class.id,
+            name: class.name,
+            namespace_id: class.namespace.id,
+            namespace: class.namespace.name,
+            json_schema: class.json_schema,
+        }
+    }
+}
+
+/// An object with its directly related objects embedded, see `expand` in object searches.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HubuumObjectWithRelations {
+    #[serde(flatten)]
+    pub object: HubuumObject,
+    pub related_object_ids: Vec<i32>,
+    /// The names of the related objects, in the same order as the IDs, if asked for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub related_object_names: Option<Vec<String>>,
+}
+
+/// A class or an object, tagged with its kind when serialized.
+///
+/// Used when searching classes and objects at once, eg `{"kind": "class", "id": 1, ...}` or
+/// `{"kind": "object", "id": 2, ...}`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ClassOrObject {
+    Class(HubuumClassExpanded),
+    Object(HubuumObject),
+}
+
 impl PartialEq<HubuumClass> for HubuumClassExpanded {
     fn eq(&self, other: &HubuumClass) -> bool {
         self.id == other.id
@@ -49,3 +102,37 @@ impl PartialEq<HubuumClassExpanded> for HubuumClass {
             && self.updated_at == other.updated_at
     }
 }
+
+/// The result of a single element in a non-atomic batch request.
+///
+/// Successful elements carry the ID of the entity they created or modified, failed elements
+/// carry the error body they would have gotten as a single request.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<serde_json::Value>,
+}
+
+impl BatchItemResult {
+    pub fn success(index: usize, status: StatusCode, id: i32) -> Self {
+        BatchItemResult {
+            index,
+            status: status.as_u16(),
+            id: Some(id),
+            error: None,
+        }
+    }
+
+    pub fn failure(index: usize, error: &ApiError) -> Self {
+        BatchItemResult {
+            index,
+            status: error.status_code().as_u16(),
+            id: None,
+            error: Some(error.to_json()),
+        }
+    }
+}
@@ -3,7 +3,10 @@ use std::{fmt, fmt::Display, slice};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{errors::ApiError, schema::permissions};
+use crate::{
+    errors::ApiError,
+    schema::{permission_audit, permissions},
+};
 
 use super::search::ParsedQueryParam;
 
@@ -298,6 +301,36 @@ pub struct NewPermission {
     pub has_delete_object_relation: bool,
 }
 
+impl NewPermission {
+    /// Every permission on the namespace, as given to the owning group of a namespace.
+    pub fn all(namespace_id: i32, group_id: i32) -> Self {
+        NewPermission {
+            namespace_id,
+            group_id,
+            has_read_namespace: true,
+            has_update_namespace: true,
+            has_delete_namespace: true,
+            has_delegate_namespace: true,
+            has_create_class: true,
+            has_read_class: true,
+            has_update_class: true,
+            has_delete_class: true,
+            has_create_object: true,
+            has_read_object: true,
+            has_update_object: true,
+            has_delete_object: true,
+            has_create_class_relation: true,
+            has_read_class_relation: true,
+            has_update_class_relation: true,
+            has_delete_class_relation: true,
+            has_create_object_relation: true,
+            has_read_object_relation: true,
+            has_update_object_relation: true,
+            has_delete_object_relation: true,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, AsChangeset, Default)]
 #[diesel(table_name = permissions)]
 pub struct UpdatePermission {
@@ -322,3 +355,41 @@ pub struct UpdatePermission {
     pub has_update_object_relation: Option<bool>,
     pub has_delete_object_relation: Option<bool>,
 }
+
+/// An entry in the permission audit trail.
+///
+/// `permissions` is a comma separated list of the permissions affected, or `None` if all the
+/// permissions of the group on the namespace were affected.
+#[derive(Debug, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = permission_audit)]
+pub struct NewPermissionAudit {
+    pub namespace_id: i32,
+    pub group_id: i32,
+    pub action: String,
+    pub permissions: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl NewPermissionAudit {
+    /// An audit entry for revoking permissions from a group, `None` meaning all permissions.
+    pub fn revocation(
+        namespace_id: i32,
+        group_id: i32,
+        permission_list: Option<&PermissionsList<Permissions>>,
+        reason: Option<&str>,
+    ) -> Self {
+        NewPermissionAudit {
+            namespace_id,
+            group_id,
+            action: "revoke".to_string(),
+            permissions: permission_list.map(|list| list.to_string()),
+            reason: reason.map(|r| r.to_string()),
+        }
+    }
+}
+
+/// Query parameters for revoking permissions. The reason is recorded in the audit trail only.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct RevokePermissionOptions {
+    pub reason: Option<String>,
+}
@@ -53,6 +53,38 @@ pub struct HubuumObjectRelation {
     pub updated_at: chrono::NaiveDateTime,
 }
 
+/// An object relation as seen from the object it was requested from.
+///
+/// Relations are stored with the lowest object ID as `from`, so when a relation is requested from
+/// the other end, `reversed` is set to tell the client that the stored direction is the opposite
+/// of the requested one.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct HubuumObjectRelationWithDirection {
+    #[serde(flatten)]
+    pub relation: HubuumObjectRelation,
+    pub reversed: bool,
+}
+
+impl HubuumObjectRelationWithDirection {
+    pub fn new(relation: HubuumObjectRelation, requested_from: i32) -> Self {
+        HubuumObjectRelationWithDirection {
+            reversed: relation.from_hubuum_object_id != requested_from,
+            relation,
+        }
+    }
+}
+
+/// A class or object relation, tagged with its kind when serialized.
+///
+/// Used when searching across both relation kinds at once, eg
+/// `{"kind": "class", "id": 1, ...}` or `{"kind": "object", "id": 2, ...}`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AnyRelation {
+    Class(HubuumClassRelation),
+    Object(HubuumObjectRelation),
+}
+
 #[derive(Debug, Serialize, Deserialize, Insertable)]
 #[diesel(table_name = hubuumobject_relation)]
 pub struct NewHubuumObjectRelation {
@@ -85,6 +117,25 @@ pub struct HubuumClassRelationTransitive {
     pub path: Vec<Option<i32>>,
 }
 
+/// A chain of class relations connecting two classes, as the class IDs along the chain.
+///
+/// The path starts with the class it was requested from and ends with the class it was
+/// requested to, and `depth` is the number of relations in it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct HubuumClassRelationPath {
+    pub depth: i32,
+    pub path: Vec<i32>,
+}
+
+/// Query parameters for resolving the paths between two classes.
+///
+/// With `shortest` set, only a path with the fewest relations is returned.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ClassRelationPathOptions {
+    #[serde(default)]
+    pub shortest: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, QueryableByName, Clone)]
 pub struct HubuumObjectTransitiveLink {
     #[diesel(sql_type = Integer)]
@@ -93,6 +144,17 @@ pub struct HubuumObjectTransitiveLink {
     path: Vec<i32>,
 }
 
+/// The readable objects directly related to an object, in either direction, sorted by ID.
+#[derive(Debug, QueryableByName, Clone)]
+pub struct RelatedObjects {
+    #[diesel(sql_type = Integer)]
+    pub object_id: i32,
+    #[diesel(sql_type = Array<Integer>)]
+    pub related_ids: Vec<i32>,
+    #[diesel(sql_type = Array<Text>)]
+    pub related_names: Vec<String>,
+}
+
 #[derive(Debug, Queryable, Selectable, Serialize, Deserialize)]
 #[diesel(table_name = class_closure_view)]
 pub struct ClassClosureView {
@@ -137,6 +199,8 @@ pub struct ObjectClosureView {
     pub descendant_created_at: chrono::NaiveDateTime,
     pub ancestor_updated_at: chrono::NaiveDateTime,
     pub descendant_updated_at: chrono::NaiveDateTime,
+    pub ancestor_external_id: Option<String>,
+    pub descendant_external_id: Option<String>,
 }
 
 #[cfg(test)]
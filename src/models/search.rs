@@ -3,16 +3,33 @@ use actix_web::web::Json;
 use chrono::{format, DateTime, NaiveDateTime, Utc};
 use diesel::dsl::Filter;
 use diesel::sql_types::Bool;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{collections::HashSet, f32::consts::E};
 use tracing::debug;
 
 use crate::models::permissions::{Permissions, PermissionsList};
+use crate::traits::SelfAccessors;
 use crate::utilities::extensions::CustomStringExtensions;
 use crate::{errors::ApiError, schema::hubuumobject::data};
 
 use super::Permission;
 
+/// The default for the maximum number of parameters in a query string, see
+/// [`set_max_query_params`].
+pub const DEFAULT_MAX_QUERY_PARAMS: usize = 100;
+
+static MAX_QUERY_PARAMS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_QUERY_PARAMS);
+
+/// Set the maximum number of parameters [`parse_query_parameter`] accepts in a query string.
+///
+/// This is set from `HUBUUM_MAX_QUERY_PARAMS` at startup.
+pub fn set_max_query_params(max: usize) {
+    MAX_QUERY_PARAMS.store(max, Ordering::Relaxed);
+}
+
 /// ## Parse a query string into search parameters
 ///
 /// ## Arguments
@@ -22,6 +39,7 @@ use super::Permission;
 /// ## Returns
 ///
 /// * A vector of parsed query parameters or ApiError::BadRequest if the query string is invalid
+///   or has more parameters than allowed (see [`set_max_query_params`])
 pub fn parse_query_parameter(query_string: &str) -> Result<Vec<ParsedQueryParam>, ApiError> {
     let mut parsed_query_params = Vec::new();
 
@@ -30,6 +48,16 @@ pub fn parse_query_parameter(query_string: &str) -> Result<Vec<ParsedQueryParam>
         return Ok(parsed_query_params);
     }
 
+    // Check the count before doing any work per parameter. Counting stops right after the
+    // limit, so an oversized query string is rejected without scanning all of it.
+    let max_params = MAX_QUERY_PARAMS.load(Ordering::Relaxed);
+    if query_string.split('&').take(max_params + 1).count() > max_params {
+        return Err(ApiError::BadRequest(format!(
+            "Too many query parameters, at most {} are allowed",
+            max_params
+        )));
+    }
+
     for query_param in query_string.split('&') {
         let query_param_parts: Vec<&str> = query_param.splitn(2, '=').collect();
 
@@ -80,6 +108,192 @@ pub fn parse_query_parameter(query_string: &str) -> Result<Vec<ParsedQueryParam>
     Ok(parsed_query_params)
 }
 
+/// The maximum number of conditions in a search body, see [`parse_query_body`].
+pub const MAX_SEARCH_CONDITIONS: usize = 64;
+
+/// Fields that control paging, ordering or the shape of the response rather than which objects
+/// match, and therefore can't be used in a search body.
+const NON_FILTER_FIELDS: &[FilterField] = &[
+    FilterField::Limit,
+    FilterField::Offset,
+    FilterField::OrderBy,
+    FilterField::Seed,
+    FilterField::Diagnostics,
+    FilterField::Shape,
+    FilterField::Expand,
+];
+
+/// ## A filter in a JSON search body
+///
+/// A filter is either a condition or a group of other filters. A condition has a `field`, an
+/// optional `operator` (defaulting to `equals`) and a `value`, using the same fields and operators
+/// as the query string, so `{"field": "name", "operator": "icontains", "value": "web"}` is the same
+/// as `name__icontains=web`. A group is either `{"and": [...]}` or `{"or": [...]}`.
+///
+/// Values may be strings, numbers, booleans, or arrays of these (which are joined with commas,
+/// like lists in the query string). An empty filter (`{}`) matches everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub and: Option<Vec<SearchFilter>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub or: Option<Vec<SearchFilter>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+}
+
+/// ## A boolean tree of parsed query parameters
+///
+/// This is what a [`SearchFilter`] is parsed into. The leaves are the same [`ParsedQueryParam`]s
+/// that [`parse_query_parameter`] produces, so the tree can be executed by the same search
+/// filters, with the groups combined into a single query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryNode {
+    Param(ParsedQueryParam),
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+}
+
+impl QueryNode {
+    /// The conditions of the tree, depth first and left to right.
+    pub fn params(&self) -> Vec<&ParsedQueryParam> {
+        match self {
+            QueryNode::Param(param) => vec![param],
+            QueryNode::And(children) | QueryNode::Or(children) => {
+                children.iter().flat_map(|child| child.params()).collect()
+            }
+        }
+    }
+}
+
+/// ## Parse a JSON search body into a boolean tree of search parameters
+///
+/// This is the JSON counterpart to [`parse_query_parameter`], see [`SearchFilter`] for the format.
+///
+/// ## Arguments
+///
+/// * `filter` - The filter from the search body
+///
+/// ## Returns
+///
+/// * A tree of parsed query parameters or ApiError::BadRequest if the filter is invalid, has
+///   more than [`MAX_SEARCH_CONDITIONS`] conditions, or uses paging or ordering fields.
+pub fn parse_query_body(filter: &SearchFilter) -> Result<QueryNode, ApiError> {
+    let tree = parse_query_node(filter)?;
+
+    let params = tree.params();
+    if params.len() > MAX_SEARCH_CONDITIONS {
+        return Err(ApiError::BadRequest(format!(
+            "Search filter is too complex, it has {} conditions and at most {} are allowed",
+            params.len(),
+            MAX_SEARCH_CONDITIONS
+        )));
+    }
+
+    if let Some(param) = params.iter().find(|p| NON_FILTER_FIELDS.contains(&p.field)) {
+        return Err(ApiError::BadRequest(format!(
+            "Field '{}' can't be used in a search filter",
+            param.field
+        )));
+    }
+
+    Ok(tree)
+}
+
+fn parse_query_node(filter: &SearchFilter) -> Result<QueryNode, ApiError> {
+    let is_condition =
+        filter.field.is_some() || filter.operator.is_some() || filter.value.is_some();
+
+    match (&filter.and, &filter.or, is_condition) {
+        (None, None, false) => Ok(QueryNode::And(vec![])),
+        (Some(children), None, false) => Ok(QueryNode::And(
+            children
+                .iter()
+                .map(parse_query_node)
+                .collect::<Result<_, _>>()?,
+        )),
+        (None, Some(children), false) => {
+            if children.is_empty() {
+                return Err(ApiError::BadRequest(
+                    "Invalid search filter: 'or' requires at least one filter".to_string(),
+                ));
+            }
+            Ok(QueryNode::Or(
+                children
+                    .iter()
+                    .map(parse_query_node)
+                    .collect::<Result<_, _>>()?,
+            ))
+        }
+        (None, None, true) => {
+            let field = filter.field.as_deref().ok_or_else(|| {
+                ApiError::BadRequest("Invalid search filter: condition without 'field'".to_string())
+            })?;
+
+            let value = match &filter.value {
+                Some(value) => search_value_to_string(field, value)?,
+                None => {
+                    return Err(ApiError::BadRequest(format!(
+                        "Invalid search filter: no value for '{}'",
+                        field
+                    )))
+                }
+            };
+
+            let operator = match &filter.operator {
+                Some(operator) => Some(SearchOperator::new_from_string(operator)?),
+                None => None,
+            };
+
+            Ok(QueryNode::Param(ParsedQueryParam::new(
+                field, operator, &value,
+            )?))
+        }
+        _ => Err(ApiError::BadRequest(
+            "Invalid search filter: a filter must be exactly one of 'and', 'or', or a condition"
+                .to_string(),
+        )),
+    }
+}
+
+/// Convert a JSON value from a search body into the string form used by the query string.
+fn search_value_to_string(field: &str, value: &serde_json::Value) -> Result<String, ApiError> {
+    use serde_json::Value;
+
+    let scalar = |value: &Value| match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    };
+
+    let value = match value {
+        Value::Array(items) => items
+            .iter()
+            .map(scalar)
+            .collect::<Option<Vec<String>>>()
+            .map(|items| items.join(",")),
+        value => scalar(value),
+    };
+
+    match value {
+        Some(value) if !value.is_empty() => Ok(value),
+        Some(_) => Err(ApiError::BadRequest(format!(
+            "Invalid search filter: no value for '{}'",
+            field
+        ))),
+        None => Err(ApiError::BadRequest(format!(
+            "Invalid search filter: value for '{}' must be a string, number, boolean, or a list of these",
+            field
+        ))),
+    }
+}
+
 /// ## A struct that represents a parsed query parameter
 ///  
 /// This struct holds a field, operator, and values for a search.
@@ -119,13 +333,56 @@ pub struct SQLComponent {
 ///
 /// This enum represents the different types of values that can be bound to a SQL query. The types
 /// are defined as we need to bind the correct type in Diesel.
+///
+/// `Numeric` holds a decimal literal that fits neither an `i64` nor (exactly) an `f64`. It is bound
+/// as text and cast to `numeric` in the query, so no precision is lost on the way to Postgres.
+///
+/// `Null` is the JSON `null` literal, not SQL NULL. It is bound as the text `null` and cast to
+/// `jsonb` in the query, so it matches keys whose value is `null` but not missing keys.
 #[derive(Debug, Clone, PartialEq)]
 pub enum SQLValue {
     String(String),
     Float(f64),
     Integer(i32),
+    BigInt(i64),
+    Numeric(String),
     Date(NaiveDateTime),
     Boolean(bool),
+    Null,
+}
+
+impl SQLValue {
+    /// ## Create the narrowest numeric SQLValue that can hold the value exactly
+    ///
+    /// Integers are bound as `Integer` if they fit in an `i32` and as `BigInt` if they fit in an
+    /// `i64`. Anything else that is a plain decimal literal (an optional sign, digits, and an
+    /// optional fraction) is bound as `Numeric`.
+    ///
+    /// ### Returns
+    ///
+    /// * A numeric SQLValue or ApiError::BadRequest if the value is not a decimal number
+    pub fn from_numeric_str(value: &str) -> Result<SQLValue, ApiError> {
+        if let Ok(i) = value.parse::<i32>() {
+            return Ok(SQLValue::Integer(i));
+        }
+
+        if let Ok(i) = value.parse::<i64>() {
+            return Ok(SQLValue::BigInt(i));
+        }
+
+        let digits = value.strip_prefix(['-', '+']).unwrap_or(value);
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, "0"));
+        let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+        if is_digits(whole) && is_digits(fraction) {
+            Ok(SQLValue::Numeric(value.to_string()))
+        } else {
+            Err(ApiError::BadRequest(format!(
+                "Invalid numeric value: '{}'",
+                value
+            )))
+        }
+    }
 }
 
 impl ParsedQueryParam {
@@ -180,13 +437,18 @@ impl ParsedQueryParam {
         self.is_json_schema() || self.is_json_data()
     }
 
-    /// ## Coerce the value into a Permissions enum
+    /// ## Coerce the value into a list of Permissions enums
+    ///
+    /// The value may hold several permissions separated by commas, eg `ReadClass,UpdateClass`.
     ///
     /// ### Returns
     ///
-    /// * A Permissions enum or ApiError::BadRequest if the value is invalid
-    pub fn value_as_permission(&self) -> Result<Permissions, ApiError> {
-        self.value.as_permission()
+    /// * A vector of Permissions enums or ApiError::BadRequest naming the first invalid entry
+    pub fn value_as_permissions(&self) -> Result<Vec<Permissions>, ApiError> {
+        self.value
+            .split(',')
+            .map(|permission| permission.trim().as_permission())
+            .collect()
     }
 
     /// ## Coerce the value into a list of integers
@@ -197,9 +459,35 @@ impl ParsedQueryParam {
     ///
     /// * A vector of integers or ApiError::BadRequest if the value is invalid
     pub fn value_as_integer(&self) -> Result<Vec<i32>, ApiError> {
+        self.validate_value_list()?;
         self.value.as_integer()
     }
 
+    /// ## Coerce the value into a single non-negative count
+    ///
+    /// Used for paging parameters like `limit` and `offset`, which only support the plain
+    /// `equals` operator.
+    ///
+    /// ### Returns
+    ///
+    /// * The count, or ApiError::BadRequest if the value is not a single non-negative integer
+    pub fn value_as_count(&self) -> Result<i64, ApiError> {
+        if self.operator != (SearchOperator::Equals { is_negated: false }) {
+            return Err(ApiError::OperatorMismatch(format!(
+                "Field '{}' only supports the equals operator",
+                self.field
+            )));
+        }
+
+        match self.value.parse::<i64>() {
+            Ok(count) if count >= 0 => Ok(count),
+            _ => Err(ApiError::BadRequest(format!(
+                "Field '{}' requires a non-negative integer, got '{}'",
+                self.field, self.value
+            ))),
+        }
+    }
+
     /// ## Coerce the value into a list of dates
     ///
     /// Accepts a comma separated list of RFC3339 dates.
@@ -209,9 +497,48 @@ impl ParsedQueryParam {
     ///
     /// * A vector of NaiveDateTime or ApiError::BadRequest if the value is invalid
     pub fn value_as_date(&self) -> Result<Vec<NaiveDateTime>, ApiError> {
+        self.validate_value_list()?;
         self.value.as_date()
     }
 
+    /// ## Validate the value as a comma separated list
+    ///
+    /// Lists may not contain empty (or blank) items, and the `between` operator requires
+    /// exactly two items (min,max). This catches degenerate values like `1,,2` or a lone
+    /// `between` bound before they reach the type specific parsers.
+    ///
+    /// ### Returns
+    ///
+    /// * Nothing if the list is valid, or ApiError::BadRequest if it is not
+    pub fn validate_value_list(&self) -> Result<(), ApiError> {
+        let items: Vec<&str> = self.value.split(',').map(|item| item.trim()).collect();
+
+        if items.iter().all(|item| item.is_empty()) {
+            return Err(ApiError::BadRequest(format!(
+                "Searching on field '{}' requires at least one value",
+                self.field
+            )));
+        }
+
+        if items.iter().any(|item| item.is_empty()) {
+            return Err(ApiError::BadRequest(format!(
+                "Empty item in value list '{}' for field '{}'",
+                self.value, self.field
+            )));
+        }
+
+        let (op, _) = self.operator.op_and_neg();
+        if op == Operator::Between && items.len() != 2 {
+            return Err(ApiError::BadRequest(format!(
+                "Operator 'between' requires exactly two values (min,max) for field '{}', got {}",
+                self.field,
+                items.len()
+            )));
+        }
+
+        Ok(())
+    }
+
     /// ## Coerce the value into a boolean
     ///
     /// Accepted values are "true" and "false" (case insensitive)
@@ -232,6 +559,7 @@ impl ParsedQueryParam {
     /// * The field must be a JSONB field (see `is_json`).
     /// * The value must be a key=value pair. The key is the JSONB
     ///   key to search in and the value is the value to search for.
+    /// * For `isnull`, the value is the key alone.
     ///
     /// The operator is used to determine the type of search to perform. In the future we
     /// may also use the schema to determine the type of the value.
@@ -261,26 +589,25 @@ impl ParsedQueryParam {
         // validatethe value and the operator against the defined type in the schema.
 
         let field = self.field.clone();
+        let (op, neg) = self.operator.op_and_neg();
 
-        // split the value on key=value
-        let parts: Vec<&str> = self.value.splitn(2, '=').collect();
-
-        let (key, value) = match parts.as_slice() {
-            [key, value] => (key, value),
-            _ => {
-                return Err(ApiError::BadRequest(
-                    "Expected exactly two parts of key=value".to_string(),
-                ))
+        // split the value on key=value, where the key may contain escaped equals signs. Null
+        // checks take the key alone.
+        let (key, value) = if op == Operator::IsNull {
+            (self.value.as_str(), "")
+        } else {
+            match split_json_key_value(&self.value) {
+                Some((key, value)) => (key, value),
+                None => {
+                    return Err(ApiError::BadRequest(
+                        "Expected exactly two parts of key=value".to_string(),
+                    ))
+                }
             }
         };
 
-        // Validate the key
-        if !key.is_valid_jsonb_search_key() {
-            return Err(ApiError::BadRequest(format!(
-                "Invalid JSON search key: '{}'",
-                key
-            )));
-        }
+        // Parsing the path validates the key
+        let path = parse_json_path(key)?;
 
         // Validate the value, no longer needed as we're using bind variables
         /*
@@ -292,7 +619,7 @@ impl ParsedQueryParam {
         }
         */
 
-        let key = format!("'{{{}}}'", key);
+        let key = json_path_literal(&path);
 
         // The bind variables for the SQL query. We can't bind the key as using
         // bind variables for the key itself is not supported in Postgres.
@@ -301,7 +628,6 @@ impl ParsedQueryParam {
         // TODO: Optionally validate that the keys exist:
         // https://github.com/terjekv/hubuum_rust/issues/4
 
-        let (op, neg) = self.operator.op_and_neg();
         let neg_str = if neg { "NOT " } else { "" };
 
         let sql_type = get_jsonb_field_type_from_value_and_operator(value, op.clone());
@@ -323,6 +649,7 @@ impl ParsedQueryParam {
             Operator::Gte => (">=", (*value).to_string()),
             Operator::Lt => ("<", (*value).to_string()),
             Operator::Lte => ("<=", (*value).to_string()),
+            Operator::IsNull => ("=", String::new()),
 
             _ => {
                 return Err(ApiError::BadRequest(format!(
@@ -339,7 +666,12 @@ impl ParsedQueryParam {
                     key, self.operator
                 )))
             }
-            Some(SQLMappedType::String) | Some(SQLMappedType::None) => {
+            Some(SQLMappedType::None) => {
+                // Compare the JSON value itself, as `#>>` turns a JSON null into SQL NULL.
+                bind_variables.push(SQLValue::Null);
+                format!("{}({} #> {}) = ?::jsonb", neg_str, field.table_field(), key)
+            }
+            Some(SQLMappedType::String) => {
                 bind_variables.push(SQLValue::String(value));
                 format!(
                     "{}{} #>> {} {} ?",
@@ -350,14 +682,19 @@ impl ParsedQueryParam {
                 )
             }
             Some(SQLMappedType::Numeric) => {
-                let ints = value.as_integer()?;
-                bind_variables.push(SQLValue::Integer(ints[0]));
+                let number = SQLValue::from_numeric_str(&value)?;
+                let placeholder = match number {
+                    SQLValue::Numeric(_) => "?::numeric",
+                    _ => "?",
+                };
+                bind_variables.push(number);
                 format!(
-                    "{}({} #>> {})::numeric {} ?",
+                    "{}({} #>> {})::numeric {} {}",
                     neg_str,
                     field.table_field(),
                     key,
-                    sql_op
+                    sql_op,
+                    placeholder
                 )
             }
             Some(SQLMappedType::Date) => {
@@ -434,6 +771,62 @@ pub trait QueryParamsExt {
     /// defined as having the `field` set as "json_data". Also validates both keys and values
     /// and their matching to the operator.
     fn json_datas(&self, filter: FilterField) -> Result<Vec<&ParsedQueryParam>, ApiError>;
+
+    /// ## Get all alternatives for a non-negated string match
+    ///
+    /// A string operator repeated on the same field is OR'ed together, so that
+    /// `name__like=web-%&name__like=db-%` matches either pattern. This returns every parameter
+    /// that takes part in the same OR-group as `param`, in the order they were given.
+    fn string_alternatives(&self, param: &ParsedQueryParam) -> Vec<&ParsedQueryParam>;
+
+    /// ## Get the class a list of parsed query parameters is restricted to, if any
+    ///
+    /// A search is restricted to a single class if all non-negated `equals` filters on the class
+    /// fields (`classes` or `class_id`) name the same single class. This is used to scope JSON
+    /// data searches so that the per-class expression indexes can be used.
+    fn single_class(&self) -> Option<i32>;
+
+    /// ## Get the classes a list of parsed query parameters asks for
+    ///
+    /// Collects the values of all non-negated `equals` filters on the class fields (`classes`
+    /// or `class_id`). The result is sorted and duplicates are removed.
+    ///
+    /// ### Returns
+    ///
+    /// * A vector of class IDs or ApiError::BadRequest if any of the class values are invalid
+    fn requested_classes(&self) -> Result<Vec<i32>, ApiError>;
+
+    /// ## Get the order a list of parsed query parameters asks for, if any
+    ///
+    /// The only supported order is `order_by=random`, optionally with a `seed` to get the same
+    /// order every time. Random ordering is meant for sampling together with `limit`, and can't
+    /// be combined with `offset`.
+    ///
+    /// ### Returns
+    ///
+    /// * The order, or ApiError::BadRequest if the order parameters are invalid
+    fn order(&self) -> Result<Option<SearchOrder>, ApiError>;
+
+    /// ## Get the response shape a list of parsed query parameters asks for
+    ///
+    /// `shape=array` (the default) returns the results as a list, `shape=map` as an object keyed
+    /// by ID.
+    ///
+    /// ### Returns
+    ///
+    /// * The shape, or ApiError::BadRequest if the shape is invalid
+    fn shape(&self) -> Result<ResponseShape, ApiError>;
+
+    /// ## Get the relation expansion a list of parsed query parameters asks for
+    ///
+    /// `expand=relations` embeds the IDs of the directly related objects in each object, and
+    /// `expand=relation_names` their names as well. Expansion is opt-in, so without `expand`
+    /// this returns None.
+    ///
+    /// ### Returns
+    ///
+    /// * The expansion, or ApiError::BadRequest if the expansion is invalid
+    fn relation_expansion(&self) -> Result<Option<RelationExpansion>, ApiError>;
 }
 
 impl QueryParamsExt for Vec<ParsedQueryParam> {
@@ -441,7 +834,8 @@ impl QueryParamsExt for Vec<ParsedQueryParam> {
     ///
     /// Iterate over the parsed query parameters and filter out the ones that are permissions,
     /// defined as having the `field` set as "permissions". For each value of a matching parsed query
-    /// parameter, attempt to parse it into a Permissions enum.
+    /// parameter, attempt to parse it into Permissions enums. A value may hold several
+    /// permissions separated by commas.
     ///
     /// Note that the list is not sorted and duplicates are removed.
     ///
@@ -449,12 +843,7 @@ impl QueryParamsExt for Vec<ParsedQueryParam> {
     fn permissions(&self) -> Result<PermissionsList<Permissions>, ApiError> {
         let mut unique_permissions = HashSet::new();
         for param in self.iter().filter(|p| p.is_permission()) {
-            match param.value_as_permission() {
-                Ok(permission) => {
-                    unique_permissions.insert(permission);
-                }
-                Err(e) => return Err(e),
-            }
+            unique_permissions.extend(param.value_as_permissions()?);
         }
         Ok(PermissionsList::new(unique_permissions))
     }
@@ -470,7 +859,7 @@ impl QueryParamsExt for Vec<ParsedQueryParam> {
 
         for p in self.iter() {
             if p.field == FilterField::Namespaces {
-                nids.extend(p.value.as_integer()?);
+                nids.extend(p.value_as_integer()?);
             }
         }
 
@@ -502,6 +891,204 @@ impl QueryParamsExt for Vec<ParsedQueryParam> {
 
         Ok(json_schema)
     }
+
+    /// ## Get all alternatives for a non-negated string match
+    ///
+    /// Parameters are alternatives if they share both field and operator. Negated operators are
+    /// never part of an OR-group, they are all applied (AND'ed) separately.
+    fn string_alternatives(&self, param: &ParsedQueryParam) -> Vec<&ParsedQueryParam> {
+        self.iter()
+            .filter(|p| {
+                p.field == param.field && p.operator == param.operator && !p.operator.op_and_neg().1
+            })
+            .collect()
+    }
+
+    fn single_class(&self) -> Option<i32> {
+        let mut class_ids = HashSet::new();
+        for p in self.iter().filter(|p| {
+            matches!(p.field, FilterField::Classes | FilterField::ClassId)
+                && p.operator == SearchOperator::Equals { is_negated: false }
+        }) {
+            class_ids.extend(p.value_as_integer().ok()?);
+        }
+
+        match class_ids.len() {
+            1 => class_ids.into_iter().next(),
+            _ => None,
+        }
+    }
+
+    fn requested_classes(&self) -> Result<Vec<i32>, ApiError> {
+        let mut class_ids = vec![];
+        for p in self.iter().filter(|p| {
+            matches!(p.field, FilterField::Classes | FilterField::ClassId)
+                && p.operator == SearchOperator::Equals { is_negated: false }
+        }) {
+            class_ids.extend(p.value_as_integer()?);
+        }
+
+        class_ids.sort_unstable();
+        class_ids.dedup();
+        Ok(class_ids)
+    }
+
+    fn order(&self) -> Result<Option<SearchOrder>, ApiError> {
+        let order_by: Vec<&ParsedQueryParam> = self
+            .iter()
+            .filter(|p| p.field == FilterField::OrderBy)
+            .collect();
+        let seeds: Vec<&ParsedQueryParam> = self
+            .iter()
+            .filter(|p| p.field == FilterField::Seed)
+            .collect();
+
+        let order = match order_by.as_slice() {
+            [] => None,
+            [param] if param.operator == (SearchOperator::Equals { is_negated: false }) => {
+                match param.value.as_str() {
+                    "random" => Some(SearchOrder::Random { seed: None }),
+                    other => {
+                        return Err(ApiError::BadRequest(format!(
+                            "Unsupported order_by '{}', only 'random' is supported",
+                            other
+                        )))
+                    }
+                }
+            }
+            [_] => {
+                return Err(ApiError::OperatorMismatch(
+                    "Field 'order_by' only supports the equals operator".to_string(),
+                ))
+            }
+            _ => {
+                return Err(ApiError::BadRequest(
+                    "order_by may only be given once".to_string(),
+                ))
+            }
+        };
+
+        let seed = match seeds.as_slice() {
+            [] => None,
+            [param] => Some(param.value_as_count()?),
+            _ => {
+                return Err(ApiError::BadRequest(
+                    "seed may only be given once".to_string(),
+                ))
+            }
+        };
+
+        match order {
+            None if seed.is_some() => Err(ApiError::BadRequest(
+                "seed requires order_by=random".to_string(),
+            )),
+            Some(_) if self.iter().any(|p| p.field == FilterField::Offset) => {
+                Err(ApiError::BadRequest(
+                    "order_by=random is for sampling and can't be combined with offset".to_string(),
+                ))
+            }
+            Some(SearchOrder::Random { .. }) => Ok(Some(SearchOrder::Random { seed })),
+            None => Ok(None),
+        }
+    }
+
+    fn shape(&self) -> Result<ResponseShape, ApiError> {
+        let shapes: Vec<&ParsedQueryParam> = self
+            .iter()
+            .filter(|p| p.field == FilterField::Shape)
+            .collect();
+
+        match shapes.as_slice() {
+            [] => Ok(ResponseShape::Array),
+            [param] if param.operator == (SearchOperator::Equals { is_negated: false }) => {
+                match param.value.as_str() {
+                    "array" => Ok(ResponseShape::Array),
+                    "map" => Ok(ResponseShape::Map),
+                    other => Err(ApiError::BadRequest(format!(
+                        "Unsupported shape '{}', expected 'array' or 'map'",
+                        other
+                    ))),
+                }
+            }
+            [_] => Err(ApiError::OperatorMismatch(
+                "Field 'shape' only supports the equals operator".to_string(),
+            )),
+            _ => Err(ApiError::BadRequest(
+                "shape may only be given once".to_string(),
+            )),
+        }
+    }
+
+    fn relation_expansion(&self) -> Result<Option<RelationExpansion>, ApiError> {
+        let mut expansion = None;
+        for param in self.iter().filter(|p| p.field == FilterField::Expand) {
+            if param.operator != (SearchOperator::Equals { is_negated: false }) {
+                return Err(ApiError::OperatorMismatch(
+                    "Field 'expand' only supports the equals operator".to_string(),
+                ));
+            }
+
+            for value in param.value.split(',').map(str::trim) {
+                match value {
+                    "relations" => {
+                        expansion.get_or_insert(RelationExpansion::Ids);
+                    }
+                    "relation_names" => expansion = Some(RelationExpansion::IdsAndNames),
+                    other => {
+                        return Err(ApiError::BadRequest(format!(
+                            "Unsupported expansion '{}', expected 'relations' or 'relation_names'",
+                            other
+                        )))
+                    }
+                }
+            }
+        }
+        Ok(expansion)
+    }
+}
+
+/// The order of search results, see [`QueryParamsExt::order`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SearchOrder {
+    /// Random order. With a seed, the same seed always gives the same order.
+    Random { seed: Option<i64> },
+}
+
+/// What to embed of the related objects of each object, see
+/// [`QueryParamsExt::relation_expansion`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RelationExpansion {
+    /// The IDs of the related objects.
+    Ids,
+    /// The IDs and the names of the related objects.
+    IdsAndNames,
+}
+
+/// The shape of a list of search results, see [`QueryParamsExt::shape`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ResponseShape {
+    /// A list, in the order of the search.
+    Array,
+    /// An object keyed by the ID of each result. The order of the results is not preserved.
+    Map,
+}
+
+impl ResponseShape {
+    /// Shape a list of results for a response.
+    pub fn apply<T: Serialize + SelfAccessors<T>>(
+        &self,
+        items: Vec<T>,
+    ) -> Result<serde_json::Value, ApiError> {
+        match self {
+            ResponseShape::Array => Ok(serde_json::to_value(items)?),
+            ResponseShape::Map => Ok(serde_json::to_value(
+                items
+                    .into_iter()
+                    .map(|item| (item.id(), item))
+                    .collect::<HashMap<i32, T>>(),
+            )?),
+        }
+    }
 }
 
 /// Operators
@@ -524,6 +1111,7 @@ pub enum Operator {
     Lt,
     Lte,
     Between,
+    IsNull,
 }
 
 impl std::fmt::Display for Operator {
@@ -544,6 +1132,7 @@ impl std::fmt::Display for Operator {
             Operator::Lt => "lt",
             Operator::Lte => "lte",
             Operator::Between => "between",
+            Operator::IsNull => "isnull",
         };
         write!(f, "{}", op)
     }
@@ -570,6 +1159,7 @@ pub enum SearchOperator {
     Lt { is_negated: bool },
     Lte { is_negated: bool },
     Between { is_negated: bool },
+    IsNull { is_negated: bool },
 }
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum DataType {
@@ -598,6 +1188,8 @@ impl SearchOperator {
             | SO::Lt { .. }
             | SO::Lte { .. }
             | SO::Between { .. } => matches!(data_type, DataType::NumericOrDate),
+            // Only JSON values can be null, and those are not typed.
+            SO::IsNull { .. } => false,
             SO::Contains { .. } => {
                 matches!(data_type, DataType::String) || matches!(data_type, DataType::Array)
             }
@@ -624,6 +1216,7 @@ impl SearchOperator {
             SearchOperator::Lt { is_negated, .. } => (Operator::Lt, *is_negated),
             SearchOperator::Lte { is_negated, .. } => (Operator::Lte, *is_negated),
             SearchOperator::Between { is_negated, .. } => (Operator::Between, *is_negated),
+            SearchOperator::IsNull { is_negated, .. } => (Operator::IsNull, *is_negated),
         }
     }
 
@@ -686,6 +1279,9 @@ impl SearchOperator {
             "between" => Ok(SO::Between {
                 is_negated: negated,
             }),
+            "isnull" => Ok(SO::IsNull {
+                is_negated: negated,
+            }),
 
             _ => Err(ApiError::BadRequest(format!(
                 "Invalid search operator: '{}'",
@@ -817,6 +1413,93 @@ fn get_jsonb_field_type_from_json_schema(
     }
 }
 
+/// ## Split a JSON search value into its key and value
+///
+/// The key ends at the first equals sign that is not escaped with a backslash, see
+/// [`parse_json_path`].
+///
+/// ### Returns
+///
+/// * The key (still escaped) and the value, or None if there is no unescaped equals sign
+pub fn split_json_key_value(value: &str) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '=' => return Some((&value[..i], &value[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// ## Parse a JSON path
+///
+/// A JSON path is a comma separated list of keys, eg `address,city` for the key `city` in the
+/// object at the key `address`. Dots have no special meaning, so `a.b` is the single key `a.b`
+/// and `a,b` is the key `b` nested in `a`. A comma, equals sign or backslash that is part of a
+/// key is escaped with a backslash, as in `\,`, `\=` and `\\`.
+///
+/// Apart from the escaped characters, keys may contain letters, digits, spaces, `_`, `$`, `.`
+/// and `-`. Empty keys are not allowed.
+///
+/// ### Returns
+///
+/// * The unescaped keys, or ApiError::BadRequest if the path is invalid
+pub fn parse_json_path(path: &str) -> Result<Vec<String>, ApiError> {
+    let invalid =
+        |reason: &str| ApiError::BadRequest(format!("Invalid JSON path '{}': {}", path, reason));
+
+    let mut keys = vec![];
+    let mut key = String::new();
+    let mut escaped = false;
+
+    for c in path.chars() {
+        if escaped {
+            match c {
+                ',' | '=' | '\\' => key.push(c),
+                _ => return Err(invalid(&format!("'\\{}' is not a valid escape", c))),
+            }
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == ',' {
+            if key.is_empty() {
+                return Err(invalid("keys can not be empty"));
+            }
+            keys.push(std::mem::take(&mut key));
+        } else if c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | '-' | ' ') {
+            key.push(c);
+        } else {
+            return Err(invalid(&format!("'{}' is not allowed in keys", c)));
+        }
+    }
+
+    if escaped {
+        return Err(invalid("the path ends with an unfinished escape"));
+    }
+    if key.is_empty() {
+        return Err(invalid("keys can not be empty"));
+    }
+    keys.push(key);
+
+    Ok(keys)
+}
+
+/// ## Format a parsed JSON path as a quoted Postgres text array
+///
+/// Every key is double quoted within the array, so that keys like `NULL` or ones containing
+/// commas and spaces are taken literally. As [`parse_json_path`] rejects quotes and braces, the
+/// result is safe to interpolate into SQL.
+pub fn json_path_literal(keys: &[String]) -> String {
+    let keys: Vec<String> = keys
+        .iter()
+        .map(|key| format!("\"{}\"", key.replace('\\', "\\\\")))
+        .collect();
+    format!("'{{{}}}'", keys.join(","))
+}
+
 /// ## Get the type of a JSON field
 ///
 /// This function takes a JSON field and an operator and returns a best guess of type of the field.
@@ -843,7 +1526,6 @@ pub fn get_jsonb_field_type_from_value_and_operator(
                 SQLMappedType::Date,
                 SQLMappedType::Boolean,
                 SQLMappedType::Numeric,
-                SQLMappedType::None,
                 SQLMappedType::String,
             ],
         ),
@@ -884,6 +1566,7 @@ pub fn get_jsonb_field_type_from_value_and_operator(
         | Operator::IEndsWith
         | Operator::Like
         | Operator::Regex => Some(SQLMappedType::String),
+        Operator::IsNull => Some(SQLMappedType::None),
     }
 }
 
@@ -988,10 +1671,12 @@ macro_rules! filter_fields {
         }
 
         impl FilterField {
+            /// The column a JSON filter field is searched in. Note that `json_data` searches
+            /// the `data` column of objects.
             pub fn table_field(&self) -> &'static str {
                 match self {
                     FilterField::JsonSchema => "json_schema",
-                    FilterField::JsonData => "json_data",
+                    FilterField::JsonData => "data",
                     _ => panic!("{:?} should not be used as a table field", self),
                 }
             }
@@ -1004,6 +1689,7 @@ filter_fields!(
     (Namespaces, "namespaces"),
     (Name, "name"),
     (Description, "description"),
+    (ExternalId, "external_id"),
     (Username, "username"),
     (Email, "email"),
     (ValidateSchema, "validate_schema"),
@@ -1035,6 +1721,19 @@ filter_fields!(
     (ClassRelation, "class_relation"),
     (Depth, "depth"),
     (Path, "path"),
+    (NamespaceMatchesClass, "namespace_matches_class"),
+    (HasRelation, "has_relation"),
+    (ChangedSince, "changed_since"),
+    (User, "user"),
+    (Issued, "issued"),
+    (LastUsed, "last_used"),
+    (Limit, "limit"),
+    (Offset, "offset"),
+    (Diagnostics, "diagnostics"),
+    (OrderBy, "order_by"),
+    (Seed, "seed"),
+    (Shape, "shape"),
+    (Expand, "expand"),
 );
 
 // TODO: Rewrite to use yare::parametrized...
@@ -1063,6 +1762,141 @@ mod test {
         assert_eq!(result, Ok(vec![]));
     }
 
+    #[test]
+    fn test_query_string_parameter_cap() {
+        let query_string = |count: usize| vec!["id=1"; count].join("&");
+
+        let result = parse_query_parameter(&query_string(DEFAULT_MAX_QUERY_PARAMS));
+        assert_eq!(result.unwrap().len(), DEFAULT_MAX_QUERY_PARAMS);
+
+        let result = parse_query_parameter(&query_string(DEFAULT_MAX_QUERY_PARAMS + 1));
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        // Rejected by the count alone, even though the parameters themselves are invalid.
+        let result = parse_query_parameter(&"&".repeat(10_000));
+        assert_eq!(
+            result,
+            Err(ApiError::BadRequest(format!(
+                "Too many query parameters, at most {} are allowed",
+                DEFAULT_MAX_QUERY_PARAMS
+            )))
+        );
+    }
+
+    fn parse_body(body: serde_json::Value) -> Result<QueryNode, ApiError> {
+        parse_query_body(&serde_json::from_value::<SearchFilter>(body).unwrap())
+    }
+
+    #[test]
+    fn test_query_body_parsing() {
+        type SO = SearchOperator;
+
+        assert_eq!(
+            parse_body(serde_json::json!({})).unwrap(),
+            QueryNode::And(vec![])
+        );
+
+        let tree = parse_body(serde_json::json!({
+            "and": [
+                {"field": "name", "operator": "icontains", "value": "web"},
+                {"or": [
+                    {"field": "namespaces", "value": [1, 2]},
+                    {"field": "json_data", "operator": "gt", "value": "count=5"},
+                ]},
+            ]
+        }))
+        .unwrap();
+
+        let name = pq("name", SO::IContains { is_negated: false }, "web");
+        let namespaces = pq("namespaces", SO::Equals { is_negated: false }, "1,2");
+        let json_data = pq("json_data", SO::Gt { is_negated: false }, "count=5");
+
+        assert_eq!(
+            tree,
+            QueryNode::And(vec![
+                QueryNode::Param(name.clone()),
+                QueryNode::Or(vec![
+                    QueryNode::Param(namespaces.clone()),
+                    QueryNode::Param(json_data.clone()),
+                ]),
+            ])
+        );
+
+        assert_eq!(tree.params(), vec![&name, &namespaces, &json_data]);
+
+        for body in [
+            serde_json::json!({"field": "name"}),
+            serde_json::json!({"value": "web"}),
+            serde_json::json!({"field": "name", "value": ""}),
+            serde_json::json!({"field": "name", "value": {"nested": true}}),
+            serde_json::json!({"field": "name", "operator": "nope", "value": "web"}),
+            serde_json::json!({"field": "nope", "value": "web"}),
+            serde_json::json!({"or": []}),
+            serde_json::json!({"and": [], "or": [{"field": "name", "value": "web"}]}),
+            serde_json::json!({"and": [], "field": "name", "value": "web"}),
+            serde_json::json!({"field": "limit", "value": 10}),
+            serde_json::json!({"or": [
+                {"field": "name", "value": "web"},
+                {"and": [{"field": "order_by", "value": "name"}]},
+            ]}),
+            serde_json::json!({"field": "diagnostics", "value": true}),
+        ] {
+            assert!(
+                matches!(parse_body(body.clone()), Err(ApiError::BadRequest(_))),
+                "Expected BadRequest for {}",
+                body
+            );
+        }
+
+        assert!(serde_json::from_value::<SearchFilter>(serde_json::json!({"nope": 1})).is_err());
+    }
+
+    #[test]
+    fn test_query_body_too_complex() {
+        let alternatives = |n: usize| {
+            serde_json::json!({
+                "or": (0..n)
+                    .map(|i| serde_json::json!({"field": "id", "value": i}))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        // 32 + 32 = 64 conditions is fine, 32 + 33 = 65 is not, however they are grouped.
+        let tree = parse_body(serde_json::json!({"and": [alternatives(32), alternatives(32)]}));
+        assert_eq!(tree.unwrap().params().len(), MAX_SEARCH_CONDITIONS);
+
+        let tree = parse_body(serde_json::json!({"or": [alternatives(32), alternatives(33)]}));
+        assert!(matches!(tree, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_single_class() {
+        type SO = SearchOperator;
+
+        let eq = SO::Equals { is_negated: false };
+        let cases = [
+            (vec![], None),
+            (vec![pq("class_id", eq.clone(), "3")], Some(3)),
+            (
+                vec![
+                    pq("class_id", eq.clone(), "3"),
+                    pq("classes", eq.clone(), "3"),
+                ],
+                Some(3),
+            ),
+            (vec![pq("classes", eq.clone(), "3,4")], None),
+            (
+                vec![pq("classes", SO::Equals { is_negated: true }, "3")],
+                None,
+            ),
+            (vec![pq("classes", SO::Gt { is_negated: false }, "3")], None),
+        ];
+
+        for (params, expected) in cases {
+            assert_eq!(params.single_class(), expected, "Failed for {:?}", params);
+        }
+    }
+
     #[test]
     fn test_query_string_without_equal_sign_returns_error() {
         let result = parse_query_parameter("name");
@@ -1146,6 +1980,53 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_degenerate_value_lists() {
+        let test_cases = vec![
+            "id__between=5",
+            "id__between=1,2,3",
+            "id__not_between=5",
+            "id__between=1,",
+            "id=,,",
+            "id=1,,2",
+            "id= ,1",
+            "namespaces=,",
+            "created_at__between=2021-01-01",
+            "created_at=2021-01-01,,2021-01-02",
+        ];
+
+        for query_string in test_cases {
+            let params = parse_query_parameter(query_string).unwrap();
+            let param = &params[0];
+            let result = if param.field == FilterField::CreatedAt {
+                param.value_as_date().map(|_| ())
+            } else {
+                param.value_as_integer().map(|_| ())
+            };
+
+            match result {
+                Err(ApiError::BadRequest(_)) => (),
+                other => panic!(
+                    "Expected BadRequest for query '{}', got {:?}",
+                    query_string, other
+                ),
+            }
+        }
+
+        for query_string in [
+            "id__between=1,5",
+            "id=1,2-4",
+            "created_at__between=2021-01-01,2021-02-01",
+        ] {
+            let params = parse_query_parameter(query_string).unwrap();
+            assert!(
+                params[0].validate_value_list().is_ok(),
+                "Expected query '{}' to be valid",
+                query_string
+            );
+        }
+    }
+
     #[test]
     fn test_query_string_bad_request() {
         let test_cases = vec![
@@ -1220,6 +2101,52 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_permissions_comma_separated() {
+        let params =
+            parse_query_parameter("permissions=ReadClass,UpdateClass&permissions=ReadObject")
+                .unwrap();
+        let permissions = params.permissions().unwrap();
+        for permission in [
+            Permissions::ReadClass,
+            Permissions::UpdateClass,
+            Permissions::ReadObject,
+        ] {
+            assert!(permissions.contains(&permission), "{:?}", permission);
+        }
+        assert!(!permissions.contains(&Permissions::DeleteClass));
+
+        // Duplicates across and within values are removed.
+        let params =
+            parse_query_parameter("permissions=ReadClass, ReadClass&permissions=ReadClass")
+                .unwrap();
+        assert_eq!(
+            params
+                .permissions()
+                .unwrap()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![Permissions::ReadClass]
+        );
+
+        for (query, invalid) in [
+            ("permissions=ReadClass,NoSuchPermission", "NoSuchPermission"),
+            ("permissions=ReadClass,", ""),
+            (
+                "permissions=ReadClass&permissions=UpdateClass,Bogus",
+                "Bogus",
+            ),
+        ] {
+            match parse_query_parameter(query).unwrap().permissions() {
+                Err(ApiError::BadRequest(message)) => {
+                    assert_eq!(message, format!("Invalid permission: '{}'", invalid))
+                }
+                other => panic!("Expected bad request for {}, got {:?}", query, other),
+            }
+        }
+    }
+
     #[test]
     fn test_json_schema_sql_query_text_generation() {
         let field = "json_schema";
@@ -1230,7 +2157,7 @@ mod test {
                     SearchOperator::Equals { is_negated: false },
                     "key=foo",
                 ),
-                format!("{} #>> '{{key}}' = ?", field),
+                format!("{} #>> '{{\"key\"}}' = ?", field),
                 SQLValue::String("foo".to_string()),
             ),
             (
@@ -1239,7 +2166,7 @@ mod test {
                     SearchOperator::IEquals { is_negated: true },
                     "key=foo",
                 ),
-                format!("NOT {} #>> '{{key}}' ILIKE ?", field),
+                format!("NOT {} #>> '{{\"key\"}}' ILIKE ?", field),
                 SQLValue::String("foo".to_string()),
             ),
             (
@@ -1248,9 +2175,36 @@ mod test {
                     SearchOperator::Gt { is_negated: false },
                     "key,subkey=3",
                 ),
-                format!("({} #>> '{{key,subkey}}')::numeric > ?", field),
+                format!("({} #>> '{{\"key\",\"subkey\"}}')::numeric > ?", field),
                 SQLValue::Integer(3),
             ),
+            (
+                pq(
+                    "json_schema",
+                    SearchOperator::Equals { is_negated: false },
+                    "key=null",
+                ),
+                format!("{} #>> '{{\"key\"}}' = ?", field),
+                SQLValue::String("null".to_string()),
+            ),
+            (
+                pq(
+                    "json_schema",
+                    SearchOperator::IsNull { is_negated: false },
+                    "key",
+                ),
+                format!("({} #> '{{\"key\"}}') = ?::jsonb", field),
+                SQLValue::Null,
+            ),
+            (
+                pq(
+                    "json_schema",
+                    SearchOperator::IsNull { is_negated: true },
+                    "key,k\\=2",
+                ),
+                format!("NOT ({} #> '{{\"key\",\"k=2\"}}') = ?::jsonb", field),
+                SQLValue::Null,
+            ),
         ];
 
         for (param, expected, sqlvalue) in test_cases {
@@ -1277,7 +2231,7 @@ mod test {
                     SearchOperator::Equals { is_negated: false },
                     "key=2021-01-01",
                 ),
-                format!("({} #>> '{{key}}')::date = ?", field),
+                format!("({} #>> '{{\"key\"}}')::date = ?", field),
                 SQLValue::Date("2021-01-01".as_date().unwrap()[0]),
             ),
             (
@@ -1286,7 +2240,7 @@ mod test {
                     SearchOperator::Gt { is_negated: false },
                     "key,subkey=2021-01-01",
                 ),
-                format!("({} #>> '{{key,subkey}}')::date > ?", field),
+                format!("({} #>> '{{\"key\",\"subkey\"}}')::date > ?", field),
                 SQLValue::Date("2021-01-01".as_date().unwrap()[0]),
             ),
             (
@@ -1295,7 +2249,7 @@ mod test {
                     SearchOperator::Gt { is_negated: true },
                     "key,subkey=2021-01-01",
                 ),
-                format!("NOT ({} #>> '{{key,subkey}}')::date > ?", field),
+                format!("NOT ({} #>> '{{\"key\",\"subkey\"}}')::date > ?", field),
                 SQLValue::Date("2021-01-01".as_date().unwrap()[0]),
             ),
         ];
@@ -1324,7 +2278,7 @@ mod test {
                     SearchOperator::Equals { is_negated: false },
                     "key=3",
                 ),
-                format!("({} #>> '{{key}}')::numeric = ?", field),
+                format!("({} #>> '{{\"key\"}}')::numeric = ?", field),
                 SQLValue::Integer(3),
             ),
             (
@@ -1333,7 +2287,7 @@ mod test {
                     SearchOperator::Gt { is_negated: false },
                     "key,subkey=3",
                 ),
-                format!("({} #>> '{{key,subkey}}')::numeric > ?", field),
+                format!("({} #>> '{{\"key\",\"subkey\"}}')::numeric > ?", field),
                 SQLValue::Integer(3),
             ),
             (
@@ -1342,9 +2296,45 @@ mod test {
                     SearchOperator::Gt { is_negated: true },
                     "key,subkey=3",
                 ),
-                format!("NOT ({} #>> '{{key,subkey}}')::numeric > ?", field),
+                format!("NOT ({} #>> '{{\"key\",\"subkey\"}}')::numeric > ?", field),
                 SQLValue::Integer(3),
             ),
+            (
+                pq(
+                    "json_schema",
+                    SearchOperator::Gt { is_negated: false },
+                    "key=5000000000",
+                ),
+                format!("({} #>> '{{\"key\"}}')::numeric > ?", field),
+                SQLValue::BigInt(5_000_000_000),
+            ),
+            (
+                pq(
+                    "json_schema",
+                    SearchOperator::Lte { is_negated: false },
+                    "key=-9223372036854775808",
+                ),
+                format!("({} #>> '{{\"key\"}}')::numeric <= ?", field),
+                SQLValue::BigInt(i64::MIN),
+            ),
+            (
+                pq(
+                    "json_schema",
+                    SearchOperator::Equals { is_negated: false },
+                    "key=123456789012345678901234567890",
+                ),
+                format!("({} #>> '{{\"key\"}}')::numeric = ?::numeric", field),
+                SQLValue::Numeric("123456789012345678901234567890".to_string()),
+            ),
+            (
+                pq(
+                    "json_schema",
+                    SearchOperator::Lt { is_negated: false },
+                    "key=0.1",
+                ),
+                format!("({} #>> '{{\"key\"}}')::numeric < ?::numeric", field),
+                SQLValue::Numeric("0.1".to_string()),
+            ),
         ];
 
         for (param, expected, sqlvalue) in test_cases {
@@ -1361,6 +2351,96 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_sql_value_from_numeric_str() {
+        assert_eq!(
+            SQLValue::from_numeric_str("3").unwrap(),
+            SQLValue::Integer(3)
+        );
+        assert_eq!(
+            SQLValue::from_numeric_str("2147483648").unwrap(),
+            SQLValue::BigInt(2_147_483_648)
+        );
+        assert_eq!(
+            SQLValue::from_numeric_str("9223372036854775808").unwrap(),
+            SQLValue::Numeric("9223372036854775808".to_string())
+        );
+        assert_eq!(
+            SQLValue::from_numeric_str("-1.25").unwrap(),
+            SQLValue::Numeric("-1.25".to_string())
+        );
+
+        for invalid in ["", "-", "1.", ".5", "1e10", "inf", "NaN", "1.2.3", "0x10"] {
+            assert!(
+                SQLValue::from_numeric_str(invalid).is_err(),
+                "Expected '{}' to be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_path_parsing() {
+        let test_cases = vec![
+            ("key", vec!["key"], "'{\"key\"}'"),
+            ("a.b", vec!["a.b"], "'{\"a.b\"}'"),
+            ("a,b", vec!["a", "b"], "'{\"a\",\"b\"}'"),
+            ("a.b,c", vec!["a.b", "c"], "'{\"a.b\",\"c\"}'"),
+            ("$id", vec!["$id"], "'{\"$id\"}'"),
+            ("a\\,b", vec!["a,b"], "'{\"a,b\"}'"),
+            ("a\\=b", vec!["a=b"], "'{\"a=b\"}'"),
+            ("a\\\\b", vec!["a\\b"], "'{\"a\\\\b\"}'"),
+            ("NULL", vec!["NULL"], "'{\"NULL\"}'"),
+            ("null,x", vec!["null", "x"], "'{\"null\",\"x\"}'"),
+            (
+                "first name,x-y",
+                vec!["first name", "x-y"],
+                "'{\"first name\",\"x-y\"}'",
+            ),
+            (
+                "a,b,c,d,e,f",
+                vec!["a", "b", "c", "d", "e", "f"],
+                "'{\"a\",\"b\",\"c\",\"d\",\"e\",\"f\"}'",
+            ),
+        ];
+
+        for (path, expected, literal) in test_cases {
+            let keys = parse_json_path(path).unwrap();
+            assert_eq!(keys, expected, "Failed test case for path: {}", path);
+            assert_eq!(
+                json_path_literal(&keys),
+                literal,
+                "Failed test case for path: {}",
+                path
+            );
+        }
+
+        for invalid in [
+            "", ",", "a,", ",a", "a,,b", "a\\", "a\\x", "a'b", "a\"b", "a{b", "a}b", "a;b",
+        ] {
+            assert!(
+                parse_json_path(invalid).is_err(),
+                "Expected '{}' to be rejected",
+                invalid
+            );
+        }
+
+        assert_eq!(split_json_key_value("a=b=c"), Some(("a", "b=c")));
+        assert_eq!(split_json_key_value("a\\=b=c"), Some(("a\\=b", "c")));
+        assert_eq!(split_json_key_value("a\\\\=b"), Some(("a\\\\", "b")));
+        assert_eq!(split_json_key_value("a\\=b"), None);
+
+        let param = pq(
+            "json_data",
+            SearchOperator::Equals { is_negated: false },
+            "x,y.z\\,w=deep",
+        );
+        assert_eq!(
+            param.as_json_sql().unwrap().sql,
+            "data #>> '{\"x\",\"y.z,w\"}' = ?"
+        );
+    }
+
     #[test]
     fn test_json_schema_sql_generation_wrapping() {
         let field = "json_schema";
@@ -1371,7 +2451,7 @@ mod test {
                     SearchOperator::Equals { is_negated: false },
                     "key,subkey=3",
                 ),
-                format!("({} #>> '{{key,subkey}}')::numeric = ?", field),
+                format!("({} #>> '{{\"key\",\"subkey\"}}')::numeric = ?", field),
             ),
             (
                 pq(
@@ -1379,7 +2459,7 @@ mod test {
                     SearchOperator::Equals { is_negated: false },
                     "key,subkey,subsubkey=3",
                 ),
-                format!("({} #>> '{{key,subkey,subsubkey}}')::numeric = ?", field),
+                format!("({} #>> '{{\"key\",\"subkey\",\"subsubkey\"}}')::numeric = ?", field),
             ),
             (
                 pq(
@@ -1388,7 +2468,7 @@ mod test {
                     "key,subkey,subsubkey,subsubsubkey=3",
                 ),
                 format!(
-                    "NOT ({} #>> '{{key,subkey,subsubkey,subsubsubkey}}')::numeric = ?",
+                    "NOT ({} #>> '{{\"key\",\"subkey\",\"subsubkey\",\"subsubsubkey\"}}')::numeric = ?",
                     field
                 ),
             ),
@@ -1405,6 +2485,23 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_json_data_sql_uses_data_column() {
+        // Objects keep their data in the `data` column, there is no `json_data` column.
+        assert_eq!(FilterField::JsonData.table_field(), "data");
+        assert_eq!(FilterField::JsonSchema.table_field(), "json_schema");
+
+        let param = pq(
+            "json_data",
+            SearchOperator::Gt { is_negated: false },
+            "key=3",
+        );
+        assert_eq!(
+            param.as_json_sql().unwrap().sql,
+            "(data #>> '{\"key\"}')::numeric > ?"
+        );
+    }
+
     #[test]
     fn test_json_field_type_from_schema() {
         let schema = serde_json::json!({
@@ -1541,7 +2638,8 @@ mod test {
             ("2021-01-01", Operator::Equals, Some(SQLMappedType::Date)),
             ("true", Operator::Equals, Some(SQLMappedType::Boolean)),
             ("FALSe", Operator::Equals, Some(SQLMappedType::Boolean)),
-            ("null", Operator::Equals, Some(SQLMappedType::None)),
+            ("null", Operator::Equals, Some(SQLMappedType::String)),
+            ("", Operator::IsNull, Some(SQLMappedType::None)),
             ("true", Operator::Equals, Some(SQLMappedType::Boolean)),
             ("2021-01-01", Operator::Gt, Some(SQLMappedType::Date)),
             ("3", Operator::Gt, Some(SQLMappedType::Numeric)),
@@ -1579,6 +2677,7 @@ mod test {
             ("lt", SO::Lt { is_negated: false }),
             ("lte", SO::Lte { is_negated: false }),
             ("between", SO::Between { is_negated: false }),
+            ("isnull", SO::IsNull { is_negated: false }),
             ("not_equals", SO::Equals { is_negated: true }),
             ("not_iequals", SO::IEquals { is_negated: true }),
             ("not_contains", SO::Contains { is_negated: true }),
@@ -1673,6 +2772,8 @@ mod test {
             (SO::Between { is_negated: false }, DT::String, false),
             (SO::Between { is_negated: false }, DT::NumericOrDate, true),
             (SO::Between { is_negated: false }, DT::Boolean, false),
+            (SO::IsNull { is_negated: false }, DT::String, false),
+            (SO::IsNull { is_negated: false }, DT::Array, false),
         ];
 
         for (operator, data_type, expected) in test_cases {
@@ -0,0 +1,106 @@
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Text};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::db::{with_connection, DbPool};
+use crate::errors::ApiError;
+use crate::models::TOKEN_LIFETIME_HOURS;
+
+/// How long collected stats are served from the cache before they are collected again.
+pub const STATS_CACHE_TTL: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    static ref STATS_CACHE: Mutex<Option<(Instant, SystemStats)>> = Mutex::new(None);
+}
+
+/// A summary of the number of entities in the system, for admins.
+///
+/// If `approximate` is set, the counts for classes, objects and relations are the estimates
+/// Postgres keeps in `pg_class`, which are cheap to get but only as fresh as the last vacuum or
+/// analyze of the table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SystemStats {
+    pub users: i64,
+    pub groups: i64,
+    pub namespaces: i64,
+    pub classes: i64,
+    pub objects: i64,
+    pub class_relations: i64,
+    pub object_relations: i64,
+    pub active_tokens: i64,
+    pub approximate: bool,
+}
+
+#[derive(QueryableByName)]
+struct Count {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+impl SystemStats {
+    /// Get the stats, collecting them only if the cached ones are older than `STATS_CACHE_TTL`.
+    pub async fn get(pool: &DbPool, approximate: bool) -> Result<SystemStats, ApiError> {
+        if let Some((collected, stats)) = STATS_CACHE.lock().unwrap().as_ref() {
+            if collected.elapsed() < STATS_CACHE_TTL && stats.approximate == approximate {
+                return Ok(stats.clone());
+            }
+        }
+
+        let stats = SystemStats::collect(pool, approximate)?;
+        *STATS_CACHE.lock().unwrap() = Some((Instant::now(), stats.clone()));
+        Ok(stats)
+    }
+
+    fn collect(pool: &DbPool, approximate: bool) -> Result<SystemStats, ApiError> {
+        with_connection(pool, |conn| {
+            let large_table_count = |conn: &mut PgConnection, table: &str| {
+                if approximate {
+                    estimated_count(conn, table)
+                } else {
+                    exact_count(conn, table)
+                }
+            };
+
+            Ok(SystemStats {
+                users: exact_count(conn, "users")?,
+                groups: exact_count(conn, "groups")?,
+                namespaces: exact_count(conn, "namespaces")?,
+                classes: large_table_count(conn, "hubuumclass")?,
+                objects: large_table_count(conn, "hubuumobject")?,
+                class_relations: large_table_count(conn, "hubuumclass_relation")?,
+                object_relations: large_table_count(conn, "hubuumobject_relation")?,
+                active_tokens: diesel::sql_query("SELECT COUNT(*) AS count FROM tokens WHERE issued > (CURRENT_TIMESTAMP - (COALESCE(ttl_hours, $1) || ' hours')::INTERVAL)")
+                    .bind::<Integer, _>(TOKEN_LIFETIME_HOURS)
+                    .get_result::<Count>(conn)?
+                    .count,
+                approximate,
+            })
+        })
+    }
+}
+
+// The table names are ours and never come from a request, so they can be formatted in.
+fn exact_count(conn: &mut PgConnection, table: &str) -> Result<i64, diesel::result::Error> {
+    diesel::sql_query(format!("SELECT COUNT(*) AS count FROM {}", table))
+        .get_result::<Count>(conn)
+        .map(|c| c.count)
+}
+
+// A table that has never been vacuumed or analyzed has no estimate (a negative reltuples), so
+// we fall back to counting it.
+fn estimated_count(conn: &mut PgConnection, table: &str) -> Result<i64, diesel::result::Error> {
+    let estimate = diesel::sql_query(
+        "SELECT reltuples::BIGINT AS count FROM pg_class WHERE relname = $1 AND relkind = 'r'",
+    )
+    .bind::<Text, _>(table)
+    .get_result::<Count>(conn)
+    .optional()?;
+
+    match estimate {
+        Some(Count { count }) if count >= 0 => Ok(count),
+        _ => exact_count(conn, table),
+    }
+}
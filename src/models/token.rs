@@ -4,13 +4,30 @@ use chrono::NaiveDateTime;
 use chrono::{Duration, Utc};
 
 use diesel::prelude::*;
-use diesel::sql_types::{Integer, Text, Timestamp};
+use diesel::sql_types::{Bool, Integer, Nullable, Text, Timestamp};
 use diesel::QueryableByName;
 use serde::{Deserialize, Serialize};
 
-use crate::db::DbPool;
+use crate::db::{with_connection, DbPool};
 use crate::errors::ApiError;
-use crate::schema::tokens;
+use crate::models::search::{FilterField, ParsedQueryParam};
+use crate::schema::{token_audit, tokens};
+use crate::{date_search, numeric_search, string_search, trace_query};
+
+/// How long a token is valid after it has been issued.
+pub const TOKEN_LIFETIME_HOURS: i32 = 24; // FIXME: Make this configurable
+
+/// The longest lifetime that can be requested for an issued token, five years.
+///
+/// Expiry is computed in SQL as `CURRENT_TIMESTAMP - ttl_hours`, which fails with "timestamp out
+/// of range" for very large values, so the lifetime has to be bounded.
+pub const MAX_TOKEN_LIFETIME_HOURS: i32 = 5 * 365 * 24;
+
+/// How many tokens a token search returns when no `limit` is given.
+pub const TOKEN_SEARCH_DEFAULT_LIMIT: i64 = 100;
+
+/// The largest `limit` a token search accepts.
+pub const TOKEN_SEARCH_MAX_LIMIT: i64 = 1000;
 
 #[derive(Serialize, Deserialize, Queryable, Insertable, Selectable, QueryableByName, Clone)]
 #[diesel(table_name = tokens)]
@@ -21,6 +38,147 @@ pub struct UserToken {
     pub user_id: i32,
     #[diesel(sql_type = Timestamp)]
     pub issued: NaiveDateTime,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub name: Option<String>,
+    #[diesel(sql_type = Nullable<Integer>)]
+    pub ttl_hours: Option<i32>,
+    #[diesel(sql_type = Nullable<Timestamp>)]
+    pub last_used: Option<NaiveDateTime>,
+    #[diesel(sql_type = Nullable<Integer>)]
+    pub namespace_id: Option<i32>,
+    #[diesel(sql_type = Bool)]
+    pub read_only: bool,
+}
+
+impl UserToken {
+    /// When the token expires. Like all our timestamps, this is in UTC, see `db::init_pool`.
+    pub fn expires(&self) -> NaiveDateTime {
+        let hours = self.ttl_hours.unwrap_or(TOKEN_LIFETIME_HOURS);
+        self.issued + Duration::hours(hours.into())
+    }
+}
+
+/// A token as shown in token introspection, without the secret itself.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenInfo {
+    pub prefix: String,
+    pub user_id: i32,
+    pub name: Option<String>,
+    pub issued: NaiveDateTime,
+    pub expires: NaiveDateTime,
+    pub last_used: Option<NaiveDateTime>,
+    pub namespace_id: Option<i32>,
+    pub read_only: bool,
+}
+
+impl From<UserToken> for TokenInfo {
+    fn from(user_token: UserToken) -> Self {
+        TokenInfo {
+            prefix: Token(user_token.token.clone()).obfuscate(),
+            user_id: user_token.user_id,
+            name: user_token.name.clone(),
+            issued: user_token.issued,
+            expires: user_token.expires(),
+            last_used: user_token.last_used,
+            namespace_id: user_token.namespace_id,
+            read_only: user_token.read_only,
+        }
+    }
+}
+
+impl UserToken {
+    /// Search all tokens, regardless of user and whether they are expired.
+    ///
+    /// Results are ordered by issue time, newest first, and may be paged with `limit` and
+    /// `offset`. Without a `limit`, at most `TOKEN_SEARCH_DEFAULT_LIMIT` tokens are returned, and
+    /// a `limit` above `TOKEN_SEARCH_MAX_LIMIT` is refused.
+    pub async fn search(
+        pool: &DbPool,
+        query_params: Vec<ParsedQueryParam>,
+    ) -> Result<Vec<UserToken>, ApiError> {
+        use crate::schema::tokens::dsl::*;
+
+        let mut base_query = tokens.into_boxed();
+        let mut limit = TOKEN_SEARCH_DEFAULT_LIMIT;
+
+        for param in &query_params {
+            let operator = param.operator.clone();
+            match param.field {
+                FilterField::User => numeric_search!(base_query, param, operator, user_id),
+                FilterField::Name => {
+                    string_search!(base_query, query_params, param, operator, name)
+                }
+                FilterField::Issued => date_search!(base_query, param, operator, issued),
+                FilterField::LastUsed => date_search!(base_query, param, operator, last_used),
+                FilterField::Limit => {
+                    limit = param.value_as_count()?;
+                    if limit > TOKEN_SEARCH_MAX_LIMIT {
+                        return Err(ApiError::BadRequest(format!(
+                            "limit can be at most {}, got {}",
+                            TOKEN_SEARCH_MAX_LIMIT, limit
+                        )));
+                    }
+                }
+                FilterField::Offset => base_query = base_query.offset(param.value_as_count()?),
+                _ => {
+                    return Err(ApiError::BadRequest(format!(
+                        "Field '{}' isn't searchable (or does not exist) for tokens",
+                        param.field
+                    )))
+                }
+            }
+        }
+
+        base_query = base_query.limit(limit);
+
+        trace_query!(base_query, "Searching tokens");
+
+        with_connection(pool, |conn| {
+            base_query
+                .order((issued.desc(), token.asc()))
+                .load::<UserToken>(conn)
+        })
+    }
+}
+
+/// A request to issue a token on behalf of a user.
+///
+/// Without a `ttl_hours`, the token gets the default lifetime. With a `namespace_id`, the token
+/// is scoped to that namespace, see `db::scope`. A `read_only` token can only
+/// be used for GET and HEAD requests.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NewTokenRequest {
+    pub name: Option<String>,
+    pub ttl_hours: Option<i32>,
+    pub namespace_id: Option<i32>,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl NewTokenRequest {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        match self.ttl_hours {
+            Some(hours) if hours <= 0 => Err(ApiError::BadRequest(format!(
+                "ttl_hours must be positive, got {}",
+                hours
+            ))),
+            Some(hours) if hours > MAX_TOKEN_LIFETIME_HOURS => Err(ApiError::BadRequest(format!(
+                "ttl_hours can be at most {}, got {}",
+                MAX_TOKEN_LIFETIME_HOURS, hours
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An entry in the token audit trail, recording a token issued on behalf of a user.
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = token_audit)]
+pub struct NewTokenAudit {
+    pub user_id: i32,
+    pub issued_by: i32,
+    pub name: Option<String>,
+    pub ttl_hours: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
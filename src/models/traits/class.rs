@@ -9,8 +9,10 @@ use crate::db::DbPool;
 use crate::errors::ApiError;
 use crate::models::traits::GroupAccessors;
 
+use crate::db::traits::drop_class_indexes;
 use crate::models::{
-    HubuumClass, HubuumClassID, Namespace, NewHubuumClass, PermissionsList, UpdateHubuumClass, User,
+    ClassIndex, HubuumClass, HubuumClassID, Namespace, NewClassIndex, NewHubuumClass,
+    PermissionsList, UpdateHubuumClass, User,
 };
 
 impl CanSave for HubuumClass {
@@ -34,9 +36,11 @@ impl CanDelete for HubuumClass {
         use crate::schema::hubuumclass::dsl::*;
 
         let mut conn = pool.get()?;
-        diesel::delete(hubuumclass.filter(id.eq(self.id))).execute(&mut conn)?;
-
-        Ok(())
+        conn.transaction::<_, ApiError, _>(|conn| {
+            drop_class_indexes(conn, &[self.id])?;
+            diesel::delete(hubuumclass.filter(id.eq(self.id))).execute(conn)?;
+            Ok(())
+        })
     }
 }
 
@@ -70,6 +74,58 @@ impl CanUpdate for UpdateHubuumClass {
     }
 }
 
+impl CanSave for NewClassIndex {
+    type Output = ClassIndex;
+
+    /// Declare an indexed JSON path and create the expression index for it
+    ///
+    /// The path uses the same form as `json_data` searches, eg `address,city`, see
+    /// `parse_json_path`.
+    /// The index covers the text value at the path for the objects of the class, so it is used
+    /// by string comparisons in searches that are scoped to the class. Creating the index locks
+    /// the object table for writes while it is built.
+    ///
+    /// The declaration and the index are created in one transaction.
+    async fn save(&self, pool: &DbPool) -> Result<ClassIndex, ApiError> {
+        use crate::models::search::{json_path_literal, parse_json_path};
+        use crate::schema::class_indexes::dsl::class_indexes;
+
+        let path = json_path_literal(&parse_json_path(&self.json_path)?);
+
+        let mut conn = pool.get()?;
+        conn.transaction::<_, ApiError, _>(|conn| {
+            let index = diesel::insert_into(class_indexes)
+                .values(self)
+                .get_result::<ClassIndex>(conn)?;
+
+            // Neither the path nor the class ID can be bound in DDL, the path is validated above.
+            diesel::sql_query(format!(
+                "CREATE INDEX {} ON hubuumobject ((data #>> {})) WHERE hubuum_class_id = {}",
+                index.index_name(),
+                path,
+                index.hubuum_class_id
+            ))
+            .execute(conn)?;
+
+            Ok(index)
+        })
+    }
+}
+
+impl CanDelete for ClassIndex {
+    async fn delete(&self, pool: &DbPool) -> Result<(), ApiError> {
+        use crate::schema::class_indexes::dsl::{class_indexes, id};
+
+        let mut conn = pool.get()?;
+        conn.transaction::<_, ApiError, _>(|conn| {
+            diesel::sql_query(format!("DROP INDEX IF EXISTS {}", self.index_name()))
+                .execute(conn)?;
+            diesel::delete(class_indexes.filter(id.eq(self.id))).execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
 impl SelfAccessors<HubuumClass> for HubuumClass {
     fn id(&self) -> i32 {
         self.id
@@ -1,10 +1,14 @@
+use crate::db::traits::drop_class_indexes;
 use crate::db::DbPool;
 use crate::errors::ApiError;
 use crate::models::group::GroupID;
 use crate::models::namespace::{
-    Namespace, NamespaceID, NewNamespace, NewNamespaceWithAssignee, UpdateNamespace,
+    Namespace, NamespaceID, NamespacePatch, NamespaceTransfer, NewNamespace,
+    NewNamespaceWithAssignee, UpdateNamespace,
+};
+use crate::models::permissions::{
+    NewPermission, NewPermissionAudit, Permission, Permissions, PermissionsList,
 };
-use crate::models::permissions::{NewPermission, Permission, Permissions, PermissionsList};
 use crate::models::traits::GroupAccessors;
 use crate::models::user::User;
 use crate::traits::{
@@ -25,6 +29,23 @@ impl CanSave for Namespace {
     }
 }
 
+/// Delete a namespace along with the expression indexes of its classes, see
+/// [`drop_class_indexes`].
+fn delete_namespace(conn: &mut PgConnection, namespace_id: i32) -> Result<(), ApiError> {
+    use crate::schema::hubuumclass::dsl as class;
+    use crate::schema::namespaces::dsl::{id, namespaces};
+
+    conn.transaction::<_, ApiError, _>(|conn| {
+        let class_ids = class::hubuumclass
+            .filter(class::namespace_id.eq(namespace_id))
+            .select(class::id)
+            .load::<i32>(conn)?;
+        drop_class_indexes(conn, &class_ids)?;
+        diesel::delete(namespaces.filter(id.eq(namespace_id))).execute(conn)?;
+        Ok(())
+    })
+}
+
 impl CanDelete for Namespace {
     /// Delete a namespace
     ///
@@ -42,12 +63,8 @@ impl CanDelete for Namespace {
     /// * Ok() - On success
     /// * Err(ApiError) - On query errors only.
     async fn delete(&self, pool: &DbPool) -> Result<(), ApiError> {
-        use crate::schema::namespaces::dsl::*;
-
         let mut conn = pool.get()?;
-        diesel::delete(namespaces.filter(id.eq(self.id))).execute(&mut conn)?;
-
-        Ok(())
+        delete_namespace(&mut conn, self.id)
     }
 }
 
@@ -68,12 +85,8 @@ impl CanDelete for NamespaceID {
     /// * Ok() - On success
     /// * Err(ApiError) - On query errors only.
     async fn delete(&self, pool: &DbPool) -> Result<(), ApiError> {
-        use crate::schema::namespaces::dsl::*;
-
         let mut conn = pool.get()?;
-        diesel::delete(namespaces.filter(id.eq(self.0))).execute(&mut conn)?;
-
-        Ok(())
+        delete_namespace(&mut conn, self.0)
     }
 }
 
@@ -122,30 +135,7 @@ impl CanSave for NewNamespaceWithAssignee {
                 .values(&new_namespace)
                 .get_result::<Namespace>(conn)?;
 
-            let group_permission = NewPermission {
-                namespace_id: namespace.id,
-                group_id: self.group_id,
-                has_read_namespace: true,
-                has_update_namespace: true,
-                has_delete_namespace: true,
-                has_delegate_namespace: true,
-                has_create_class: true,
-                has_read_class: true,
-                has_update_class: true,
-                has_delete_class: true,
-                has_create_object: true,
-                has_read_object: true,
-                has_update_object: true,
-                has_delete_object: true,
-                has_create_class_relation: true,
-                has_read_class_relation: true,
-                has_update_class_relation: true,
-                has_delete_class_relation: true,
-                has_create_object_relation: true,
-                has_read_object_relation: true,
-                has_update_object_relation: true,
-                has_delete_object_relation: true,
-            };
+            let group_permission = NewPermission::all(namespace.id, self.group_id);
 
             diesel::insert_into(crate::schema::permissions::table)
                 .values(&group_permission)
@@ -238,30 +228,7 @@ impl NewNamespace {
                 .values(&self)
                 .get_result::<Namespace>(conn)?;
 
-            let group_permission = NewPermission {
-                namespace_id: namespace.id,
-                group_id: assignee.0,
-                has_read_namespace: true,
-                has_update_namespace: true,
-                has_delete_namespace: true,
-                has_delegate_namespace: true,
-                has_create_class: true,
-                has_read_class: true,
-                has_update_class: true,
-                has_delete_class: true,
-                has_create_object: true,
-                has_read_object: true,
-                has_update_object: true,
-                has_delete_object: true,
-                has_create_class_relation: true,
-                has_read_class_relation: true,
-                has_update_class_relation: true,
-                has_delete_class_relation: true,
-                has_create_object_relation: true,
-                has_read_object_relation: true,
-                has_update_object_relation: true,
-                has_delete_object_relation: true,
-            };
+            let group_permission = NewPermission::all(namespace.id, assignee.0);
 
             diesel::insert_into(permissions)
                 .values(&group_permission)
@@ -285,30 +252,7 @@ impl NewNamespace {
                 .values(&self)
                 .get_result::<Namespace>(conn)?;
 
-            let group_permission = NewPermission {
-                namespace_id: namespace.id,
-                group_id: ns_with_assignee.group_id,
-                has_read_namespace: true,
-                has_update_namespace: true,
-                has_delete_namespace: true,
-                has_delegate_namespace: true,
-                has_create_class: true,
-                has_read_class: true,
-                has_update_class: true,
-                has_delete_class: true,
-                has_create_object: true,
-                has_read_object: true,
-                has_update_object: true,
-                has_delete_object: true,
-                has_create_class_relation: true,
-                has_read_class_relation: true,
-                has_update_class_relation: true,
-                has_delete_class_relation: true,
-                has_create_object_relation: true,
-                has_read_object_relation: true,
-                has_update_object_relation: true,
-                has_delete_object_relation: true,
-            };
+            let group_permission = NewPermission::all(namespace.id, ns_with_assignee.group_id);
 
             diesel::insert_into(permissions)
                 .values(&group_permission)
@@ -319,5 +263,113 @@ impl NewNamespace {
     }
 }
 
+impl Namespace {
+    /// Update the name, description and settings of the namespace in one transaction.
+    ///
+    /// The settings are validated first, see [`UpdateNamespaceSettings::validate`]. Parts of
+    /// the patch without changes are skipped.
+    ///
+    /// [`UpdateNamespaceSettings::validate`]: crate::models::UpdateNamespaceSettings::validate
+    pub async fn patch(
+        &self,
+        pool: &DbPool,
+        patch: &NamespacePatch,
+    ) -> Result<Namespace, ApiError> {
+        use crate::schema::namespaces::dsl::{id, namespaces};
+
+        patch.settings.validate()?;
+
+        let mut conn = pool.get()?;
+        conn.transaction::<_, ApiError, _>(|conn| {
+            if patch.namespace.name.is_some() || patch.namespace.description.is_some() {
+                diesel::update(namespaces)
+                    .filter(id.eq(self.id))
+                    .set(&patch.namespace)
+                    .execute(conn)?;
+            }
+
+            if !patch.settings.is_empty() {
+                diesel::update(namespaces)
+                    .filter(id.eq(self.id))
+                    .set(&patch.settings)
+                    .execute(conn)?;
+            }
+
+            Ok(namespaces.filter(id.eq(self.id)).first::<Namespace>(conn)?)
+        })
+    }
+
+    /// Transfer ownership of the namespace, see [`NamespaceTransfer`].
+    ///
+    /// The previous owner must have permissions on the namespace. The grant and the revocation
+    /// happen in one transaction, and the revocation is recorded in the permission audit trail.
+    ///
+    /// ## Returns
+    /// * Ok(Vec<Permission>) - The permissions on the namespace after the transfer, by group ID.
+    pub async fn transfer(
+        &self,
+        pool: &DbPool,
+        transfer: &NamespaceTransfer,
+    ) -> Result<Vec<Permission>, ApiError> {
+        use crate::schema::permission_audit::dsl::permission_audit;
+        use crate::schema::permissions::dsl::{group_id, namespace_id, permissions};
+
+        if transfer.from_group_id == transfer.to_group_id {
+            return Err(ApiError::BadRequest(
+                "Cannot transfer a namespace to its current owner".to_string(),
+            ));
+        }
+
+        let mut conn = pool.get()?;
+        conn.transaction::<_, ApiError, _>(|conn| {
+            let owner = permissions
+                .filter(namespace_id.eq(self.id))
+                .filter(group_id.eq(transfer.from_group_id))
+                .first::<Permission>(conn)
+                .optional()?;
+
+            if owner.is_none() {
+                return Err(ApiError::NotFound(format!(
+                    "Group {} has no permissions on namespace {}",
+                    transfer.from_group_id, self.id
+                )));
+            }
+
+            diesel::delete(permissions)
+                .filter(namespace_id.eq(self.id))
+                .filter(group_id.eq(transfer.to_group_id))
+                .execute(conn)?;
+
+            let group_permission = NewPermission::all(self.id, transfer.to_group_id);
+
+            diesel::insert_into(permissions)
+                .values(&group_permission)
+                .execute(conn)?;
+
+            if !transfer.keep_access {
+                diesel::delete(permissions)
+                    .filter(namespace_id.eq(self.id))
+                    .filter(group_id.eq(transfer.from_group_id))
+                    .execute(conn)?;
+
+                let reason = format!("Namespace transferred to group {}", transfer.to_group_id);
+                diesel::insert_into(permission_audit)
+                    .values(NewPermissionAudit::revocation(
+                        self.id,
+                        transfer.from_group_id,
+                        None,
+                        Some(&reason),
+                    ))
+                    .execute(conn)?;
+            }
+
+            Ok(permissions
+                .filter(namespace_id.eq(self.id))
+                .order_by(group_id)
+                .load::<Permission>(conn)?)
+        })
+    }
+}
+
 impl PermissionController for Namespace {}
 impl PermissionController for NamespaceID {}
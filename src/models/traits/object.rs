@@ -1,10 +1,15 @@
-use crate::db::DbPool;
+use crate::config::{get_config, ExternalIdScope};
+use crate::db::{with_connection, DbPool};
 use crate::errors::ApiError;
 use crate::models::traits::GroupAccessors;
 
 use crate::models::class::HubuumClass;
 use crate::models::namespace::Namespace;
-use crate::models::object::{HubuumObject, HubuumObjectID, NewHubuumObject, UpdateHubuumObject};
+use crate::models::object::{
+    BatchUpdateHubuumObject, HubuumObject, HubuumObjectID, NewHubuumObject, NewValidationAudit,
+    UpdateHubuumObject, UpsertHubuumObject,
+};
+use crate::models::output::HubuumObjectWithRelations;
 use crate::models::permissions::{NewPermission, Permission, Permissions, PermissionsList};
 use crate::models::user::User;
 use crate::traits::{
@@ -26,23 +31,236 @@ impl CanSave for HubuumObject {
             hubuum_class_id: Some(self.hubuum_class_id),
             data: Some(self.data.clone()),
             description: Some(self.description.clone()),
+            external_id: Some(self.external_id.clone()),
         };
         updated_object.update(pool, self.id).await
     }
 }
 
+/// Ensure that an external ID is not used by any other object in the given scope.
+///
+/// For [`ExternalIdScope::Namespace`] this is enforced by a unique index in the database, so
+/// there is nothing to check. For [`ExternalIdScope::Global`] we take a transaction level
+/// advisory lock on the external ID before checking, so this must be called from within the
+/// transaction that creates or updates the object.
+///
+/// ### Returns
+///
+/// * Nothing if the external ID is available, or an ApiError::Conflict if it is in use.
+pub(crate) fn ensure_external_id_available(
+    conn: &mut PgConnection,
+    scope: ExternalIdScope,
+    external_id: &str,
+    object_id: Option<i32>,
+) -> Result<(), ApiError> {
+    use crate::schema::hubuumobject::dsl::{external_id as eid, hubuumobject, id};
+    use diesel::dsl::{exists, sql};
+    use diesel::sql_types::{Bool, Text};
+
+    if scope == ExternalIdScope::Namespace {
+        return Ok(());
+    }
+
+    diesel::select(
+        sql::<Bool>("pg_advisory_xact_lock(hashtext(")
+            .bind::<Text, _>(external_id)
+            .sql(")) IS NOT NULL"),
+    )
+    .execute(conn)?;
+
+    let in_use = diesel::select(exists(
+        hubuumobject
+            .filter(eid.eq(external_id))
+            .filter(id.ne(object_id.unwrap_or(0))),
+    ))
+    .get_result::<bool>(conn)?;
+
+    if in_use {
+        return Err(ApiError::Conflict(format!(
+            "External ID '{}' is already in use",
+            external_id
+        )));
+    }
+
+    Ok(())
+}
+
+impl UpsertHubuumObject {
+    /// Create or update the object with the given external ID in a single transaction.
+    ///
+    /// The caller is expected to have looked up the current object (if any) to check
+    /// permissions, and passes its ID as `expected_id`. If the object has been created or
+    /// removed by someone else in the meantime we bail out with a conflict rather than
+    /// performing an operation the permission check did not cover.
+    ///
+    /// ### Returns
+    ///
+    /// * The resulting object, and whether or not it was created.
+    pub async fn upsert(
+        &self,
+        pool: &DbPool,
+        class_id: i32,
+        eid: &str,
+        expected_id: Option<i32>,
+    ) -> Result<(HubuumObject, bool), ApiError> {
+        use crate::schema::hubuumobject::dsl::*;
+
+        let scope = get_config().await.object_external_id_scope;
+
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            let existing = hubuumobject
+                .filter(namespace_id.eq(self.namespace_id))
+                .filter(external_id.eq(eid))
+                .for_update()
+                .first::<HubuumObject>(conn)
+                .optional()?;
+
+            if existing.as_ref().map(|o| o.id) != expected_id {
+                return Err(ApiError::Conflict(format!(
+                    "Object with external ID '{}' was modified concurrently, please retry",
+                    eid
+                )));
+            }
+
+            match existing {
+                Some(object) => {
+                    let object = diesel::update(hubuumobject)
+                        .filter(id.eq(object.id))
+                        .set((
+                            name.eq(&self.name),
+                            data.eq(&self.data),
+                            description.eq(&self.description),
+                        ))
+                        .get_result::<HubuumObject>(conn)?;
+                    Ok((object, false))
+                }
+                None => {
+                    ensure_external_id_available(conn, scope, eid, None)?;
+                    let object = diesel::insert_into(hubuumobject)
+                        .values(NewHubuumObject {
+                            name: self.name.clone(),
+                            namespace_id: self.namespace_id,
+                            hubuum_class_id: class_id,
+                            data: self.data.clone(),
+                            description: self.description.clone(),
+                            external_id: Some(eid.to_string()),
+                        })
+                        .get_result::<HubuumObject>(conn)?;
+                    Ok((object, true))
+                }
+            }
+        })
+    }
+}
+
+impl NewHubuumObject {
+    /// Insert the object, expected to be called from within a transaction, see
+    /// [`ensure_external_id_available`].
+    fn insert(
+        &self,
+        conn: &mut PgConnection,
+        scope: ExternalIdScope,
+    ) -> Result<HubuumObject, ApiError> {
+        use crate::schema::hubuumobject::dsl::*;
+
+        if let Some(new_external_id) = &self.external_id {
+            ensure_external_id_available(conn, scope, new_external_id, None)?;
+        }
+
+        diesel::insert_into(hubuumobject)
+            .values(self)
+            .get_result::<HubuumObject>(conn)
+            .map_err(ApiError::from)
+    }
+
+    /// Save a batch of objects in a single transaction, so either all or none are created.
+    ///
+    /// ### Returns
+    ///
+    /// * The created objects, in the order they were given.
+    pub async fn save_all(
+        pool: &DbPool,
+        objects: &[NewHubuumObject],
+    ) -> Result<Vec<HubuumObject>, ApiError> {
+        let scope = get_config().await.object_external_id_scope;
+
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            objects
+                .iter()
+                .map(|object| object.insert(conn, scope))
+                .collect()
+        })
+    }
+}
+
 impl CanSave for NewHubuumObject {
     type Output = HubuumObject;
 
     async fn save(&self, pool: &DbPool) -> Result<Self::Output, ApiError> {
-        use crate::schema::hubuumobject::dsl::*;
+        let scope = get_config().await.object_external_id_scope;
 
         let mut conn = pool.get()?;
-        let result = diesel::insert_into(hubuumobject)
-            .values(self)
-            .get_result::<Self::Output>(&mut conn)?;
+        conn.transaction(|conn| self.insert(conn, scope))
+    }
+}
+
+impl CanSave for NewValidationAudit {
+    type Output = ();
+
+    async fn save(&self, pool: &DbPool) -> Result<Self::Output, ApiError> {
+        use crate::schema::validation_audit::dsl::validation_audit;
+
+        with_connection(pool, |conn| {
+            diesel::insert_into(validation_audit)
+                .values(self)
+                .execute(conn)
+        })?;
+        Ok(())
+    }
+}
+
+impl UpdateHubuumObject {
+    /// Apply the changes to an object, expected to be called from within a transaction, see
+    /// [`ensure_external_id_available`].
+    fn apply(
+        &self,
+        conn: &mut PgConnection,
+        scope: ExternalIdScope,
+        object_id: i32,
+    ) -> Result<HubuumObject, ApiError> {
+        use crate::schema::hubuumobject::dsl::*;
+
+        if let Some(Some(new_external_id)) = &self.external_id {
+            ensure_external_id_available(conn, scope, new_external_id, Some(object_id))?;
+        }
 
-        Ok(result)
+        diesel::update(hubuumobject)
+            .filter(id.eq(object_id))
+            .set(self)
+            .get_result::<HubuumObject>(conn)
+            .map_err(ApiError::from)
+    }
+
+    /// Update a batch of objects in a single transaction, so either all or none are updated.
+    ///
+    /// ### Returns
+    ///
+    /// * The updated objects, in the order they were given.
+    pub async fn update_all(
+        pool: &DbPool,
+        updates: &[BatchUpdateHubuumObject],
+    ) -> Result<Vec<HubuumObject>, ApiError> {
+        let scope = get_config().await.object_external_id_scope;
+
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            updates
+                .iter()
+                .map(|update| update.update.apply(conn, scope, update.id))
+                .collect()
+        })
     }
 }
 
@@ -50,15 +268,22 @@ impl CanUpdate for UpdateHubuumObject {
     type Output = HubuumObject;
 
     async fn update(&self, pool: &DbPool, object_id: i32) -> Result<Self::Output, ApiError> {
-        use crate::schema::hubuumobject::dsl::*;
+        let scope = get_config().await.object_external_id_scope;
 
         let mut conn = pool.get()?;
-        let result = diesel::update(hubuumobject)
-            .filter(id.eq(object_id))
-            .set(self)
-            .get_result::<Self::Output>(&mut conn)?;
+        conn.transaction(|conn| self.apply(conn, scope, object_id))
+    }
+}
+
+impl HubuumObject {
+    /// Delete a batch of objects in a single statement, so either all or none are deleted.
+    pub async fn delete_all(pool: &DbPool, object_ids: &[i32]) -> Result<(), ApiError> {
+        use crate::schema::hubuumobject::dsl::{hubuumobject, id};
+
+        let mut conn = pool.get()?;
+        diesel::delete(hubuumobject.filter(id.eq_any(object_ids))).execute(&mut conn)?;
 
-        Ok(result)
+        Ok(())
     }
 }
 
@@ -86,6 +311,16 @@ impl SelfAccessors<HubuumObject> for HubuumObject {
     }
 }
 
+impl SelfAccessors<HubuumObjectWithRelations> for HubuumObjectWithRelations {
+    fn id(&self) -> i32 {
+        self.object.id
+    }
+
+    async fn instance(&self, _pool: &DbPool) -> Result<HubuumObjectWithRelations, ApiError> {
+        Ok(self.clone())
+    }
+}
+
 impl NamespaceAccessors for HubuumObject {
     async fn namespace(&self, pool: &DbPool) -> Result<Namespace, ApiError> {
         use crate::schema::namespaces::dsl::{id, namespaces};
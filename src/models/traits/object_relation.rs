@@ -122,6 +122,7 @@ impl ObjectClosureView {
             description: self.descendant_description.clone(),
             created_at: self.descendant_created_at,
             updated_at: self.descendant_updated_at,
+            external_id: self.descendant_external_id.clone(),
         }
     }
 
@@ -135,6 +136,7 @@ impl ObjectClosureView {
             description: self.descendant_description.clone(),
             created_at: self.descendant_created_at,
             updated_at: self.descendant_updated_at,
+            external_id: self.descendant_external_id.clone(),
             path: self.path.clone(),
         }
     }
@@ -149,6 +151,7 @@ impl ObjectClosureView {
             description: self.ancestor_description.clone(),
             created_at: self.ancestor_created_at,
             updated_at: self.ancestor_updated_at,
+            external_id: self.ancestor_external_id.clone(),
         }
     }
 }
@@ -79,6 +79,10 @@ impl ExpandNamespaceFromMap<HubuumClassExpanded> for HubuumClass {
                     description: "Unknown".to_string(),
                     created_at: chrono::NaiveDateTime::default(),
                     updated_at: chrono::NaiveDateTime::default(),
+                    write_rate_limit: None,
+                    object_quota: None,
+                    default_class_permissions: None,
+                    attachment_size_limit: None,
                 }
             }
         };
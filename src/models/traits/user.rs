@@ -1,8 +1,12 @@
 use argon2::password_hash::rand_core::le;
 use diesel::dsl::Filter;
 use diesel::query_builder;
-use diesel::sql_types::Integer;
-use diesel::{pg::Pg, ExpressionMethods, JoinOnDsl, QueryDsl, RunQueryDsl, Table};
+use diesel::dsl::sql;
+use diesel::expression::BoxableExpression;
+use diesel::sql_types::{BigInt, Bool, Double, Integer, Text};
+use diesel::{
+    pg::Pg, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, QueryDsl, RunQueryDsl, Table,
+};
 
 use std::iter::IntoIterator;
 
@@ -10,12 +14,16 @@ use futures::future::try_join_all;
 use tracing::debug;
 
 use crate::api::v1::handlers::namespaces;
-use crate::models::search::{FilterField, ParsedQueryParam, QueryParamsExt, SearchOperator};
+use crate::models::search::{
+    FilterField, ParsedQueryParam, QueryNode, QueryParamsExt, RelationExpansion, SearchOperator,
+    SearchOrder,
+};
 use crate::models::traits::ExpandNamespaceFromMap;
 use crate::models::{
-    class, group, permissions, ClassClosureView, Group, HubuumClass, HubuumClassExpanded,
-    HubuumClassRelation, HubuumObject, HubuumObjectRelation, Namespace, ObjectClosureView,
-    Permission, Permissions, User, UserID,
+    class, group, permissions, AnyRelation, ClassClosureView, Group, HubuumClass,
+    HubuumClassExpanded, HubuumClassRelation, HubuumObject, HubuumObjectRelation,
+    HubuumObjectWithRelations, Namespace, ObjectClosureView, Permission, Permissions,
+    RelatedObjects, User, UserID,
 };
 
 use crate::schema::hubuumclass::namespace_id;
@@ -23,6 +31,7 @@ use crate::schema::{hubuumclass, hubuumobject};
 use crate::traits::{ClassAccessors, NamespaceAccessors, SelfAccessors};
 
 use crate::db::{with_connection, DbPool};
+use crate::db::scope::scope_permissions;
 use crate::errors::ApiError;
 use crate::utilities::extensions::CustomStringExtensions;
 
@@ -55,14 +64,14 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
         let mut base_query = namespaces
             .filter(
                 namespace_id.eq_any(
-                    permissions
+                    scope_permissions(permissions.into_boxed())
                         .filter(group_id.eq_any(group_id_subquery))
                         .select(permissions_nid),
                 ),
             )
             .into_boxed();
 
-        for param in query_params {
+        for param in &query_params {
             use crate::models::search::{DataType, SearchOperator};
             use crate::{boolean_search, date_search, numeric_search, string_search};
             let operator = param.operator.clone();
@@ -87,12 +96,14 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
                 ),
                 FilterField::Name => string_search!(
                     base_query,
+                    query_params,
                     param,
                     operator,
                     crate::schema::namespaces::dsl::name
                 ),
                 FilterField::Description => string_search!(
                     base_query,
+                    query_params,
                     param,
                     operator,
                     crate::schema::namespaces::dsl::description
@@ -149,44 +160,19 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
         );
 
         let mut base_query = hubuumclass
-            .filter(hubuum_classes_nid.eq_any(namespace_ids))
+            .filter(hubuum_classes_nid.eq_any(namespace_ids.clone()))
             .into_boxed();
 
         let json_schema_queries = query_params.json_schemas()?;
-        if !json_schema_queries.is_empty() {
-            debug!(
-                message = "Searching classes",
-                stage = "JSON Schema",
-                user_id = self.id(),
-                query_params = ?json_schema_queries
-            );
-
-            let json_schema_integers = self.json_schema_subquery(pool, json_schema_queries)?;
-
-            if json_schema_integers.is_empty() {
-                debug!(
-                    message = "Searching classes",
-                    stage = "JSON Schema",
-                    user_id = self.id(),
-                    result = "No class IDs found, returning empty result"
-                );
-                return Ok(vec![]);
-            }
-
-            debug!(
-                message = "Searching classes",
-                stage = "JSON Schema",
-                user_id = self.id(),
-                result = "Found class IDs",
-                class_ids = ?json_schema_integers
-            );
-
-            base_query = base_query.filter(hubuum_class_id.eq_any(json_schema_integers));
+        for param in &json_schema_queries {
+            param.as_json_sql()?;
         }
 
-        for param in query_params {
+        for param in &query_params {
             use crate::models::search::{DataType, SearchOperator};
-            use crate::{boolean_search, date_search, numeric_search, string_search};
+            use crate::{
+                boolean_search, changed_since_search, date_search, numeric_search, string_search,
+            };
             let operator = param.operator.clone();
             match param.field {
                 FilterField::Id => numeric_search!(
@@ -213,14 +199,23 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
                     operator,
                     crate::schema::hubuumclass::dsl::updated_at
                 ),
+                FilterField::ChangedSince => changed_since_search!(
+                    base_query,
+                    param,
+                    operator,
+                    crate::schema::hubuumclass::dsl::created_at,
+                    crate::schema::hubuumclass::dsl::updated_at
+                ),
                 FilterField::Name => string_search!(
                     base_query,
+                    query_params,
                     param,
                     operator,
                     crate::schema::hubuumclass::dsl::name
                 ),
                 FilterField::Description => string_search!(
                     base_query,
+                    query_params,
                     param,
                     operator,
                     crate::schema::hubuumclass::dsl::description
@@ -231,6 +226,8 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
                     operator,
                     crate::schema::hubuumclass::dsl::validate_schema
                 ),
+                FilterField::Limit => base_query = base_query.limit(param.value_as_count()?),
+                FilterField::Offset => base_query = base_query.offset(param.value_as_count()?),
                 FilterField::JsonSchema => {}  // Handled above
                 FilterField::Permissions => {} // Handled above
                 _ => {
@@ -242,12 +239,56 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
             }
         }
 
+        // Without readable namespaces nothing can match, so skip running the query. This is done
+        // after validating the query, so that invalid queries are refused for every user.
+        if namespace_ids.is_empty() {
+            debug!(
+                message = "Searching classes",
+                stage = "Namespace IDs",
+                user_id = self.id(),
+                result = "No readable namespaces, returning empty result"
+            );
+            return Ok(vec![]);
+        }
+
+        if !json_schema_queries.is_empty() {
+            debug!(
+                message = "Searching classes",
+                stage = "JSON Schema",
+                user_id = self.id(),
+                query_params = ?json_schema_queries
+            );
+
+            let json_schema_integers = self.json_schema_subquery(pool, json_schema_queries)?;
+
+            if json_schema_integers.is_empty() {
+                debug!(
+                    message = "Searching classes",
+                    stage = "JSON Schema",
+                    user_id = self.id(),
+                    result = "No class IDs found, returning empty result"
+                );
+                return Ok(vec![]);
+            }
+
+            debug!(
+                message = "Searching classes",
+                stage = "JSON Schema",
+                user_id = self.id(),
+                result = "Found class IDs",
+                class_ids = ?json_schema_integers
+            );
+
+            base_query = base_query.filter(hubuum_class_id.eq_any(json_schema_integers));
+        }
+
         trace_query!(base_query, "Searching classes");
 
         let result = with_connection(pool, |conn| {
             base_query
                 .select(hubuumclass::all_columns())
                 .distinct() // TODO: Is it the joins that makes this required?
+                .order_by(hubuum_class_id) // Stable pages with limit and offset
                 .load::<HubuumClass>(conn)
         })?;
 
@@ -298,11 +339,94 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
             namespace_ids = ?namespace_ids
         );
 
+        // Objects are only visible if their class is readable as well, and the class may live in
+        // another namespace than the object. See docs/permissions.md for the full rule.
+        let class_namespace_ids: Vec<i32> = self
+            .namespaces(pool, &[Permissions::ReadClass])
+            .await?
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+
+        // Relations only count for has_relation if the user may read them, see below.
+        let relation_namespace_ids: Vec<i32> = if query_params
+            .iter()
+            .any(|p| p.field == FilterField::HasRelation)
+        {
+            self.namespaces(pool, &[Permissions::ReadObjectRelation])
+                .await?
+                .into_iter()
+                .map(|n| n.id)
+                .collect()
+        } else {
+            vec![]
+        };
+
+        let readable_classes = hubuumclass::table
+            .filter(hubuumclass::namespace_id.eq_any(class_namespace_ids.clone()))
+            .select(hubuumclass::id);
+
         let mut base_query = hubuumobject
-            .filter(hubuum_object_nid.eq_any(namespace_ids))
+            .filter(hubuum_object_nid.eq_any(namespace_ids.clone()))
+            .filter(hubuum_class_id.eq_any(readable_classes))
             .into_boxed();
 
         let json_data_queries = query_params.json_datas(FilterField::JsonData)?;
+        for param in &json_data_queries {
+            param.as_json_sql()?;
+        }
+
+        for param in &query_params {
+            base_query = match param.field {
+                FilterField::Limit => base_query.limit(param.value_as_count()?),
+                FilterField::Offset => base_query.offset(param.value_as_count()?),
+                FilterField::JsonData => base_query, // Handled above
+                FilterField::Permissions => base_query, // Handled above
+                FilterField::OrderBy | FilterField::Seed => base_query, // Handled below
+                _ => filter_objects(base_query, param, &query_params, &relation_namespace_ids)?,
+            };
+        }
+
+        // A seeded order hashes the object IDs with the seed rather than using setseed() and
+        // random(), so the sample doesn't depend on the order Postgres happens to scan the rows
+        // in, and no seed is left behind on the pooled connection.
+        base_query = match query_params.order()? {
+            Some(SearchOrder::Random { seed: Some(seed) }) => base_query
+                .order(
+                    sql::<Text>("md5(hubuumobject.id::text || ':' || ")
+                        .bind::<BigInt, _>(seed)
+                        .sql("::text)"),
+                )
+                .then_order_by(hubuum_object_id),
+            Some(SearchOrder::Random { seed: None }) => {
+                base_query.order(sql::<Double>("random()"))
+            }
+            // Sorted by ID for stable pages with limit and offset.
+            // TODO: Is it the joins that makes distinct required?
+            None => base_query.distinct().order(hubuum_object_id),
+        };
+
+        // As for classes, skip running the query if nothing can match, once it is validated.
+        if namespace_ids.is_empty() {
+            debug!(
+                message = "Searching objects",
+                stage = "Namespace IDs",
+                user_id = self.id(),
+                result = "No readable namespaces, returning empty result"
+            );
+            return Ok(vec![]);
+        }
+
+        if class_namespace_ids.is_empty() {
+            debug!(
+                message = "Searching objects",
+                stage = "Namespace IDs",
+                user_id = self.id(),
+                result = "No readable classes, returning empty result"
+            );
+            return Ok(vec![]);
+        }
+
         if !json_data_queries.is_empty() {
             debug!(
                 message = "Searching objects",
@@ -311,7 +435,8 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
                 query_params = ?json_data_queries
             );
 
-            let json_data_integers = self.json_data_subquery(pool, json_data_queries)?;
+            let json_data_integers =
+                self.json_data_subquery(pool, json_data_queries, query_params.single_class())?;
 
             if json_data_integers.is_empty() {
                 debug!(
@@ -334,82 +459,338 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
             base_query = base_query.filter(hubuum_object_id.eq_any(json_data_integers));
         }
 
-        for param in query_params {
-            use crate::models::search::{DataType, SearchOperator};
-            use crate::{boolean_search, date_search, numeric_search, string_search};
-            let operator = param.operator.clone();
-            match param.field {
-                FilterField::Id => numeric_search!(
-                    base_query,
-                    param,
-                    operator,
-                    crate::schema::hubuumobject::dsl::id
-                ),
-                FilterField::Namespaces => numeric_search!(
-                    base_query,
-                    param,
-                    operator,
-                    crate::schema::hubuumobject::dsl::namespace_id
-                ),
-                FilterField::CreatedAt => date_search!(
-                    base_query,
-                    param,
-                    operator,
-                    crate::schema::hubuumobject::dsl::created_at
-                ),
-                FilterField::UpdatedAt => date_search!(
-                    base_query,
-                    param,
-                    operator,
-                    crate::schema::hubuumobject::dsl::updated_at
-                ),
-                FilterField::Name => string_search!(
-                    base_query,
-                    param,
-                    operator,
-                    crate::schema::hubuumobject::dsl::name
-                ),
-                FilterField::Description => string_search!(
-                    base_query,
-                    param,
-                    operator,
-                    crate::schema::hubuumobject::dsl::description
-                ),
-                FilterField::Classes => numeric_search!(
-                    base_query,
-                    param,
-                    operator,
-                    crate::schema::hubuumobject::dsl::hubuum_class_id
-                ),
-                FilterField::ClassId => numeric_search!(
-                    base_query,
-                    param,
-                    operator,
-                    crate::schema::hubuumobject::dsl::hubuum_class_id
-                ),
-                FilterField::JsonData => {}    // Handled above
-                FilterField::Permissions => {} // Handled above
-                _ => {
-                    return Err(ApiError::BadRequest(format!(
-                        "Field '{}' isn't searchable (or does not exist) for objects",
-                        param.field
-                    )))
-                }
-            }
-        }
-
         trace_query!(base_query, "Searching objects");
 
         let result = with_connection(pool, |conn| {
             base_query
                 .select(hubuumobject::all_columns())
-                .distinct() // TODO: Is it the joins that makes this required?
                 .load::<HubuumObject>(conn)
         })?;
 
         Ok(result)
     }
 
+    /// ## List the distinct names of the classes the user may read
+    ///
+    /// Each name is listed once, sorted by name.
+    async fn search_class_names(&self, pool: &DbPool) -> Result<Vec<String>, ApiError> {
+        let namespace_ids: Vec<i32> = self
+            .namespaces(pool, &[Permissions::ReadClass, Permissions::ReadCollection])
+            .await?
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+
+        if namespace_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        with_connection(pool, |conn| {
+            hubuumclass::table
+                .filter(hubuumclass::namespace_id.eq_any(namespace_ids))
+                .select(hubuumclass::name)
+                .distinct()
+                .order_by(hubuumclass::name)
+                .load::<String>(conn)
+        })
+    }
+
+    /// ## Find the classes an object search can not see
+    ///
+    /// Objects are only visible if their class is readable, so a search restricted to classes in
+    /// namespaces without ReadClass silently finds nothing for those classes. This returns the
+    /// given classes that exist but are filtered out that way, sorted by ID.
+    async fn unreadable_classes(
+        &self,
+        pool: &DbPool,
+        class_ids: &[i32],
+    ) -> Result<Vec<i32>, ApiError> {
+        if class_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let class_namespace_ids: Vec<i32> = self
+            .namespaces(pool, &[Permissions::ReadClass])
+            .await?
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+
+        with_connection(pool, |conn| {
+            hubuumclass::table
+                .filter(hubuumclass::id.eq_any(class_ids))
+                .filter(hubuumclass::namespace_id.ne_all(class_namespace_ids))
+                .select(hubuumclass::id)
+                .order_by(hubuumclass::id)
+                .load::<i32>(conn)
+        })
+    }
+
+    /// ## Search objects with a boolean filter tree
+    ///
+    /// Every condition in the tree is turned into a filter on the object IDs, and the filters
+    /// are combined with the AND and OR of the tree into a single query. As for other object
+    /// searches, only objects the user may read are returned, sorted by ID.
+    async fn search_objects_by_filter(
+        &self,
+        pool: &DbPool,
+        filter: &QueryNode,
+    ) -> Result<Vec<HubuumObject>, ApiError> {
+        use crate::models::PermissionFilter;
+        use crate::schema::hubuumobject::dsl::{
+            hubuum_class_id, hubuumobject, id as hubuum_object_id,
+            namespace_id as hubuum_object_nid,
+        };
+
+        let params = filter.params();
+
+        debug!(
+            message = "Searching objects by filter",
+            stage = "Starting",
+            user_id = self.id(),
+            conditions = params.len()
+        );
+
+        let namespace_ids: Vec<i32> = self
+            .namespaces(
+                pool,
+                &[Permissions::ReadObject, Permissions::ReadCollection],
+            )
+            .await?
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+
+        let class_namespace_ids: Vec<i32> = self
+            .namespaces(pool, &[Permissions::ReadClass])
+            .await?
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+
+        // Relations only count for has_relation if the user may read them, see search_objects.
+        let relation_namespace_ids: Vec<i32> =
+            if params.iter().any(|p| p.field == FilterField::HasRelation) {
+                self.namespaces(pool, &[Permissions::ReadObjectRelation])
+                    .await?
+                    .into_iter()
+                    .map(|n| n.id)
+                    .collect()
+            } else {
+                vec![]
+            };
+
+        for param in &params {
+            if param.field == FilterField::JsonData {
+                param.as_json_sql()?;
+            }
+        }
+
+        let mut conditions: Vec<ObjectCondition> = Vec::with_capacity(params.len());
+        for param in params {
+            let condition: ObjectCondition = match param.field {
+                FilterField::JsonData => {
+                    let ids = self.json_data_subquery(pool, vec![param], None)?;
+                    Box::new(hubuum_object_id.eq_any(ids))
+                }
+                FilterField::Permissions => {
+                    let mut permission_list = vec![param.clone()].permissions()?;
+                    permission_list
+                        .ensure_contains(&[Permissions::ReadObject, Permissions::ReadCollection]);
+                    let ids: Vec<i32> = self
+                        .namespaces(pool, &permission_list)
+                        .await?
+                        .into_iter()
+                        .map(|n| n.id)
+                        .collect();
+                    Box::new(hubuum_object_nid.eq_any(ids))
+                }
+                _ => {
+                    // A condition on its own, so a repeated string operator is not OR'ed.
+                    let leaf = vec![param.clone()];
+                    let matching = filter_objects(
+                        hubuumobject.into_boxed(),
+                        &leaf[0],
+                        &leaf,
+                        &relation_namespace_ids,
+                    )?
+                    .select(hubuum_object_id);
+                    Box::new(hubuum_object_id.eq_any(matching))
+                }
+            };
+            conditions.push(condition);
+        }
+
+        let readable_classes = hubuumclass::table
+            .filter(hubuumclass::namespace_id.eq_any(class_namespace_ids))
+            .select(hubuumclass::id);
+
+        let mut base_query = hubuumobject
+            .filter(hubuum_object_nid.eq_any(namespace_ids))
+            .filter(hubuum_class_id.eq_any(readable_classes))
+            .into_boxed();
+
+        if let Some(condition) = combine_object_conditions(filter, &mut conditions.into_iter()) {
+            base_query = base_query.filter(condition);
+        }
+
+        trace_query!(base_query, "Searching objects by filter");
+
+        with_connection(pool, |conn| {
+            base_query
+                .order_by(hubuum_object_id)
+                .load::<HubuumObject>(conn)
+        })
+    }
+
+    /// ## Find the objects in a class whose data equals a document
+    ///
+    /// The comparison is done on jsonb, which does not preserve key order or whitespace, so
+    /// documents that only differ in those match. Only objects the user may read are returned,
+    /// sorted by ID.
+    async fn search_objects_by_data(
+        &self,
+        pool: &DbPool,
+        class_id: i32,
+        document: &serde_json::Value,
+    ) -> Result<Vec<HubuumObject>, ApiError> {
+        use crate::schema::hubuumobject::dsl::{
+            data, hubuum_class_id, hubuumobject, id as hubuum_object_id,
+            namespace_id as hubuum_object_nid,
+        };
+
+        debug!(
+            message = "Searching objects by data",
+            user_id = self.id(),
+            class_id = class_id
+        );
+
+        let namespace_ids: Vec<i32> = self
+            .namespaces(
+                pool,
+                &[Permissions::ReadObject, Permissions::ReadCollection],
+            )
+            .await?
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+
+        // As for other object searches, the class has to be readable as well.
+        if !self.unreadable_classes(pool, &[class_id]).await?.is_empty() {
+            return Ok(vec![]);
+        }
+
+        with_connection(pool, |conn| {
+            hubuumobject
+                .filter(hubuum_class_id.eq(class_id))
+                .filter(hubuum_object_nid.eq_any(namespace_ids))
+                .filter(data.eq(document))
+                .order_by(hubuum_object_id)
+                .load::<HubuumObject>(conn)
+        })
+    }
+
+    /// ## Embed the directly related objects in a list of objects
+    ///
+    /// The related objects of all the objects are fetched with a single grouped query, in
+    /// either direction of the relations. As for relation searches, a relation is only shown if
+    /// the user has ReadObjectRelation on the namespaces of both ends. Related objects the user
+    /// may not read are left out, and the names are only included if asked for. The order of
+    /// the objects is kept.
+    async fn expand_object_relations(
+        &self,
+        pool: &DbPool,
+        objects: Vec<HubuumObject>,
+        expansion: RelationExpansion,
+    ) -> Result<Vec<HubuumObjectWithRelations>, ApiError> {
+        use diesel::sql_types::Array;
+        use std::collections::HashMap;
+
+        let relation_namespace_ids: Vec<i32> = if objects.is_empty() {
+            vec![]
+        } else {
+            self.namespaces(pool, &[Permissions::ReadObjectRelation])
+                .await?
+                .into_iter()
+                .map(|n| n.id)
+                .collect()
+        };
+
+        // Only the relations of objects in namespaces with ReadObjectRelation may be shown.
+        let object_ids: Vec<i32> = objects
+            .iter()
+            .filter(|o| relation_namespace_ids.contains(&o.namespace_id))
+            .map(|o| o.id)
+            .collect();
+
+        let mut related: HashMap<i32, RelatedObjects> = if object_ids.is_empty() {
+            HashMap::new()
+        } else {
+            let object_namespace_ids: Vec<i32> = self
+                .namespaces(
+                    pool,
+                    &[
+                        Permissions::ReadObject,
+                        Permissions::ReadCollection,
+                        Permissions::ReadObjectRelation,
+                    ],
+                )
+                .await?
+                .into_iter()
+                .map(|n| n.id)
+                .collect();
+            let class_namespace_ids: Vec<i32> = self
+                .namespaces(pool, &[Permissions::ReadClass])
+                .await?
+                .into_iter()
+                .map(|n| n.id)
+                .collect();
+
+            let query = diesel::sql_query(
+                "SELECT r.object_id, \
+                    array_agg(o.id ORDER BY o.id) AS related_ids, \
+                    array_agg(o.name ORDER BY o.id) AS related_names \
+                 FROM ( \
+                    SELECT from_hubuum_object_id AS object_id, to_hubuum_object_id AS related_id \
+                    FROM hubuumobject_relation WHERE from_hubuum_object_id = ANY($1) \
+                    UNION \
+                    SELECT to_hubuum_object_id, from_hubuum_object_id \
+                    FROM hubuumobject_relation WHERE to_hubuum_object_id = ANY($1) \
+                 ) r \
+                 JOIN hubuumobject o ON o.id = r.related_id \
+                 JOIN hubuumclass c ON c.id = o.hubuum_class_id \
+                 WHERE o.namespace_id = ANY($2) AND c.namespace_id = ANY($3) \
+                 GROUP BY r.object_id",
+            )
+            .bind::<Array<Integer>, _>(&object_ids)
+            .bind::<Array<Integer>, _>(&object_namespace_ids)
+            .bind::<Array<Integer>, _>(&class_namespace_ids);
+
+            with_connection(pool, |conn| query.load::<RelatedObjects>(conn))?
+                .into_iter()
+                .map(|r| (r.object_id, r))
+                .collect()
+        };
+
+        Ok(objects
+            .into_iter()
+            .map(|object| {
+                let (ids, names) = related
+                    .remove(&object.id)
+                    .map(|r| (r.related_ids, r.related_names))
+                    .unwrap_or_default();
+                HubuumObjectWithRelations {
+                    object,
+                    related_object_ids: ids,
+                    related_object_names: match expansion {
+                        RelationExpansion::Ids => None,
+                        RelationExpansion::IdsAndNames => Some(names),
+                    },
+                }
+            })
+            .collect())
+    }
+
     async fn search_class_relations(
         &self,
         pool: &DbPool,
@@ -724,6 +1105,79 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
         Ok(result)
     }
 
+    async fn search_relations(
+        &self,
+        pool: &DbPool,
+        query_params: Vec<ParsedQueryParam>,
+    ) -> Result<Vec<AnyRelation>, ApiError> {
+        // Shared search fields (applied to both kinds):
+        // - id (int)
+        // - created_at (date)
+        // - updated_at (date)
+        // Class relation fields (only class relations can match):
+        // - from_classes, to_classes, from_class_name, to_class_name
+        // Object relation fields (only object relations can match):
+        // - from_objects, to_objects, class_relation
+        //
+        // Each kind is searched with its own read permission, so a user may see
+        // object relations in namespaces where they can't see class relations,
+        // or vice versa.
+
+        debug!(
+            message = "Searching relations",
+            stage = "Starting",
+            user_id = self.id(),
+            query_params = ?query_params
+        );
+
+        let mut class_only = false;
+        let mut object_only = false;
+        for param in &query_params {
+            match param.field {
+                FilterField::Id | FilterField::CreatedAt | FilterField::UpdatedAt => {}
+                FilterField::ClassFrom
+                | FilterField::ClassTo
+                | FilterField::ClassFromName
+                | FilterField::ClassToName => class_only = true,
+                FilterField::ObjectFrom | FilterField::ObjectTo | FilterField::ClassRelation => {
+                    object_only = true
+                }
+                _ => {
+                    return Err(ApiError::BadRequest(format!(
+                        "Field '{}' isn't searchable (or does not exist) for relations",
+                        param.field
+                    )))
+                }
+            }
+        }
+
+        let class_search = async {
+            if object_only {
+                Ok(vec![])
+            } else {
+                self.search_class_relations(pool, query_params.clone())
+                    .await
+            }
+        };
+        let object_search = async {
+            if class_only {
+                Ok(vec![])
+            } else {
+                self.search_object_relations(pool, query_params.clone())
+                    .await
+            }
+        };
+
+        let (class_relations, object_relations) =
+            futures::future::try_join(class_search, object_search).await?;
+
+        Ok(class_relations
+            .into_iter()
+            .map(AnyRelation::Class)
+            .chain(object_relations.into_iter().map(AnyRelation::Object))
+            .collect())
+    }
+
     async fn search_objects_related_to<O>(
         &self,
         pool: &DbPool,
@@ -826,16 +1280,40 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
                     numeric_search!(base_query, param, operator, obj::descendant_namespace_id)
                 }
                 FilterField::NameFrom => {
-                    string_search!(base_query, param, operator, obj::ancestor_name)
+                    string_search!(
+                        base_query,
+                        query_params,
+                        param,
+                        operator,
+                        obj::ancestor_name
+                    )
                 }
                 FilterField::NameTo => {
-                    string_search!(base_query, param, operator, obj::descendant_name)
+                    string_search!(
+                        base_query,
+                        query_params,
+                        param,
+                        operator,
+                        obj::descendant_name
+                    )
                 }
                 FilterField::DescriptionFrom => {
-                    string_search!(base_query, param, operator, obj::ancestor_description)
+                    string_search!(
+                        base_query,
+                        query_params,
+                        param,
+                        operator,
+                        obj::ancestor_description
+                    )
                 }
                 FilterField::DescriptionTo => {
-                    string_search!(base_query, param, operator, obj::descendant_description)
+                    string_search!(
+                        base_query,
+                        query_params,
+                        param,
+                        operator,
+                        obj::descendant_description
+                    )
                 }
                 FilterField::CreatedAtFrom => {
                     date_search!(base_query, param, operator, obj::ancestor_created_at)
@@ -897,6 +1375,158 @@ pub trait Search: SelfAccessors<User> + GroupAccessors + UserNamespaceAccessors
     }
 }
 
+/// A condition on objects, for combining search filters with AND and OR.
+type ObjectCondition = Box<dyn BoxableExpression<hubuumobject::table, Pg, SqlType = Bool>>;
+
+/// ## Apply a search parameter to an object query
+///
+/// This covers the parameters that filter on the objects themselves. Parameters that need
+/// more context, such as paging, permissions and JSON data, are handled by the callers.
+/// `query_params` is the list `param` is from, as repeated string operators on a field are
+/// OR'ed. Relations only count for `has_relation` if both ends are in `relation_namespace_ids`.
+fn filter_objects(
+    mut base_query: hubuumobject::BoxedQuery<'static, Pg>,
+    param: &ParsedQueryParam,
+    query_params: &Vec<ParsedQueryParam>,
+    relation_namespace_ids: &[i32],
+) -> Result<hubuumobject::BoxedQuery<'static, Pg>, ApiError> {
+    use crate::models::search::{DataType, SearchOperator};
+    use crate::{boolean_search, changed_since_search, date_search, numeric_search, string_search};
+
+    let operator = param.operator.clone();
+    match param.field {
+        FilterField::Id => numeric_search!(
+            base_query,
+            param,
+            operator,
+            crate::schema::hubuumobject::dsl::id
+        ),
+        FilterField::Namespaces => numeric_search!(
+            base_query,
+            param,
+            operator,
+            crate::schema::hubuumobject::dsl::namespace_id
+        ),
+        FilterField::CreatedAt => date_search!(
+            base_query,
+            param,
+            operator,
+            crate::schema::hubuumobject::dsl::created_at
+        ),
+        FilterField::UpdatedAt => date_search!(
+            base_query,
+            param,
+            operator,
+            crate::schema::hubuumobject::dsl::updated_at
+        ),
+        FilterField::ChangedSince => changed_since_search!(
+            base_query,
+            param,
+            operator,
+            crate::schema::hubuumobject::dsl::created_at,
+            crate::schema::hubuumobject::dsl::updated_at
+        ),
+        FilterField::Name => string_search!(
+            base_query,
+            query_params,
+            param,
+            operator,
+            crate::schema::hubuumobject::dsl::name
+        ),
+        FilterField::Description => string_search!(
+            base_query,
+            query_params,
+            param,
+            operator,
+            crate::schema::hubuumobject::dsl::description
+        ),
+        FilterField::ExternalId => string_search!(
+            base_query,
+            query_params,
+            param,
+            operator,
+            crate::schema::hubuumobject::dsl::external_id
+        ),
+        FilterField::Classes => numeric_search!(
+            base_query,
+            param,
+            operator,
+            crate::schema::hubuumobject::dsl::hubuum_class_id
+        ),
+        FilterField::ClassId => numeric_search!(
+            base_query,
+            param,
+            operator,
+            crate::schema::hubuumobject::dsl::hubuum_class_id
+        ),
+        // Objects filed in another namespace than their class.
+        FilterField::NamespaceMatchesClass => boolean_search!(
+            base_query,
+            param,
+            operator,
+            diesel::dsl::sql::<diesel::sql_types::Bool>(
+                "(hubuumobject.namespace_id = (SELECT hubuumclass.namespace_id \
+                 FROM hubuumclass WHERE hubuumclass.id = hubuumobject.hubuum_class_id))"
+            )
+        ),
+        // Objects with at least one object relation, in either direction. As for relation
+        // searches, only relations with both ends in namespaces with ReadObjectRelation
+        // count.
+        FilterField::HasRelation => boolean_search!(
+            base_query,
+            param,
+            operator,
+            diesel::dsl::sql::<diesel::sql_types::Bool>("(hubuumobject.namespace_id = ANY(")
+                .bind::<diesel::sql_types::Array<Integer>, _>(relation_namespace_ids.to_vec())
+                .sql(
+                    ") AND EXISTS (SELECT 1 FROM hubuumobject_relation r \
+                     JOIN hubuumobject other ON other.id = CASE \
+                     WHEN r.from_hubuum_object_id = hubuumobject.id \
+                     THEN r.to_hubuum_object_id ELSE r.from_hubuum_object_id END \
+                     WHERE (r.from_hubuum_object_id = hubuumobject.id \
+                     OR r.to_hubuum_object_id = hubuumobject.id) \
+                     AND other.namespace_id = ANY("
+                )
+                .bind::<diesel::sql_types::Array<Integer>, _>(relation_namespace_ids.to_vec())
+                .sql(")))")
+        ),
+        _ => {
+            return Err(ApiError::BadRequest(format!(
+                "Field '{}' isn't searchable (or does not exist) for objects",
+                param.field
+            )))
+        }
+    }
+
+    Ok(base_query)
+}
+
+/// ## Combine the conditions of a search filter tree
+///
+/// `conditions` holds a condition for each parameter in the tree, in the order of
+/// [`QueryNode::params`]. Returns None if the tree matches every object, ie for empty AND-groups.
+fn combine_object_conditions(
+    node: &QueryNode,
+    conditions: &mut impl Iterator<Item = ObjectCondition>,
+) -> Option<ObjectCondition> {
+    match node {
+        QueryNode::Param(_) => conditions.next(),
+        QueryNode::And(children) => children
+            .iter()
+            .filter_map(|child| combine_object_conditions(child, conditions))
+            .reduce(|left, right| Box::new(left.and(right))),
+        // An alternative that matches everything makes the whole group match everything.
+        QueryNode::Or(children) => children
+            .iter()
+            .map(|child| combine_object_conditions(child, conditions))
+            .reduce(|left, right| match (left, right) {
+                (Some(left), Some(right)) => Some(Box::new(left.or(right))),
+                _ => None,
+            })
+            .flatten(),
+    }
+}
+
 pub trait GroupAccessors: SelfAccessors<User> {
     /// Return all groups that the user is a member of.
     #[allow(async_fn_in_trait)]
@@ -929,7 +1559,7 @@ pub trait GroupAccessors: SelfAccessors<User> {
       }
     */
 
-    /// Generate a subquery to get all group IDs for a user.
+    /// Generate a subquery to get all active group IDs for a user.
     ///
     /// Note that this does not execute the query, it only creates it.
     ///
@@ -954,9 +1584,13 @@ pub trait GroupAccessors: SelfAccessors<User> {
         &self,
     ) -> crate::schema::user_groups::BoxedQuery<'a, diesel::pg::Pg, diesel::sql_types::Integer>
     {
+        use crate::schema::groups::dsl::{active, groups, id as groups_id};
         use crate::schema::user_groups::dsl::*;
+
+        // Inactive groups are ignored, which suppresses their permission grants.
         user_groups
             .filter(user_id.eq(self.id()))
+            .filter(group_id.eq_any(groups.filter(active.eq(true)).select(groups_id)))
             .select(group_id)
             .into_boxed()
     }
@@ -996,10 +1630,13 @@ pub trait GroupAccessors: SelfAccessors<User> {
         for bind_var in bind_varaibles {
             match bind_var {
                 SQLValue::Integer(i) => query = query.bind::<diesel::sql_types::Integer, _>(i),
+                SQLValue::BigInt(i) => query = query.bind::<diesel::sql_types::BigInt, _>(i),
+                SQLValue::Numeric(n) => query = query.bind::<diesel::sql_types::Text, _>(n),
                 SQLValue::String(s) => query = query.bind::<diesel::sql_types::Text, _>(s),
                 SQLValue::Boolean(b) => query = query.bind::<diesel::sql_types::Bool, _>(b),
                 SQLValue::Float(f) => query = query.bind::<diesel::sql_types::Float8, _>(f),
                 SQLValue::Date(d) => query = query.bind::<diesel::sql_types::Timestamp, _>(d),
+                SQLValue::Null => query = query.bind::<diesel::sql_types::Text, _>("null"),
             }
         }
 
@@ -1019,6 +1656,7 @@ pub trait GroupAccessors: SelfAccessors<User> {
         &self,
         pool: &DbPool,
         json_schema_query_params: Vec<&ParsedQueryParam>,
+        class_id: Option<i32>,
     ) -> Result<Vec<i32>, ApiError> {
         use crate::models::object::ObjectIDResult;
         use crate::models::search::{Operator, SQLValue};
@@ -1040,6 +1678,12 @@ pub trait GroupAccessors: SelfAccessors<User> {
             bind_varaibles.extend(clause.bind_variables);
         }
 
+        // Scoping the query to a class lets Postgres use the class indexes, which are partial on
+        // the class. The ID is inlined rather than bound so the planner can match the predicate.
+        if let Some(class_id) = class_id {
+            raw_sql_clauses.push(format!("hubuum_class_id = {}", class_id));
+        }
+
         let raw_sql = format!("{} {}", raw_sql_prefix, raw_sql_clauses.join(" and "))
             .replace_question_mark_with_indexed_n();
 
@@ -1050,10 +1694,13 @@ pub trait GroupAccessors: SelfAccessors<User> {
         for bind_var in bind_varaibles {
             match bind_var {
                 SQLValue::Integer(i) => query = query.bind::<diesel::sql_types::Integer, _>(i),
+                SQLValue::BigInt(i) => query = query.bind::<diesel::sql_types::BigInt, _>(i),
+                SQLValue::Numeric(n) => query = query.bind::<diesel::sql_types::Text, _>(n),
                 SQLValue::String(s) => query = query.bind::<diesel::sql_types::Text, _>(s),
                 SQLValue::Boolean(b) => query = query.bind::<diesel::sql_types::Bool, _>(b),
                 SQLValue::Float(f) => query = query.bind::<diesel::sql_types::Float8, _>(f),
                 SQLValue::Date(d) => query = query.bind::<diesel::sql_types::Timestamp, _>(d),
+                SQLValue::Null => query = query.bind::<diesel::sql_types::Text, _>("null"),
             }
         }
 
@@ -1091,9 +1738,8 @@ pub trait UserNamespaceAccessors: SelfAccessors<User> + GroupAccessors {
 
         let groups_id_subquery = self.group_ids_subquery();
 
-        let mut base_query = permissions
-            .into_boxed()
-            .filter(group_id.eq_any(groups_id_subquery));
+        let mut base_query =
+            scope_permissions(permissions.into_boxed()).filter(group_id.eq_any(groups_id_subquery));
 
         for perm in permissions_list {
             base_query = perm.create_boxed_filter(base_query, true);
@@ -1143,7 +1789,7 @@ impl SelfAccessors<User> for UserID {
     }
 }
 
-impl<'a> SelfAccessors<User> for &'a User {
+impl SelfAccessors<User> for &User {
     fn id(&self) -> i32 {
         (*self).id()
     }
@@ -1152,7 +1798,7 @@ impl<'a> SelfAccessors<User> for &'a User {
     }
 }
 
-impl<'a> SelfAccessors<User> for &'a UserID {
+impl SelfAccessors<User> for &UserID {
     fn id(&self) -> i32 {
         (*self).id()
     }
@@ -1284,7 +1930,7 @@ mod test {
         assert_contains!(&classlist, &class);
 
         class
-            .revoke_one(&pool, test_group_2.id, Permissions::ReadClass)
+            .revoke_one(&pool, test_group_2.id, Permissions::ReadClass, None)
             .await
             .unwrap();
 
@@ -1300,7 +1946,7 @@ mod test {
             .unwrap();
         assert_contains!(&nslist, &ns);
 
-        ns.revoke_all(&pool, test_group_2.id).await.unwrap();
+        ns.revoke_all(&pool, test_group_2.id, None).await.unwrap();
 
         let nslist = test_user_2
             .search_namespaces(&pool, vec![read_namespace_param.clone()])
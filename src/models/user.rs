@@ -1,7 +1,9 @@
+use crate::config::get_config;
+use crate::db::scope::namespace_scope;
 use crate::models::group::Group;
-use crate::models::token::{Token, UserToken};
+use crate::models::token::{NewTokenAudit, NewTokenRequest, Token, UserToken};
 use crate::models::user_group::UserGroup;
-use crate::schema::users;
+use crate::schema::{password_audit, users};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -33,6 +35,90 @@ impl User {
             .map(|_| generated_token)?)
     }
 
+    /// Issue a token for this user on behalf of another user, typically an admin.
+    ///
+    /// The issuance is recorded in the token audit trail in the same transaction. The returned
+    /// token is the only time the caller sees the secret.
+    pub async fn issue_token(
+        &self,
+        pool: &DbPool,
+        issued_by: i32,
+        request: &NewTokenRequest,
+    ) -> Result<UserToken, ApiError> {
+        use crate::schema::token_audit::dsl::token_audit;
+        use crate::schema::tokens::dsl::*;
+
+        request.validate()?;
+        let generated_token = crate::utilities::auth::generate_token();
+
+        let mut conn = pool.get()?;
+        conn.transaction::<_, ApiError, _>(|conn| {
+            let issued_token = diesel::insert_into(tokens)
+                .values((
+                    user_id.eq(self.id),
+                    token.eq(generated_token.get_token()),
+                    name.eq(&request.name),
+                    ttl_hours.eq(request.ttl_hours),
+                    namespace_id.eq(request.namespace_id),
+                    read_only.eq(request.read_only),
+                ))
+                .get_result::<UserToken>(conn)?;
+
+            diesel::insert_into(token_audit)
+                .values(NewTokenAudit {
+                    user_id: self.id,
+                    issued_by,
+                    name: request.name.clone(),
+                    ttl_hours: request.ttl_hours,
+                })
+                .execute(conn)?;
+
+            Ok(issued_token)
+        })
+    }
+
+    /// Reset the password of this user on behalf of another user, typically an admin.
+    ///
+    /// The new password is checked against the password policy and hashed as any other
+    /// password. If asked to, all the tokens of the user are revoked as well. The reset is
+    /// recorded in the password audit trail in the same transaction.
+    pub async fn reset_password(
+        &self,
+        pool: &DbPool,
+        reset_by: i32,
+        reset: &PasswordReset,
+    ) -> Result<(), ApiError> {
+        use crate::schema::password_audit::dsl::password_audit;
+
+        crate::utilities::auth::validate_password_strength(&reset.password, &self.username)?;
+        let hashed_password = crate::utilities::auth::hash_password(&reset.password)
+            .map_err(|e| ApiError::HashError(format!("Failed to hash password: {}", e)))?;
+
+        let mut conn = pool.get()?;
+        conn.transaction::<_, ApiError, _>(|conn| {
+            diesel::update(crate::schema::users::table.find(self.id))
+                .set(crate::schema::users::password.eq(hashed_password))
+                .execute(conn)?;
+
+            if reset.revoke_tokens {
+                diesel::delete(
+                    crate::schema::tokens::table.filter(crate::schema::tokens::user_id.eq(self.id)),
+                )
+                .execute(conn)?;
+            }
+
+            diesel::insert_into(password_audit)
+                .values(NewPasswordAudit {
+                    user_id: self.id,
+                    reset_by,
+                    tokens_revoked: reset.revoke_tokens,
+                })
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
     pub async fn token_is_mine(
         &self,
         token_param: Token,
@@ -67,6 +153,26 @@ impl User {
         Ok(diesel::delete(users.filter(id.eq(self.id))).execute(&mut pool.get()?)?)
     }
 
+    /// Count the data that deleting this user would remove, without deleting anything.
+    pub async fn deletion_impact(&self, pool: &DbPool) -> Result<UserDeletionImpact, ApiError> {
+        use crate::schema::{tokens, user_groups};
+
+        let mut conn = pool.get()?;
+        Ok(UserDeletionImpact {
+            tokens: tokens::table
+                .filter(tokens::user_id.eq(self.id))
+                .count()
+                .get_result(&mut conn)?,
+            group_memberships: user_groups::table
+                .filter(user_groups::user_id.eq(self.id))
+                .count()
+                .get_result(&mut conn)?,
+        })
+    }
+
+    /// Whether the user is a member of the active group with the given name.
+    ///
+    /// Deactivated groups don't count, so deactivating the admin group revokes admin access.
     pub async fn is_in_group_by_name(&self, groupname_queried: &str, pool: &DbPool) -> bool {
         use crate::schema::groups::dsl::*;
         use crate::schema::user_groups::dsl::*;
@@ -76,6 +182,7 @@ impl User {
                 .filter(user_id.eq(self.id))
                 .inner_join(groups.on(id.eq(group_id)))
                 .filter(groupname.eq(groupname_queried)) // Clarify the field and variable
+                .filter(active.eq(true)) // Deactivated groups grant nothing, not even admin
                 .first::<(UserGroup, Group)>(&mut conn) // Change the expected type
                 .is_ok(),
             Err(e) => {
@@ -110,6 +217,51 @@ impl User {
     pub async fn is_admin(&self, pool: &DbPool) -> bool {
         self.is_in_group_by_name("admin", pool).await
     }
+
+    /// Whether the user may act as an admin in the current request.
+    ///
+    /// Admin operations are not confined to a namespace, so this requires that the user is an
+    /// admin and that the token of the request is not scoped to a namespace. Every admin-only
+    /// check for a request goes through this, rather than through [`User::is_admin`].
+    pub async fn has_admin_access(&self, pool: &DbPool) -> bool {
+        namespace_scope().is_none() && self.is_admin(pool).await
+    }
+}
+
+/// Query parameters for deleting users and groups.
+///
+/// With `dry_run` set, nothing is deleted and the data that would be removed is reported instead.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DeleteOptions {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// The data that deleting a user would remove, see [`User::deletion_impact`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UserDeletionImpact {
+    pub tokens: i64,
+    pub group_memberships: i64,
+}
+
+/// A password reset by an admin, see [`User::reset_password`].
+///
+/// The password is expected to be plaintext.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct PasswordReset {
+    pub password: String,
+    /// Revoke all the tokens of the user, logging them out everywhere.
+    #[serde(default)]
+    pub revoke_tokens: bool,
+}
+
+/// An entry in the password audit trail, recording a password reset by an admin.
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = password_audit)]
+pub struct NewPasswordAudit {
+    pub user_id: i32,
+    pub reset_by: i32,
+    pub tokens_revoked: bool,
 }
 
 /// Struct to update a user.
@@ -147,12 +299,74 @@ impl UpdateUser {
 
     pub async fn save(self, user_id: i32, pool: &DbPool) -> Result<User, ApiError> {
         use crate::schema::users::dsl::*;
-        Ok(diesel::update(users.filter(id.eq(user_id)))
-            .set(self.hash_password()?)
-            .get_result::<User>(&mut pool.get()?)?)
+
+        let require_unique = get_config().await.require_unique_email;
+        let update = self.hash_password()?;
+
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            ensure_email_available(conn, require_unique, update.email.as_deref(), Some(user_id))?;
+
+            Ok(diesel::update(users.filter(id.eq(user_id)))
+                .set(&update)
+                .get_result::<User>(conn)?)
+        })
     }
 }
 
+/// Ensure that an email address is not used by any other user.
+///
+/// Emails are only required to be unique if `HUBUUM_REQUIRE_UNIQUE_EMAIL` is set, in which case
+/// they are compared case-insensitively. Users without an email never collide. We take a
+/// transaction level advisory lock on the email before checking, so this must be called from
+/// within the transaction that creates or updates the user.
+///
+/// ### Returns
+///
+/// * Nothing if the email is available, or an ApiError::Conflict if it is in use.
+pub(crate) fn ensure_email_available(
+    conn: &mut PgConnection,
+    require_unique: bool,
+    address: Option<&str>,
+    user_id: Option<i32>,
+) -> Result<(), ApiError> {
+    use crate::schema::users::dsl::{id, users};
+    use diesel::dsl::{exists, sql};
+    use diesel::sql_types::{Bool, Text};
+
+    let address = match address {
+        Some(address) if require_unique => address,
+        _ => return Ok(()),
+    };
+
+    diesel::select(
+        sql::<Bool>("pg_advisory_xact_lock(hashtext(lower(")
+            .bind::<Text, _>(address)
+            .sql("))) IS NOT NULL"),
+    )
+    .execute(conn)?;
+
+    let in_use = diesel::select(exists(
+        users
+            .filter(
+                sql::<Bool>("lower(email) = lower(")
+                    .bind::<Text, _>(address)
+                    .sql(")"),
+            )
+            .filter(id.ne(user_id.unwrap_or(0))),
+    ))
+    .get_result::<bool>(conn)?;
+
+    if in_use {
+        return Err(ApiError::Conflict(format!(
+            "Email '{}' is already in use",
+            address
+        )));
+    }
+
+    Ok(())
+}
+
 /// Struct to create a new user.
 ///
 /// The password is expected to be hashed
@@ -177,9 +391,18 @@ impl NewUser {
 
     pub async fn save(self, pool: &DbPool) -> Result<User, ApiError> {
         use crate::schema::users::dsl::*;
-        Ok(diesel::insert_into(users)
-            .values(&self.hash_password()?)
-            .get_result::<User>(&mut pool.get()?)?)
+
+        let require_unique = get_config().await.require_unique_email;
+        let user = self.hash_password()?;
+
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            ensure_email_available(conn, require_unique, user.email.as_deref(), None)?;
+
+            Ok(diesel::insert_into(users)
+                .values(&user)
+                .get_result::<User>(conn)?)
+        })
     }
 
     pub fn hash_password(mut self) -> Result<Self, ApiError> {
@@ -298,3 +521,69 @@ impl LoginUser {
 pub fn auth_failure() -> ApiError {
     ApiError::Unauthorized("Authentication failure".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::get_pool_and_config;
+    use crate::utilities::auth::generate_random_password;
+
+    #[actix_rt::test]
+    async fn test_unique_email() {
+        let (pool, _) = get_pool_and_config().await;
+        let suffix = generate_random_password(16).to_lowercase();
+        let address = format!("unique-{}@example.com", suffix);
+
+        let user = NewUser::new(&format!("email_{}", suffix), "password", Some(&address))
+            .await
+            .save(&pool)
+            .await
+            .unwrap();
+        let no_email = NewUser::new(&format!("no_email_{}", suffix), "password", None)
+            .await
+            .save(&pool)
+            .await
+            .unwrap();
+
+        let mut conn = pool.get().unwrap();
+        let shouted = address.to_uppercase();
+
+        // Without the requirement, anything goes
+        assert!(ensure_email_available(&mut conn, false, Some(&shouted), None).is_ok());
+
+        // With it, emails may not be reused by other users, regardless of case...
+        match ensure_email_available(&mut conn, true, Some(&shouted), None) {
+            Err(ApiError::Conflict(_)) => (),
+            other => panic!("Expected conflict, got {:?}", other),
+        }
+        match ensure_email_available(&mut conn, true, Some(&address), Some(no_email.id)) {
+            Err(ApiError::Conflict(_)) => (),
+            other => panic!("Expected conflict, got {:?}", other),
+        }
+
+        // ...but the user may keep its email, and missing emails never collide
+        assert!(ensure_email_available(&mut conn, true, Some(&shouted), Some(user.id)).is_ok());
+        assert!(ensure_email_available(&mut conn, true, None, None).is_ok());
+        assert!(ensure_email_available(&mut conn, true, None, Some(no_email.id)).is_ok());
+
+        // The requirement is off by default, so saving a duplicate works
+        let duplicate = NewUser::new(&format!("duplicate_{}", suffix), "password", Some(&shouted))
+            .await
+            .save(&pool)
+            .await
+            .unwrap();
+        let updated = UpdateUser {
+            username: None,
+            password: None,
+            email: Some(address.clone()),
+        }
+        .save(no_email.id, &pool)
+        .await
+        .unwrap();
+        assert_eq!(updated.email, Some(address));
+
+        for user in [user, no_email, duplicate] {
+            UserID(user.id).delete(&pool).await.unwrap();
+        }
+    }
+}
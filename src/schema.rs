@@ -1,5 +1,14 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    class_indexes (id) {
+        id -> Int4,
+        hubuum_class_id -> Int4,
+        json_path -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     groups (id) {
         id -> Int4,
@@ -7,6 +16,7 @@ diesel::table! {
         description -> Varchar,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        active -> Bool,
     }
 }
 
@@ -52,6 +62,7 @@ diesel::table! {
         description -> Varchar,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        external_id -> Nullable<Varchar>,
     }
 }
 
@@ -82,6 +93,32 @@ diesel::table! {
         description -> Varchar,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        write_rate_limit -> Nullable<Int4>,
+        object_quota -> Nullable<Int4>,
+        default_class_permissions -> Nullable<Array<Text>>,
+        attachment_size_limit -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    password_audit (id) {
+        id -> Int4,
+        user_id -> Int4,
+        reset_by -> Int4,
+        tokens_revoked -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    permission_audit (id) {
+        id -> Int4,
+        namespace_id -> Int4,
+        group_id -> Int4,
+        action -> Varchar,
+        permissions -> Nullable<Text>,
+        reason -> Nullable<Text>,
+        created_at -> Timestamp,
     }
 }
 
@@ -115,11 +152,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    token_audit (id) {
+        id -> Int4,
+        user_id -> Int4,
+        issued_by -> Int4,
+        name -> Nullable<Varchar>,
+        ttl_hours -> Nullable<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     tokens (token) {
         token -> Varchar,
         user_id -> Int4,
         issued -> Timestamp,
+        name -> Nullable<Varchar>,
+        ttl_hours -> Nullable<Int4>,
+        last_used -> Nullable<Timestamp>,
+        namespace_id -> Nullable<Int4>,
+        read_only -> Bool,
     }
 }
 
@@ -186,20 +239,36 @@ diesel::table! {
         descendant_created_at -> Timestamp,
         ancestor_updated_at -> Timestamp,
         descendant_updated_at -> Timestamp,
+        ancestor_external_id -> Nullable<Text>,
+        descendant_external_id -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    validation_audit (id) {
+        id -> Int4,
+        object_id -> Int4,
+        class_id -> Int4,
+        user_id -> Int4,
+        action -> Varchar,
+        created_at -> Timestamp,
     }
 }
 
+diesel::joinable!(class_indexes -> hubuumclass (hubuum_class_id));
 diesel::joinable!(hubuumclass -> namespaces (namespace_id));
 diesel::joinable!(hubuumobject -> hubuumclass (hubuum_class_id));
 diesel::joinable!(hubuumobject -> namespaces (namespace_id));
 diesel::joinable!(hubuumobject_relation -> hubuumclass_relation (class_relation_id));
 diesel::joinable!(permissions -> groups (group_id));
 diesel::joinable!(permissions -> namespaces (namespace_id));
+diesel::joinable!(tokens -> namespaces (namespace_id));
 diesel::joinable!(tokens -> users (user_id));
 diesel::joinable!(user_groups -> groups (group_id));
 diesel::joinable!(user_groups -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    class_indexes,
     groups,
     hubuumclass,
     hubuumclass_closure,
@@ -208,10 +277,14 @@ diesel::allow_tables_to_appear_in_same_query!(
     hubuumobject_closure,
     hubuumobject_relation,
     namespaces,
+    password_audit,
+    permission_audit,
     permissions,
+    token_audit,
     tokens,
     user_groups,
     users,
+    validation_audit,
     class_closure_view,
     object_closure_view,
 );
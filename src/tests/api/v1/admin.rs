@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+
+    use crate::models::stats::SystemStats;
+    use crate::models::token::{NewTokenRequest, TokenInfo};
+    use crate::tests::api_operations::get_request;
+    use crate::tests::asserts::assert_response_status;
+    use crate::tests::{create_test_user, setup_pool_and_tokens};
+
+    const TOKENS_ENDPOINT: &str = "/api/v1/admin/tokens";
+    const STATS_ENDPOINT: &str = "/api/v1/admin/stats";
+
+    #[actix_web::test]
+    async fn test_admin_list_tokens() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+        let user = create_test_user(&pool).await;
+
+        let used = user.create_token(&pool).await.unwrap().get_token();
+        let named = user
+            .issue_token(
+                &pool,
+                user.id,
+                &NewTokenRequest {
+                    name: Some("nightly-sync".to_string()),
+                    ttl_hours: Some(1),
+                    namespace_id: None,
+                    read_only: false,
+                },
+            )
+            .await
+            .unwrap();
+
+        let resp = get_request(&pool, &normal_token, TOKENS_ENDPOINT).await;
+        let _ = assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        // Using a token records when it was last used.
+        let resp = get_request(&pool, &used, "/api/v1/iam/users/").await;
+        let _ = assert_response_status(resp, StatusCode::OK).await;
+
+        let url = format!("{}?user={}", TOKENS_ENDPOINT, user.id);
+        let resp = get_request(&pool, &admin_token, &url).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let tokens: Vec<TokenInfo> = test::read_body_json(resp).await;
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens.iter().all(|t| t.user_id == user.id));
+
+        // The secret is never exposed, and the newest token comes first.
+        let body = serde_json::to_string(&tokens).unwrap();
+        assert!(!body.contains(&used));
+        assert!(!body.contains(&named.token));
+        assert_eq!(tokens[0].name, Some("nightly-sync".to_string()));
+        assert_eq!(tokens[0].expires, named.expires());
+        assert!(tokens[0].last_used.is_none());
+        assert!(tokens[1].last_used.is_some());
+
+        // Using it again right away doesn't update it, to keep authentication free of writes.
+        let resp = get_request(&pool, &used, "/api/v1/iam/users/").await;
+        let _ = assert_response_status(resp, StatusCode::OK).await;
+        let resp = get_request(&pool, &admin_token, &url).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let tokens_again: Vec<TokenInfo> = test::read_body_json(resp).await;
+        assert_eq!(tokens_again[1].last_used, tokens[1].last_used);
+
+        let url = format!(
+            "{}?user={}&last_used__gte=2000-01-01",
+            TOKENS_ENDPOINT, user.id
+        );
+        let resp = get_request(&pool, &admin_token, &url).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let tokens: Vec<TokenInfo> = test::read_body_json(resp).await;
+        assert_eq!(tokens.len(), 1);
+        assert!(tokens[0].name.is_none());
+
+        let url = format!("{}?user={}&limit=1&offset=1", TOKENS_ENDPOINT, user.id);
+        let resp = get_request(&pool, &admin_token, &url).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let tokens: Vec<TokenInfo> = test::read_body_json(resp).await;
+        assert_eq!(tokens.len(), 1);
+        assert!(tokens[0].name.is_none());
+
+        for query in ["limit=-1", "limit__gt=1", "limit=1001", "namespaces=1"] {
+            let url = format!("{}?{}", TOKENS_ENDPOINT, query);
+            let resp = get_request(&pool, &admin_token, &url).await;
+            let _ = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        }
+
+        user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_admin_stats() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let resp = get_request(&pool, &normal_token, STATS_ENDPOINT).await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = get_request(&pool, &admin_token, STATS_ENDPOINT).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let stats: SystemStats = test::read_body_json(resp).await;
+        assert!(!stats.approximate);
+        assert!(stats.users >= 2);
+        assert!(stats.groups >= 1);
+        assert!(stats.active_tokens >= 2);
+
+        // Estimates are never negative, even for tables Postgres hasn't analyzed yet.
+        let stats = SystemStats::get(&pool, true).await.unwrap();
+        assert!(stats.approximate);
+        assert!(stats.objects >= 0 && stats.object_relations >= 0);
+    }
+}
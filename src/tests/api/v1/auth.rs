@@ -430,4 +430,86 @@ mod tests {
         new_user.delete(&pool).await.unwrap();
         admin_user.delete(&pool).await.unwrap();
     }
+
+    #[actix_web::test]
+    async fn test_user_concurrency_limit() {
+        use crate::extractors::UserAccess;
+        use crate::middlewares::access_log::AuthenticatedUserId;
+        use crate::middlewares::concurrency::{ConcurrencyLimitMiddleware, ConcurrencyLimiter};
+        use actix_rt::time::sleep;
+        use actix_web::{HttpMessage, HttpResponse};
+        use std::time::Duration;
+
+        // A request that stays in flight for a while, so other requests overlap with it.
+        async fn slow(_: UserAccess) -> HttpResponse {
+            sleep(Duration::from_millis(300)).await;
+            HttpResponse::Ok().finish()
+        }
+
+        let config = get_config().await;
+        let pool = init_pool(&config.database_url, config.db_pool_size);
+        let user = create_test_user(&pool).await;
+        let other_user = create_test_user(&pool).await;
+        let limiter = Data::new(ConcurrencyLimiter::new(1));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(pool.clone()))
+                .app_data(limiter.clone())
+                .service(
+                    web::scope("/slow")
+                        .wrap(ConcurrencyLimitMiddleware)
+                        .route("", web::get().to(slow)),
+                )
+                .configure(api::config),
+        )
+        .await;
+
+        let request = |uri: &str, token: &str| {
+            test::TestRequest::get()
+                .uri(uri)
+                .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+                .to_request()
+        };
+        let token = user.create_token(&pool).await.unwrap().get_token();
+        let other_token = other_user.create_token(&pool).await.unwrap().get_token();
+
+        let (first, (second, other)) =
+            futures::join!(test::call_service(&app, request("/slow", &token)), async {
+                // Wait until the first request holds the only slot of the user.
+                for attempt in 0.. {
+                    if matches!(limiter.acquire_for_token(&token), Some(Err(_))) {
+                        break;
+                    }
+                    assert!(attempt < 50, "The first request never took a slot");
+                    sleep(Duration::from_millis(5)).await;
+                }
+                futures::join!(
+                    test::call_service(&app, request("/api/v1/me/groups", &token)),
+                    test::call_service(&app, request("/slow", &other_token)),
+                )
+            });
+
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(second.headers().get(header::RETRY_AFTER).unwrap(), "1");
+        // The request was rejected before the user was looked up in the database.
+        assert!(second
+            .request()
+            .extensions()
+            .get::<AuthenticatedUserId>()
+            .is_none());
+        assert_eq!(other.status(), StatusCode::OK);
+
+        // Finished requests free their slot, and the user is forgotten until the next request.
+        drop((first, second, other));
+        assert!(limiter.acquire_for_token(&token).is_none());
+        for _ in 0..3 {
+            let resp = test::call_service(&app, request("/api/v1/me/groups", &token)).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+
+        user.delete(&pool).await.unwrap();
+        other_user.delete(&pool).await.unwrap();
+    }
 }
@@ -1,10 +1,16 @@
 #[cfg(test)]
 pub mod tests {
-    use crate::models::{HubuumClass, HubuumClassExpanded, NamespaceID, NewHubuumClass};
+    use crate::models::{
+        HubuumClass, HubuumClassExpanded, NamespaceID, NamespaceObjectCount, NewHubuumClass,
+        NewHubuumObject,
+    };
     use crate::traits::{CanDelete, CanSave};
-    use actix_web::{http::StatusCode, test};
+    use actix_web::{http, http::StatusCode, test};
 
-    use crate::tests::api_operations::{delete_request, get_request, patch_request, post_request};
+    use crate::tests::api_operations::{
+        delete_request, get_request, get_request_with_headers, head_request, patch_request,
+        post_request,
+    };
     use crate::tests::asserts::assert_response_status;
     use crate::tests::constants::{get_schema, SchemaType};
     use crate::tests::{create_namespace, setup_pool_and_tokens};
@@ -79,6 +85,27 @@ pub mod tests {
         classes
     }
 
+    #[actix_web::test]
+    async fn test_api_classes_get_degenerate_value_lists() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        for query_string in [
+            "id__between=5",
+            "id=1,,2",
+            "id=,,",
+            "namespaces=,",
+            "created_at__between=2021-01-01",
+        ] {
+            let resp = get_request(
+                &pool,
+                &admin_token,
+                &format!("{}?{}", CLASSES_ENDPOINT, query_string),
+            )
+            .await;
+            assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        }
+    }
+
     #[actix_web::test]
     async fn test_api_classes_get() {
         let created_classes = create_test_classes("get").await;
@@ -122,6 +149,15 @@ pub mod tests {
         cleanup(&created_classes).await;
     }
 
+    #[actix_web::test]
+    async fn test_api_classes_get_filtered_name_like_alternatives() {
+        let created_classes = create_test_classes("get_filtered_name_like_alternatives").await;
+        let query_string = "name__like=get_filtered_name_like_alternatives_api_class_1&name__like=%25alternatives_api_class_5%25";
+        let classes = api_get_classes_with_query_string(query_string).await;
+        assert_contains_same_ids!(&classes, &[&created_classes[0], &created_classes[4]]);
+        cleanup(&created_classes).await;
+    }
+
     #[actix_web::test]
     async fn test_api_classes_get_filtered_description_contains() {
         let created_classes = create_test_classes("get_filtered_description_contains").await;
@@ -202,6 +238,60 @@ pub mod tests {
         cleanup(&created_classes).await;
     }
 
+    #[actix_web::test]
+    async fn test_api_classes_get_filtered_changed_since() {
+        use diesel::prelude::*;
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let classes = create_test_classes("changed_since").await;
+
+        // Updates always set updated_at to now, so the second class ends up created after its
+        // last update, as could happen with imported data.
+        {
+            let mut conn = pool.get().unwrap();
+            for (class, created) in classes.iter().zip(["2001-01-01", "2030-01-01"]) {
+                diesel::sql_query(format!(
+                    "UPDATE hubuumclass SET created_at = '{}' WHERE id = {}",
+                    created, class.id
+                ))
+                .execute(&mut conn)
+                .unwrap();
+            }
+        }
+
+        let namespace = classes[0].namespace_id;
+        let cases = [
+            ("changed_since=2020-01-01T00:00:00Z", vec![0, 1, 2, 3, 4, 5]),
+            ("changed_since=2029-01-01", vec![1]),
+            ("updated_at__gte=2029-01-01", vec![]),
+        ];
+
+        for (query, expected) in cases {
+            let found =
+                api_get_classes_with_query_string(&format!("namespaces={}&{}", namespace, query))
+                    .await;
+            let mut ids = found.iter().map(|c| c.id).collect::<Vec<_>>();
+            ids.sort();
+            let expected = expected.iter().map(|i| classes[*i].id).collect::<Vec<_>>();
+            assert_eq!(ids, expected, "Failed for query {}", query);
+        }
+
+        for query in [
+            "changed_since__gt=2020-01-01",
+            "changed_since=2020-01-01,2021-01-01",
+        ] {
+            let resp = get_request(
+                &pool,
+                &admin_token,
+                &format!("{}?{}", CLASSES_ENDPOINT, query),
+            )
+            .await;
+            assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        }
+
+        cleanup(&classes).await;
+    }
+
     #[actix_web::test]
     async fn test_api_classes_get_by_id() {
         let created_classes = create_test_classes("api_classes_get_by_id").await;
@@ -234,6 +324,198 @@ pub mod tests {
         }
     }
 
+    #[actix_web::test]
+    async fn test_api_classes_get_schema() {
+        use crate::models::UpdateHubuumClass;
+
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let ns = create_namespace(&pool, "api_classes_get_schema")
+            .await
+            .unwrap();
+
+        let new_class = NewHubuumClass {
+            name: "api_classes_get_schema".to_string(),
+            description: "api_classes_get_schema".to_string(),
+            namespace_id: ns.id,
+            json_schema: Some(get_schema(SchemaType::Blog).clone()),
+            validate_schema: Some(false),
+        };
+        let class = new_class.save(&pool).await.unwrap();
+        let endpoint = format!("{}/{}/schema", CLASSES_ENDPOINT, class.id);
+
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let headers = resp.headers().clone();
+        assert_eq!(
+            headers.get(http::header::CONTENT_TYPE).unwrap(),
+            "application/schema+json"
+        );
+        let etag = headers.get(http::header::ETAG).unwrap().to_str().unwrap();
+        let schema: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(&schema, get_schema(SchemaType::Blog));
+
+        // A matching ETag means the client already has the schema.
+        let resp = get_request_with_headers(
+            &pool,
+            &admin_token,
+            &endpoint,
+            vec![(http::header::IF_NONE_MATCH, etag)],
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::NOT_MODIFIED).await;
+        assert_eq!(resp.headers().get(http::header::ETAG).unwrap(), etag);
+
+        // Updating the class changes the ETag.
+        let update = UpdateHubuumClass {
+            name: None,
+            namespace_id: None,
+            json_schema: None,
+            validate_schema: None,
+            description: Some("api_classes_get_schema_updated".to_string()),
+        };
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &format!("{}/{}", CLASSES_ENDPOINT, class.id),
+            &update,
+        )
+        .await;
+        assert_response_status(resp, StatusCode::OK).await;
+
+        let resp = get_request_with_headers(
+            &pool,
+            &admin_token,
+            &endpoint,
+            vec![(http::header::IF_NONE_MATCH, etag)],
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        assert_ne!(resp.headers().get(http::header::ETAG).unwrap(), etag);
+
+        // The normal user has no permissions on the namespace.
+        let resp = get_request(&pool, &normal_token, &endpoint).await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        // A class without a schema has nothing to return.
+        let new_class = NewHubuumClass {
+            name: "api_classes_get_schema_none".to_string(),
+            description: "api_classes_get_schema_none".to_string(),
+            namespace_id: ns.id,
+            json_schema: None,
+            validate_schema: Some(false),
+        };
+        let class = new_class.save(&pool).await.unwrap();
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!("{}/{}/schema", CLASSES_ENDPOINT, class.id),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        ns.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_api_classes_head() {
+        let created_classes = create_test_classes("api_classes_head").await;
+        let class = &created_classes[0];
+
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let object = NewHubuumObject {
+            name: "api_classes_head_object".to_string(),
+            namespace_id: class.namespace_id,
+            hubuum_class_id: class.id,
+            data: serde_json::json!({}),
+            description: "api_classes_head object".to_string(),
+            external_id: None,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let class_endpoint = format!("{}/{}", CLASSES_ENDPOINT, class.id);
+        let object_endpoint = format!("{}/{}/{}", CLASSES_ENDPOINT, class.id, object.id);
+        let missing_endpoint = format!("{}/{}/{}", CLASSES_ENDPOINT, class.id, 999999);
+
+        // HEAD mirrors GET for status and headers, including for tokens without access
+        for endpoint in [&class_endpoint, &object_endpoint, &missing_endpoint] {
+            for token in [&admin_token, &normal_token] {
+                let get = get_request(&pool, token, endpoint).await;
+                let head = head_request(&pool, token, endpoint).await;
+                assert_eq!(get.status(), head.status(), "HEAD {}", endpoint);
+                assert_eq!(
+                    get.headers().get(http::header::CONTENT_TYPE),
+                    head.headers().get(http::header::CONTENT_TYPE)
+                );
+            }
+        }
+
+        let resp = head_request(&pool, &admin_token, &class_endpoint).await;
+        assert_response_status(resp, StatusCode::OK).await;
+        let resp = head_request(&pool, &normal_token, &object_endpoint).await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+        let resp = head_request(&pool, &admin_token, &missing_endpoint).await;
+        assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        cleanup(&created_classes).await;
+    }
+
+    #[actix_web::test]
+    async fn test_api_classes_get_namespaces() {
+        let created_classes = create_test_classes("api_classes_get_namespaces").await;
+        let class = &created_classes[0];
+
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let other_ns = create_namespace(&pool, "api_classes_get_namespaces_other")
+            .await
+            .unwrap();
+
+        for (i, nid) in [class.namespace_id, class.namespace_id, other_ns.id]
+            .iter()
+            .enumerate()
+        {
+            NewHubuumObject {
+                name: format!("api_classes_get_namespaces_object_{}", i),
+                namespace_id: *nid,
+                hubuum_class_id: class.id,
+                data: serde_json::json!({}),
+                description: "api_classes_get_namespaces object".to_string(),
+                external_id: None,
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+        }
+
+        let endpoint = format!("{}/{}/namespaces", CLASSES_ENDPOINT, class.id);
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let namespaces: Vec<NamespaceObjectCount> = test::read_body_json(resp).await;
+
+        assert_eq!(namespaces.len(), 2);
+        assert_eq!(namespaces[0].namespace.id, class.namespace_id);
+        assert_eq!(namespaces[0].object_count, 2);
+        assert_eq!(namespaces[1].namespace.id, other_ns.id);
+        assert_eq!(namespaces[1].object_count, 1);
+
+        // A class without objects has no namespaces to report
+        let endpoint = format!("{}/{}/namespaces", CLASSES_ENDPOINT, created_classes[1].id);
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let namespaces: Vec<NamespaceObjectCount> = test::read_body_json(resp).await;
+        assert!(namespaces.is_empty());
+
+        let resp = get_request(&pool, &normal_token, &endpoint).await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        other_ns.delete(&pool).await.unwrap();
+        cleanup(&created_classes).await;
+    }
+
     #[actix_web::test]
     async fn test_api_classes_create() {
         let (pool, admin_token, _) = setup_pool_and_tokens().await;
@@ -271,6 +553,154 @@ pub mod tests {
         ns.delete(&pool).await.unwrap();
     }
 
+    #[actix_web::test]
+    async fn test_api_classes_create_if_not_exists() {
+        use crate::models::Permissions;
+        use crate::tests::{create_test_group, create_test_user};
+        use crate::traits::PermissionController;
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        let ns = create_namespace(&pool, "api_create_if_not_exists")
+            .await
+            .unwrap();
+        let other_ns = create_namespace(&pool, "api_create_if_not_exists_other")
+            .await
+            .unwrap();
+
+        let new_class = NewHubuumClass {
+            name: "api_create_if_not_exists".to_string(),
+            description: "api_create_if_not_exists".to_string(),
+            namespace_id: ns.id,
+            json_schema: None,
+            validate_schema: Some(false),
+        };
+        let endpoint = format!("{}?if_not_exists=true", CLASSES_ENDPOINT);
+
+        let resp = post_request(&pool, &admin_token, &endpoint, &new_class).await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let created: HubuumClassExpanded = test::read_body_json(resp).await;
+
+        let resp = post_request(&pool, &admin_token, &endpoint, &new_class).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let existing: HubuumClassExpanded = test::read_body_json(resp).await;
+        assert_eq!(created, existing);
+
+        // Without the flag the name is still a conflict.
+        let resp = post_request(&pool, &admin_token, CLASSES_ENDPOINT, &new_class).await;
+        assert_response_status(resp, StatusCode::CONFLICT).await;
+
+        // As is the same name in a different namespace.
+        let new_class = NewHubuumClass {
+            namespace_id: other_ns.id,
+            ..new_class
+        };
+        let resp = post_request(&pool, &admin_token, &endpoint, &new_class).await;
+        let resp = assert_response_status(resp, StatusCode::CONFLICT).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            body["message"],
+            format!(
+                "Class 'api_create_if_not_exists' already exists in namespace {}",
+                ns.id
+            )
+        );
+
+        // Those who may not read the existing class are not told where it is.
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        other_ns
+            .grant_one(&pool, group.id, Permissions::CreateClass)
+            .await
+            .unwrap();
+        let token = user.create_token(&pool).await.unwrap().get_token();
+        let resp = post_request(&pool, &token, &endpoint, &new_class).await;
+        let resp = assert_response_status(resp, StatusCode::CONFLICT).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            body["message"],
+            "Class 'api_create_if_not_exists' already exists"
+        );
+
+        // Finding the existing class writes nothing, and is not counted against the write rate
+        // limit of the namespace.
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &format!("/api/v1/namespaces/{}", ns.id),
+            &serde_json::json!({"write_rate_limit": 1}),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::ACCEPTED).await;
+        let new_class = NewHubuumClass {
+            namespace_id: ns.id,
+            ..new_class
+        };
+        for _ in 0..3 {
+            let resp = post_request(&pool, &admin_token, &endpoint, &new_class).await;
+            assert_response_status(resp, StatusCode::OK).await;
+        }
+
+        ns.delete(&pool).await.unwrap();
+        other_ns.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_api_class_names() {
+        use crate::models::Permissions;
+        use crate::tests::{create_test_group, create_test_user};
+        use crate::traits::PermissionController;
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        let mut namespaces = vec![];
+        for (suffix, class_name) in [("1", "b"), ("2", "a"), ("hidden", "hidden")] {
+            let ns = create_namespace(&pool, &format!("api_class_names_{}", suffix))
+                .await
+                .unwrap();
+            NewHubuumClass {
+                name: format!("api_class_names_{}", class_name),
+                description: "api_class_names".to_string(),
+                namespace_id: ns.id,
+                json_schema: None,
+                validate_schema: Some(false),
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+            namespaces.push(ns);
+        }
+
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        for ns in &namespaces[..2] {
+            for permission in [Permissions::ReadCollection, Permissions::ReadClass] {
+                ns.grant_one(&pool, group.id, permission).await.unwrap();
+            }
+        }
+        let token = user.create_token(&pool).await.unwrap().get_token();
+
+        // Only the names in readable namespaces are listed, sorted by name.
+        let endpoint = format!("{}/names", CLASSES_ENDPOINT);
+        let resp = get_request(&pool, &token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let names: Vec<String> = test::read_body_json(resp).await;
+        assert_eq!(names, vec!["api_class_names_a", "api_class_names_b"]);
+
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let names: Vec<String> = test::read_body_json(resp).await;
+        assert!(names.iter().any(|n| n == "api_class_names_hidden"));
+
+        for ns in namespaces {
+            ns.delete(&pool).await.unwrap();
+        }
+        group.delete(&pool).await.unwrap();
+        user.delete(&pool).await.unwrap();
+    }
+
     #[actix_web::test]
     async fn test_api_classes_patch() {
         use crate::models::UpdateHubuumClass;
@@ -359,4 +789,243 @@ pub mod tests {
             assert_response_status(resp, StatusCode::NOT_FOUND).await;
         }
     }
+
+    fn index_exists(pool: &crate::db::DbPool, index_name: &str) -> bool {
+        use diesel::dsl::sql;
+        use diesel::prelude::*;
+        use diesel::sql_types::{Bool, Text};
+
+        let mut conn = pool.get().unwrap();
+        diesel::select(
+            sql::<Bool>("EXISTS (SELECT 1 FROM pg_indexes WHERE indexname = ")
+                .bind::<Text, _>(index_name)
+                .sql(")"),
+        )
+        .get_result::<bool>(&mut conn)
+        .unwrap()
+    }
+
+    fn json_query_plan(pool: &crate::db::DbPool, class_id: i32) -> String {
+        use diesel::prelude::*;
+
+        #[derive(QueryableByName)]
+        struct PlanLine {
+            #[diesel(sql_type = diesel::sql_types::Text, column_name = "QUERY PLAN")]
+            line: String,
+        }
+
+        let mut conn = pool.get().unwrap();
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::sql_query("SET LOCAL enable_seqscan = off").execute(conn)?;
+            let plan = diesel::sql_query(format!(
+                "EXPLAIN SELECT id FROM hubuumobject WHERE data #>> '{{title}}' = 'x' AND hubuum_class_id = {}",
+                class_id
+            ))
+            .load::<PlanLine>(conn)?;
+            Ok(plan.into_iter().map(|p| p.line).collect::<Vec<_>>().join("\n"))
+        })
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_api_class_indexes() {
+        use crate::models::ClassIndex;
+
+        let created_classes = create_test_classes("api_class_indexes").await;
+        let class = &created_classes[0];
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let endpoint = format!("{}/{}/indexes/", CLASSES_ENDPOINT, class.id);
+        let body = serde_json::json!({"json_path": "title"});
+
+        let resp = post_request(&pool, &normal_token, &endpoint, &body).await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = post_request(&pool, &admin_token, &endpoint, &body).await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let index: ClassIndex = test::read_body_json(resp).await;
+        assert_eq!(index.hubuum_class_id, class.id);
+        assert_eq!(index.json_path, "title");
+        assert!(index_exists(&pool, &index.index_name()));
+        assert!(json_query_plan(&pool, class.id).contains(&index.index_name()));
+
+        let resp = post_request(&pool, &admin_token, &endpoint, &body).await;
+        assert_response_status(resp, StatusCode::CONFLICT).await;
+
+        for json_path in ["", "title'; --", "a,,b", ",title"] {
+            let resp = post_request(
+                &pool,
+                &admin_token,
+                &endpoint,
+                serde_json::json!({ "json_path": json_path }),
+            )
+            .await;
+            assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        }
+
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let indexes: Vec<ClassIndex> = test::read_body_json(resp).await;
+        assert_eq!(indexes, vec![index.clone()]);
+
+        let resp = delete_request(&pool, &normal_token, &format!("{}{}", endpoint, index.id)).await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        // Indexes are only reachable through their own class
+        let other_endpoint = format!(
+            "{}/{}/indexes/{}",
+            CLASSES_ENDPOINT, created_classes[1].id, index.id
+        );
+        let resp = delete_request(&pool, &admin_token, &other_endpoint).await;
+        assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        let resp = delete_request(&pool, &admin_token, &format!("{}{}", endpoint, index.id)).await;
+        assert_response_status(resp, StatusCode::NO_CONTENT).await;
+        assert!(!index_exists(&pool, &index.index_name()));
+
+        // Deleting the namespace (and with it the class) drops the index as well
+        let resp = post_request(&pool, &admin_token, &endpoint, &body).await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let index: ClassIndex = test::read_body_json(resp).await;
+        assert!(index_exists(&pool, &index.index_name()));
+
+        cleanup(&created_classes).await;
+        assert!(!index_exists(&pool, &index.index_name()));
+    }
+
+    #[actix_web::test]
+    async fn test_api_classes_clone() {
+        use crate::db::traits::SelfRelations;
+        use crate::models::{NewHubuumClassRelation, Permissions};
+        use crate::tests::{create_test_group, create_test_user};
+        use crate::traits::PermissionController;
+
+        let created_classes = create_test_classes("api_classes_clone").await;
+        let source = &created_classes[5];
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        for (from, to) in [(0, 5), (5, 1)] {
+            NewHubuumClassRelation {
+                from_hubuum_class_id: created_classes[from].id,
+                to_hubuum_class_id: created_classes[to].id,
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+        }
+
+        let relation_pairs = |relations: Vec<crate::models::HubuumClassRelation>| {
+            let mut pairs = relations
+                .iter()
+                .map(|r| {
+                    let mut pair = [r.from_hubuum_class_id, r.to_hubuum_class_id];
+                    pair.sort();
+                    pair
+                })
+                .collect::<Vec<_>>();
+            pairs.sort();
+            pairs
+        };
+
+        let endpoint = format!("{}/{}/clone", CLASSES_ENDPOINT, source.id);
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &endpoint,
+            serde_json::json!({"name": "api_classes_clone_copy", "include_relations": true}),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let clone: HubuumClass = test::read_body_json(resp).await;
+
+        assert_ne!(clone.id, source.id);
+        assert_eq!(clone.name, "api_classes_clone_copy");
+        assert_eq!(clone.namespace_id, source.namespace_id);
+        assert_eq!(clone.json_schema, source.json_schema);
+        assert_eq!(clone.validate_schema, source.validate_schema);
+        assert_eq!(clone.description, source.description);
+
+        // The relations of the source are remapped to the clone, and the source keeps its own
+        let mut expected = vec![
+            [created_classes[0].id, clone.id],
+            [created_classes[1].id, clone.id],
+        ];
+        expected.sort();
+        assert_eq!(
+            relation_pairs(clone.relations(&pool).await.unwrap()),
+            expected
+        );
+        assert_eq!(source.relations(&pool).await.unwrap().len(), 2);
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &endpoint,
+            serde_json::json!({"name": "api_classes_clone_copy"}),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::CONFLICT).await;
+
+        // A clone into another namespace without relations
+        let other_ns = create_namespace(&pool, "api_classes_clone_other")
+            .await
+            .unwrap();
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &endpoint,
+            serde_json::json!({"name": "api_classes_clone_other", "namespace_id": other_ns.id}),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let other_clone: HubuumClass = test::read_body_json(resp).await;
+        assert_eq!(other_clone.namespace_id, other_ns.id);
+        assert!(other_clone.relations(&pool).await.unwrap().is_empty());
+
+        // Copying relations requires the right to create them
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        let token = user.create_token(&pool).await.unwrap().get_token();
+        let source_ns = NamespaceID(source.namespace_id);
+
+        let resp = post_request(
+            &pool,
+            &token,
+            &endpoint,
+            serde_json::json!({"name": "api_classes_clone_user"}),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        for permission in [Permissions::ReadClass, Permissions::CreateClass] {
+            source_ns
+                .grant_one(&pool, group.id, permission)
+                .await
+                .unwrap();
+        }
+
+        let resp = post_request(
+            &pool,
+            &token,
+            &endpoint,
+            serde_json::json!({"name": "api_classes_clone_user", "include_relations": true}),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = post_request(
+            &pool,
+            &token,
+            &endpoint,
+            serde_json::json!({"name": "api_classes_clone_user"}),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::CREATED).await;
+
+        other_ns.delete(&pool).await.unwrap();
+        group.delete(&pool).await.unwrap();
+        user.delete(&pool).await.unwrap();
+        cleanup(&created_classes).await;
+    }
 }
@@ -1,13 +1,17 @@
 #[cfg(test)]
 mod tests {
-    use crate::models::group::{Group, NewGroup, UpdateGroup};
+    use crate::models::group::{Group, GroupDeletionImpact, NewGroup, UpdateGroup};
+    use crate::models::namespace::Namespace;
     use crate::models::user::User;
+    use crate::models::Permissions;
+    use crate::traits::{CanDelete, PermissionController};
     use actix_web::{http::StatusCode, test};
 
     use crate::tests::api_operations::{delete_request, get_request, patch_request, post_request};
     use crate::tests::asserts::assert_response_status;
     use crate::tests::{
-        create_test_admin, create_test_group, create_test_user, setup_pool_and_tokens,
+        create_namespace, create_test_admin, create_test_group, create_test_user,
+        setup_pool_and_tokens,
     };
 
     const GROUPS_ENDPOINT: &str = "/api/v1/iam/groups";
@@ -93,6 +97,76 @@ mod tests {
         let _ = assert_response_status(resp, StatusCode::NOT_FOUND).await;
     }
 
+    #[actix_web::test]
+    async fn test_delete_group_dry_run() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let group = create_test_group(&pool).await;
+        let users = [create_test_user(&pool).await, create_test_user(&pool).await];
+        for user in &users {
+            group.add_member(&pool, user).await.unwrap();
+        }
+        let namespaces = [
+            create_namespace(&pool, "delete_group_dry_run_1")
+                .await
+                .unwrap(),
+            create_namespace(&pool, "delete_group_dry_run_2")
+                .await
+                .unwrap(),
+        ];
+        for namespace in &namespaces {
+            namespace
+                .grant_one(&pool, group.id, Permissions::ReadCollection)
+                .await
+                .unwrap();
+        }
+
+        let group_url = format!("{}/{}", GROUPS_ENDPOINT, group.id);
+        let dry_run_url = format!("{}?dry_run=true", group_url);
+
+        let resp = delete_request(&pool, &normal_token, &dry_run_url).await;
+        let _ = assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = delete_request(&pool, &admin_token, &dry_run_url).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let impact: GroupDeletionImpact = test::read_body_json(resp).await;
+        assert_eq!(
+            impact,
+            GroupDeletionImpact {
+                members: 2,
+                permission_grants: 2,
+            }
+        );
+
+        // Nothing was deleted.
+        let resp = get_request(&pool, &admin_token, &group_url).await;
+        let _ = assert_response_status(resp, StatusCode::OK).await;
+        assert_eq!(group.members(&pool).await.unwrap().len(), 2);
+        assert_eq!(group.deletion_impact(&pool).await.unwrap(), impact);
+
+        let resp = delete_request(
+            &pool,
+            &admin_token,
+            &format!("{}/{}?dry_run=true", GROUPS_ENDPOINT, i32::MAX),
+        )
+        .await;
+        let _ = assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        let resp =
+            delete_request(&pool, &admin_token, &format!("{}?dry_run=false", group_url)).await;
+        let _ = assert_response_status(resp, StatusCode::NO_CONTENT).await;
+
+        let resp = get_request(&pool, &admin_token, &group_url).await;
+        let _ = assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        for namespace in namespaces {
+            namespace.delete(&pool).await.unwrap();
+        }
+        for user in users {
+            user.delete(&pool).await.unwrap();
+        }
+    }
+
     #[actix_web::test]
     async fn test_patch_group() {
         let (pool, admin_token, group_token) = setup_pool_and_tokens().await;
@@ -100,6 +174,7 @@ mod tests {
         // Test setting a new password
         let updated_group = UpdateGroup {
             groupname: Some("newgroupname".to_string()),
+            active: None,
         };
 
         let test_group = create_test_group(&pool).await;
@@ -120,4 +195,95 @@ mod tests {
         assert_eq!(patched_group.groupname, updated_group.groupname.unwrap());
         assert_eq!(patched_group, refetched_group);
     }
+
+    #[actix_web::test]
+    async fn test_deactivate_group_suppresses_permissions() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let ns = create_namespace(&pool, "test_deactivate_group")
+            .await
+            .unwrap();
+        let test_group = create_test_group(&pool).await;
+        let test_user = create_test_user(&pool).await;
+
+        test_group.add_member(&pool, &test_user).await.unwrap();
+        ns.grant_one(&pool, test_group.id, Permissions::ReadCollection)
+            .await
+            .unwrap();
+        let token = test_user.create_token(&pool).await.unwrap().get_token();
+
+        let ns_endpoint = format!("/api/v1/namespaces/{}", ns.id);
+        let group_endpoint = format!("{}/{}", GROUPS_ENDPOINT, test_group.id);
+        let set_active = |active: bool| UpdateGroup {
+            groupname: None,
+            active: Some(active),
+        };
+
+        let resp = get_request(&pool, &token, &ns_endpoint).await;
+        let _ = assert_response_status(resp, StatusCode::OK).await;
+
+        let resp = patch_request(&pool, &admin_token, &group_endpoint, set_active(false)).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let patched_group: Group = test::read_body_json(resp).await;
+        assert!(!patched_group.active);
+
+        // The grant is still there, but it no longer applies
+        let resp = get_request(&pool, &token, &ns_endpoint).await;
+        let _ = assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = get_request(&pool, &token, "/api/v1/namespaces").await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let namespaces: Vec<Namespace> = test::read_body_json(resp).await;
+        assert!(!namespaces.contains(&ns));
+
+        let resp = patch_request(&pool, &admin_token, &group_endpoint, set_active(true)).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let patched_group: Group = test::read_body_json(resp).await;
+        assert!(patched_group.active);
+
+        let resp = get_request(&pool, &token, &ns_endpoint).await;
+        let _ = assert_response_status(resp, StatusCode::OK).await;
+
+        ns.delete(&pool).await.unwrap();
+        test_group.delete(&pool).await.unwrap();
+        test_user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_deactivated_group_membership_by_name() {
+        // Admin access is membership of the group named "admin", looked up with
+        // is_in_group_by_name. The admin group is shared by all tests, so the lookup is tested
+        // with a group of our own.
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let test_group = create_test_group(&pool).await;
+        let test_user = create_test_user(&pool).await;
+        test_group.add_member(&pool, &test_user).await.unwrap();
+
+        let set_active = |active: bool| UpdateGroup {
+            groupname: None,
+            active: Some(active),
+        };
+
+        assert!(
+            test_user
+                .is_in_group_by_name(&test_group.groupname, &pool)
+                .await
+        );
+
+        set_active(false).save(test_group.id, &pool).await.unwrap();
+        assert!(
+            !test_user
+                .is_in_group_by_name(&test_group.groupname, &pool)
+                .await
+        );
+
+        set_active(true).save(test_group.id, &pool).await.unwrap();
+        assert!(
+            test_user
+                .is_in_group_by_name(&test_group.groupname, &pool)
+                .await
+        );
+
+        test_group.delete(&pool).await.unwrap();
+        test_user.delete(&pool).await.unwrap();
+    }
 }
@@ -1,7 +1,9 @@
+pub mod admin;
 pub mod auth;
 pub mod classes;
 pub mod groups;
 pub mod namespaces;
 pub mod objects;
 pub mod relations;
+pub mod schemas;
 pub mod users;
@@ -1,19 +1,23 @@
 #[cfg(test)]
 mod tests {
+    use yare::parameterized;
+
     use crate::models::{
-        GroupPermission, Namespace, NewNamespaceWithAssignee, Permission, Permissions,
-        UpdateNamespace,
+        GroupPermission, HubuumClassExpanded, Namespace, NamespaceID, NamespaceTransfer,
+        NewNamespaceWithAssignee, Permission, Permissions, UpdateNamespace,
     };
 
+    use crate::tests::api::v1::classes::tests::{cleanup, create_test_classes};
     use crate::tests::api_operations::{delete_request, get_request, patch_request, post_request};
     use crate::tests::asserts::assert_response_status;
     use crate::tests::{
         create_namespace, create_test_group, create_test_user, ensure_admin_group,
         setup_pool_and_tokens,
     };
-    use crate::traits::{CanDelete, PermissionController};
-    use crate::{assert_contains, assert_contains_all};
+    use crate::traits::{CanDelete, CanSave, PermissionController, SelfAccessors};
+    use crate::{assert_contains, assert_contains_all, assert_contains_same_ids};
     use actix_web::{http, test};
+    use serde_json::json;
 
     const NAMESPACE_ENDPOINT: &str = "/api/v1/namespaces";
 
@@ -282,6 +286,142 @@ mod tests {
         normal_group.delete(&pool).await.unwrap();
     }
 
+    #[actix_web::test]
+    async fn test_api_namespace_permissions_revoke_reason() {
+        use crate::schema::permission_audit::dsl::*;
+        use diesel::prelude::*;
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let ns = create_namespace(&pool, "test_namespace_permissions_revoke_reason")
+            .await
+            .unwrap();
+        let group = create_test_group(&pool).await;
+        let group_endpoint = format!(
+            "{}/{}/permissions/group/{}",
+            NAMESPACE_ENDPOINT, ns.id, group.id
+        );
+
+        for permission in ["ReadCollection", "CreateClass"] {
+            let endpoint = format!("{}/{}", group_endpoint, permission);
+            let resp = post_request(&pool, &admin_token, &endpoint, &()).await;
+            let _ = assert_response_status(resp, http::StatusCode::CREATED).await;
+        }
+
+        let endpoint = format!("{}/CreateClass?reason=leaving%20the%20team", group_endpoint);
+        let resp = delete_request(&pool, &admin_token, &endpoint).await;
+        let _ = assert_response_status(resp, http::StatusCode::NO_CONTENT).await;
+
+        let resp = delete_request(&pool, &admin_token, &group_endpoint).await;
+        let _ = assert_response_status(resp, http::StatusCode::NO_CONTENT).await;
+
+        // Nothing left to revoke, so nothing is recorded.
+        let resp = delete_request(&pool, &admin_token, &group_endpoint).await;
+        let _ = assert_response_status(resp, http::StatusCode::NO_CONTENT).await;
+
+        let mut conn = pool.get().unwrap();
+        let entries = permission_audit
+            .filter(namespace_id.eq(ns.id))
+            .filter(group_id.eq(group.id))
+            .order_by(id)
+            .select((action, permissions, reason))
+            .load::<(String, Option<String>, Option<String>)>(&mut conn)
+            .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "revoke".to_string(),
+                    Some("CreateClass".to_string()),
+                    Some("leaving the team".to_string())
+                ),
+                ("revoke".to_string(), None, None),
+            ]
+        );
+
+        ns.delete(&pool).await.unwrap();
+        group.delete(&pool).await.unwrap();
+    }
+
+    #[parameterized(
+        keep_access = { true },
+        drop_access = { false },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn test_api_namespace_transfer(keep_access: bool) {
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let old_owner = create_test_group(&pool).await;
+        let new_owner = create_test_group(&pool).await;
+        let operators = create_test_group(&pool).await;
+        let operator = create_test_user(&pool).await;
+        operators.add_member(&pool, &operator).await.unwrap();
+        let token = operator.create_token(&pool).await.unwrap().get_token();
+
+        let ns = NewNamespaceWithAssignee {
+            name: format!("test_namespace_transfer_{}", keep_access),
+            description: "Transfer test namespace".to_string(),
+            group_id: old_owner.id,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+        ns.grant_one(&pool, operators.id, Permissions::DelegateCollection)
+            .await
+            .unwrap();
+
+        let endpoint = format!("{}/{}/transfer", NAMESPACE_ENDPOINT, ns.id);
+        let transfer = NamespaceTransfer {
+            from_group_id: old_owner.id,
+            to_group_id: new_owner.id,
+            keep_access,
+        };
+
+        let resp = post_request(&pool, &token, &endpoint, &transfer).await;
+        let resp = assert_response_status(resp, http::StatusCode::OK).await;
+        let permissions: Vec<Permission> = test::read_body_json(resp).await;
+
+        let group_ids = permissions.iter().map(|p| p.group_id).collect::<Vec<_>>();
+        if keep_access {
+            assert_eq!(group_ids, vec![old_owner.id, new_owner.id, operators.id]);
+        } else {
+            assert_eq!(group_ids, vec![new_owner.id, operators.id]);
+        }
+
+        let new_permissions = permissions
+            .iter()
+            .find(|p| p.group_id == new_owner.id)
+            .unwrap();
+        assert!(new_permissions.has_delegate_namespace);
+        assert!(new_permissions.has_delete_object_relation);
+
+        // Transferring again from the old owner only works if it kept its access.
+        let transfer = NamespaceTransfer {
+            from_group_id: old_owner.id,
+            to_group_id: new_owner.id,
+            keep_access: true,
+        };
+        let expected = if keep_access {
+            http::StatusCode::OK
+        } else {
+            http::StatusCode::NOT_FOUND
+        };
+        let resp = post_request(&pool, &token, &endpoint, &transfer).await;
+        let _ = assert_response_status(resp, expected).await;
+
+        let transfer = NamespaceTransfer {
+            from_group_id: new_owner.id,
+            to_group_id: new_owner.id,
+            keep_access: false,
+        };
+        let resp = post_request(&pool, &token, &endpoint, &transfer).await;
+        let _ = assert_response_status(resp, http::StatusCode::BAD_REQUEST).await;
+
+        ns.delete(&pool).await.unwrap();
+        old_owner.delete(&pool).await.unwrap();
+        new_owner.delete(&pool).await.unwrap();
+        operators.delete(&pool).await.unwrap();
+    }
+
     /// Test that after granting a permission to a group, the API allows us to perform
     /// the action that the permission grants.
     #[actix_web::test]
@@ -372,4 +512,367 @@ mod tests {
         test_group.delete(&pool).await.unwrap();
         test_user.delete(&pool).await.unwrap();
     }
+
+    #[actix_web::test]
+    async fn test_api_namespace_classes() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+        let created_classes = create_test_classes("api_namespace_classes").await;
+        let ns_id = created_classes[0].namespace_id;
+
+        let classes_endpoint = format!("{}/{}/classes", NAMESPACE_ENDPOINT, ns_id);
+
+        let resp = get_request(&pool, &admin_token, &classes_endpoint).await;
+        let resp = assert_response_status(resp, http::StatusCode::OK).await;
+        let classes: Vec<HubuumClassExpanded> = test::read_body_json(resp).await;
+        assert_contains_same_ids!(&classes, &created_classes);
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!("{}?name__endswith=class_6", classes_endpoint),
+        )
+        .await;
+        let resp = assert_response_status(resp, http::StatusCode::OK).await;
+        let classes: Vec<HubuumClassExpanded> = test::read_body_json(resp).await;
+        assert_contains_same_ids!(&classes, &[&created_classes[5]]);
+
+        // The normal user can't read the namespace, so it isn't there as far as they know
+        let resp = get_request(&pool, &normal_token, &classes_endpoint).await;
+        let _ = assert_response_status(resp, http::StatusCode::NOT_FOUND).await;
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!("{}/{}/classes", NAMESPACE_ENDPOINT, i32::MAX),
+        )
+        .await;
+        let _ = assert_response_status(resp, http::StatusCode::NOT_FOUND).await;
+
+        cleanup(&created_classes).await;
+    }
+
+    #[actix_web::test]
+    async fn test_api_namespace_write_rate_limit() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+        let namespace = create_namespace(&pool, "api_namespace_write_rate_limit")
+            .await
+            .unwrap();
+        let limit_endpoint = format!("{}/{}", NAMESPACE_ENDPOINT, namespace.id);
+
+        let resp = patch_request(
+            &pool,
+            &normal_token,
+            &limit_endpoint,
+            &json!({"write_rate_limit": 2}),
+        )
+        .await;
+        let _ = assert_response_status(resp, http::StatusCode::FORBIDDEN).await;
+
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &limit_endpoint,
+            &json!({"write_rate_limit": -1}),
+        )
+        .await;
+        let _ = assert_response_status(resp, http::StatusCode::BAD_REQUEST).await;
+
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &limit_endpoint,
+            &json!({"write_rate_limit": 2}),
+        )
+        .await;
+        let resp = assert_response_status(resp, http::StatusCode::ACCEPTED).await;
+        let updated: Namespace = test::read_body_json(resp).await;
+        assert_eq!(updated.write_rate_limit, Some(2));
+
+        let create_class = |name: &str| {
+            json!({
+                "name": format!("api_namespace_write_rate_limit_{}", name),
+                "namespace_id": namespace.id,
+                "description": "Rate limited class",
+            })
+        };
+
+        for name in ["one", "two"] {
+            let resp =
+                post_request(&pool, &admin_token, "/api/v1/classes", &create_class(name)).await;
+            let _ = assert_response_status(resp, http::StatusCode::CREATED).await;
+        }
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            "/api/v1/classes",
+            &create_class("three"),
+        )
+        .await;
+        let resp = assert_response_status(resp, http::StatusCode::TOO_MANY_REQUESTS).await;
+        let retry_after: u64 = resp
+            .headers()
+            .get(http::header::RETRY_AFTER)
+            .expect("Retry-After header missing")
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after > 0 && retry_after <= 60);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "too_many_requests");
+
+        // Clearing the override goes back to the configured default, which is no limit.
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &limit_endpoint,
+            &json!({"write_rate_limit": null}),
+        )
+        .await;
+        let resp = assert_response_status(resp, http::StatusCode::ACCEPTED).await;
+        let updated: Namespace = test::read_body_json(resp).await;
+        assert_eq!(updated.write_rate_limit, None);
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            "/api/v1/classes",
+            &create_class("three"),
+        )
+        .await;
+        let _ = assert_response_status(resp, http::StatusCode::CREATED).await;
+
+        namespace.delete(&pool).await.unwrap();
+    }
+
+    #[parameterized(
+        object_quota = { "object_quota", json!(1000) },
+        default_class_permissions = { "default_class_permissions", json!(["ReadClass", "ReadObject"]) },
+        attachment_size_limit = { "attachment_size_limit", json!(10_000_000_000i64) },
+        write_rate_limit = { "write_rate_limit", json!(100) },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn test_api_namespace_settings(setting: &str, value: serde_json::Value) {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let namespace = create_namespace(&pool, &format!("api_namespace_settings_{}", setting))
+            .await
+            .unwrap();
+        let endpoint = format!("{}/{}", NAMESPACE_ENDPOINT, namespace.id);
+
+        let resp = patch_request(&pool, &admin_token, &endpoint, &json!({ setting: value })).await;
+        let resp = assert_response_status(resp, http::StatusCode::ACCEPTED).await;
+        let updated: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(updated[setting], value);
+        assert_eq!(updated["name"], json!(namespace.name));
+
+        // Settings that are left out are kept.
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &endpoint,
+            &json!({"description": "Still set"}),
+        )
+        .await;
+        let resp = assert_response_status(resp, http::StatusCode::ACCEPTED).await;
+        let updated: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(updated[setting], value);
+        assert_eq!(updated["description"], "Still set");
+
+        let resp = patch_request(&pool, &admin_token, &endpoint, &json!({ setting: null })).await;
+        let resp = assert_response_status(resp, http::StatusCode::ACCEPTED).await;
+        let updated: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(updated[setting], serde_json::Value::Null);
+
+        namespace.delete(&pool).await.unwrap();
+    }
+
+    #[parameterized(
+        negative_quota = { json!({"object_quota": -1}) },
+        negative_attachment_size = { json!({"attachment_size_limit": -1}) },
+        negative_write_rate_limit = { json!({"write_rate_limit": -1}) },
+        unknown_permission = { json!({"default_class_permissions": ["ReadEverything"]}) },
+        collection_permission = { json!({"default_class_permissions": ["DelegateCollection"]}) },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn test_api_namespace_settings_invalid(body: serde_json::Value) {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let namespace = create_namespace(&pool, "api_namespace_settings_invalid")
+            .await
+            .unwrap();
+        let endpoint = format!("{}/{}", NAMESPACE_ENDPOINT, namespace.id);
+
+        // Nothing is updated when a setting is out of range, including the description.
+        let mut body = body;
+        body["description"] = json!("Not updated");
+        let resp = patch_request(&pool, &admin_token, &endpoint, &body).await;
+        let _ = assert_response_status(resp, http::StatusCode::BAD_REQUEST).await;
+
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, http::StatusCode::OK).await;
+        let fetched: Namespace = test::read_body_json(resp).await;
+        assert_eq!(fetched, namespace);
+
+        namespace.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_api_namespace_settings_require_delegate() {
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let namespace = create_namespace(&pool, "api_namespace_settings_delegate")
+            .await
+            .unwrap();
+        let endpoint = format!("{}/{}", NAMESPACE_ENDPOINT, namespace.id);
+
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        namespace
+            .grant_one(&pool, group.id, Permissions::UpdateCollection)
+            .await
+            .unwrap();
+        let token = user.create_token(&pool).await.unwrap().get_token();
+
+        let resp =
+            patch_request(&pool, &token, &endpoint, &json!({"description": "Updated"})).await;
+        let _ = assert_response_status(resp, http::StatusCode::ACCEPTED).await;
+
+        let resp = patch_request(&pool, &token, &endpoint, &json!({"object_quota": 10})).await;
+        let _ = assert_response_status(resp, http::StatusCode::FORBIDDEN).await;
+
+        namespace
+            .grant_one(&pool, group.id, Permissions::DelegateCollection)
+            .await
+            .unwrap();
+        let resp = patch_request(&pool, &token, &endpoint, &json!({"object_quota": 10})).await;
+        let resp = assert_response_status(resp, http::StatusCode::ACCEPTED).await;
+        let updated: Namespace = test::read_body_json(resp).await;
+        assert_eq!(updated.object_quota, Some(10));
+        assert_eq!(updated.description, "Updated");
+
+        // The write rate limit is admin only, even for those who may delegate the namespace.
+        let body = json!({"object_quota": 20, "write_rate_limit": 10});
+        let resp = patch_request(&pool, &token, &endpoint, &body).await;
+        let _ = assert_response_status(resp, http::StatusCode::FORBIDDEN).await;
+        let fetched = NamespaceID(namespace.id).instance(&pool).await.unwrap();
+        assert_eq!(fetched.object_quota, Some(10));
+        assert_eq!(fetched.write_rate_limit, None);
+
+        namespace.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_api_namespace_search() {
+        use crate::models::{ClassOrObject, NewHubuumClass, NewHubuumObject};
+
+        fn kinds_and_ids(results: &[ClassOrObject]) -> Vec<(&'static str, i32)> {
+            results
+                .iter()
+                .map(|result| match result {
+                    ClassOrObject::Class(class) => ("class", class.id),
+                    ClassOrObject::Object(object) => ("object", object.id),
+                })
+                .collect()
+        }
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let namespace = create_namespace(&pool, "api_namespace_search")
+            .await
+            .unwrap();
+        let other = create_namespace(&pool, "api_namespace_search_other")
+            .await
+            .unwrap();
+
+        let mut classes = vec![];
+        for (name, nid) in [
+            ("api_namespace_search_Web", namespace.id),
+            ("api_namespace_search_mail", namespace.id),
+            ("api_namespace_search_web_other", other.id),
+        ] {
+            let class = NewHubuumClass {
+                name: name.to_string(),
+                namespace_id: nid,
+                json_schema: None,
+                validate_schema: Some(false),
+                description: "Namespace search class".to_string(),
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+            classes.push(class);
+        }
+
+        let mut objects = vec![];
+        for (name, class) in [
+            ("web-1", &classes[1]),
+            ("WEB-2", &classes[0]),
+            ("mail-1", &classes[1]),
+            ("web-3", &classes[2]),
+        ] {
+            let object = NewHubuumObject {
+                name: name.to_string(),
+                namespace_id: class.namespace_id,
+                hubuum_class_id: class.id,
+                data: json!({}),
+                description: "Namespace search object".to_string(),
+                external_id: None,
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+            objects.push(object);
+        }
+
+        let endpoint = format!("{}/{}/search", NAMESPACE_ENDPOINT, namespace.id);
+
+        // Classes come first, and only what lives in the namespace is returned.
+        let resp = get_request(&pool, &admin_token, &format!("{}?q=web", endpoint)).await;
+        let resp = assert_response_status(resp, http::StatusCode::OK).await;
+        let results: Vec<ClassOrObject> = test::read_body_json(resp).await;
+        assert_eq!(
+            kinds_and_ids(&results),
+            vec![
+                ("class", classes[0].id),
+                ("object", objects[0].id),
+                ("object", objects[1].id)
+            ]
+        );
+
+        let resp = get_request(&pool, &admin_token, &format!("{}?q=Mail", endpoint)).await;
+        let resp = assert_response_status(resp, http::StatusCode::OK).await;
+        let results: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(results[0]["kind"], "class");
+        assert_eq!(results[0]["id"], classes[1].id);
+        assert_eq!(results[1]["kind"], "object");
+        assert_eq!(results[1]["id"], objects[2].id);
+        assert_eq!(results.as_array().unwrap().len(), 2);
+
+        for query in ["", "?q=", "?q=%20"] {
+            let resp = get_request(&pool, &admin_token, &format!("{}{}", endpoint, query)).await;
+            let _ = assert_response_status(resp, http::StatusCode::BAD_REQUEST).await;
+        }
+
+        // A namespace the user can't read is not found, and objects need their own permission.
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        let token = user.create_token(&pool).await.unwrap().get_token();
+
+        let resp = get_request(&pool, &token, &format!("{}?q=web", endpoint)).await;
+        let _ = assert_response_status(resp, http::StatusCode::NOT_FOUND).await;
+
+        for permission in [Permissions::ReadCollection, Permissions::ReadClass] {
+            namespace
+                .grant_one(&pool, group.id, permission)
+                .await
+                .unwrap();
+        }
+        let resp = get_request(&pool, &token, &format!("{}?q=web", endpoint)).await;
+        let resp = assert_response_status(resp, http::StatusCode::OK).await;
+        let results: Vec<ClassOrObject> = test::read_body_json(resp).await;
+        assert_eq!(kinds_and_ids(&results), vec![("class", classes[0].id)]);
+
+        namespace.delete(&pool).await.unwrap();
+        other.delete(&pool).await.unwrap();
+    }
 }
@@ -2,13 +2,24 @@
 mod tests {
     use yare::parameterized;
 
-    use crate::models::{HubuumObject, NewHubuumObject, UpdateHubuumObject};
-    use crate::traits::{CanDelete, CanSave};
+    use crate::models::{
+        HubuumObject, NewHubuumClass, NewHubuumClassRelation, NewHubuumObject,
+        NewHubuumObjectRelation, UpdateHubuumObject, UpsertHubuumObject,
+    };
+    use crate::models::{NamespaceID, Permissions};
+    use crate::traits::{CanDelete, CanSave, PermissionController, SelfAccessors};
     use actix_web::{http::StatusCode, test};
+    use std::collections::HashMap;
 
-    use crate::tests::api_operations::{delete_request, get_request, patch_request, post_request};
+    use crate::tests::api_operations::{
+        delete_request, delete_request_with_headers, delete_request_with_json, get_request,
+        patch_request, post_request, put_request,
+    };
     use crate::tests::asserts::assert_response_status;
-    use crate::tests::{create_namespace, setup_pool_and_tokens};
+    use crate::tests::constants::{get_schema, SchemaType};
+    use crate::tests::{
+        create_namespace, create_test_group, create_test_user, setup_pool_and_tokens,
+    };
     // use crate::{assert_contains_all, assert_contains_same_ids};
 
     use crate::tests::api::v1::classes::tests::{cleanup, create_test_classes};
@@ -40,6 +51,7 @@ mod tests {
             data: serde_json::json!({"test": "data"}),
             name: "test object".to_string(),
             description: "test object description".to_string(),
+            external_id: None,
         };
 
         let object = object.save(&pool).await.unwrap();
@@ -62,6 +74,7 @@ mod tests {
             data: None,
             name: Some("updated object".to_string()),
             description: None,
+            external_id: None,
         };
 
         let resp = patch_request(
@@ -107,6 +120,147 @@ mod tests {
         cleanup(&classes).await;
     }
 
+    #[actix_rt::test]
+    async fn patch_object_to_new_class() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let namespace = create_namespace(&pool, "patch_object_to_new_class")
+            .await
+            .unwrap();
+        let classes = create_test_classes("patch_object_to_new_class").await;
+
+        let validating_class = NewHubuumClass {
+            name: "patch_object_to_new_class_validating".to_string(),
+            namespace_id: namespace.id,
+            json_schema: Some(get_schema(SchemaType::Geo).clone()),
+            validate_schema: Some(true),
+            description: "Validating class".to_string(),
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let object = NewHubuumObject {
+            namespace_id: namespace.id,
+            hubuum_class_id: classes[0].id,
+            data: serde_json::json!({"test": "data"}),
+            name: "test move object".to_string(),
+            description: "test move object description".to_string(),
+            external_id: None,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let move_to = |class_id: i32, data: Option<serde_json::Value>| UpdateHubuumObject {
+            namespace_id: None,
+            hubuum_class_id: Some(class_id),
+            data,
+            name: None,
+            description: None,
+            external_id: None,
+        };
+
+        // The path must match the current class of the object
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &object_in_class_endpoint(classes[1].id, object.id),
+            move_to(classes[2].id, None),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        // The normal user has no permissions at all
+        let resp = patch_request(
+            &pool,
+            &normal_token,
+            &object_in_class_endpoint(classes[0].id, object.id),
+            move_to(classes[1].id, None),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        // The current data does not validate against the geo schema
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &object_in_class_endpoint(classes[0].id, object.id),
+            move_to(validating_class.id, None),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "schema_validation_failed");
+        assert_eq!(body["error"], "Bad Request");
+
+        // But data that is passed along with the move is validated instead
+        let geo_data = serde_json::json!({"latitude": 60.0, "longitude": 10.0});
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &object_in_class_endpoint(classes[0].id, object.id),
+            move_to(validating_class.id, Some(geo_data.clone())),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let moved_object: HubuumObject = test::read_body_json(resp).await;
+        assert_eq!(moved_object.hubuum_class_id, validating_class.id);
+        assert_eq!(moved_object.data, geo_data);
+
+        // Moving to a class without validation works regardless of data
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &object_in_class_endpoint(validating_class.id, object.id),
+            move_to(classes[1].id, None),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let moved_object: HubuumObject = test::read_body_json(resp).await;
+        assert_eq!(moved_object.hubuum_class_id, classes[1].id);
+
+        // Objects with relations can not be moved
+        let class_relation = NewHubuumClassRelation {
+            from_hubuum_class_id: classes[1].id,
+            to_hubuum_class_id: classes[2].id,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+        let other_object = NewHubuumObject {
+            namespace_id: namespace.id,
+            hubuum_class_id: classes[2].id,
+            data: serde_json::json!({}),
+            name: "test move object relation".to_string(),
+            description: "test move object relation description".to_string(),
+            external_id: None,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+        NewHubuumObjectRelation {
+            from_hubuum_object_id: object.id,
+            to_hubuum_object_id: other_object.id,
+            class_relation_id: class_relation.id,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &object_in_class_endpoint(classes[1].id, object.id),
+            move_to(classes[0].id, None),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::CONFLICT).await;
+
+        namespace.delete(&pool).await.unwrap();
+        cleanup(&classes).await;
+    }
+
     // This will create objects with the same name but potentially in differnet classes.
     // This is to test that the name is unique within the class.
     // [class_idx1, class_idx2] [expected_status1, expected_status2]
@@ -143,6 +297,7 @@ mod tests {
                 data: serde_json::json!({"test": "data"}),
                 name: "test create object".to_string(),
                 description: "test create object description".to_string(),
+                external_id: None,
             };
 
             let resp = post_request(
@@ -174,6 +329,74 @@ mod tests {
         namespace.delete(&pool).await.unwrap();
     }
 
+    #[actix_rt::test]
+    async fn create_object_in_unusable_namespace() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let classes = create_test_classes("create_object_in_unusable_namespace").await;
+        let class = &classes[0];
+        let namespace = create_namespace(&pool, "create_object_in_unusable_namespace")
+            .await
+            .unwrap();
+
+        let object = |name: &str, namespace_id: i32| NewHubuumObject {
+            namespace_id,
+            hubuum_class_id: class.id,
+            data: serde_json::json!({}),
+            name: format!("unusable namespace {}", name),
+            description: "unusable namespace".to_string(),
+            external_id: None,
+        };
+        let endpoint = objects_in_class_endpoint(class.id);
+
+        let resp = post_request(&pool, &admin_token, &endpoint, object("missing", i32::MAX)).await;
+        let resp = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            body["message"],
+            format!("Namespace {} does not exist", i32::MAX)
+        );
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &format!("{}/{}/batch", OBJECT_ENDPOINT, class.id),
+            vec![
+                object("batch", class.namespace_id),
+                object("batch missing", i32::MAX),
+            ],
+        )
+        .await;
+        assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+
+        // May create objects of the class, but not in the namespace.
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        let token = user.create_token(&pool).await.unwrap().get_token();
+        NamespaceID(class.namespace_id)
+            .instance(&pool)
+            .await
+            .unwrap()
+            .grant_one(&pool, group.id, Permissions::CreateObject)
+            .await
+            .unwrap();
+
+        let resp = post_request(&pool, &token, &endpoint, object("denied", namespace.id)).await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        namespace
+            .grant_one(&pool, group.id, Permissions::CreateObject)
+            .await
+            .unwrap();
+        let resp = post_request(&pool, &token, &endpoint, object("allowed", namespace.id)).await;
+        assert_response_status(resp, StatusCode::CREATED).await;
+
+        cleanup(&classes).await;
+        namespace.delete(&pool).await.unwrap();
+        group.delete(&pool).await.unwrap();
+        user.delete(&pool).await.unwrap();
+    }
+
     #[actix_rt::test]
     async fn get_objects_in_class() {
         let (pool, admin_token, _) = setup_pool_and_tokens().await;
@@ -194,6 +417,7 @@ mod tests {
                 data: serde_json::json!({"test": format!("data_{}", i)}),
                 name: format!("test get objects {}", i),
                 description: format!("test object description {}", i),
+                external_id: None,
             };
             objects.push(object.save(&pool).await.unwrap());
         }
@@ -204,4 +428,2070 @@ mod tests {
 
         assert_eq!(objects_from_api.len(), objects.len());
     }
+
+    #[actix_rt::test]
+    async fn sample_objects_in_class_with_seed() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        let namespace = create_namespace(&pool, "sample_objects_in_class")
+            .await
+            .unwrap();
+        let classes = create_test_classes("sample_objects_in_class").await;
+        let class = &classes[0];
+
+        for i in 0..20 {
+            NewHubuumObject {
+                namespace_id: namespace.id,
+                hubuum_class_id: class.id,
+                data: serde_json::json!({}),
+                name: format!("sample object {}", i),
+                description: format!("sample object description {}", i),
+                external_id: None,
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+        }
+
+        let sample = |query: &'static str| {
+            let pool = pool.clone();
+            let admin_token = admin_token.clone();
+            let endpoint = format!("{}?{}", objects_in_class_endpoint(class.id), query);
+            async move {
+                let resp = get_request(&pool, &admin_token, &endpoint).await;
+                let resp = assert_response_status(resp, StatusCode::OK).await;
+                let objects: Vec<HubuumObject> = test::read_body_json(resp).await;
+                objects.into_iter().map(|o| o.id).collect::<Vec<_>>()
+            }
+        };
+
+        let first = sample("order_by=random&seed=42&limit=5").await;
+        assert_eq!(first.len(), 5);
+        assert_eq!(first, sample("order_by=random&seed=42&limit=5").await);
+
+        // The full order is a permutation of all objects, and another seed gives another one.
+        let all = sample("order_by=random&seed=42").await;
+        assert_eq!(all.len(), 20);
+        assert_eq!(&all[..5], first.as_slice());
+        assert_ne!(all, sample("order_by=random&seed=43").await);
+
+        // Without order_by, objects are sorted by ID, so pages are stable and don't overlap.
+        let mut sorted = all.clone();
+        sorted.sort();
+        assert_eq!(sample("").await, sorted);
+        assert_eq!(sample("limit=5").await, sorted[..5]);
+        assert_eq!(sample("limit=5&offset=5").await, sorted[5..10]);
+
+        for query in [
+            "order_by=random&seed=42&offset=5",
+            "order_by=name",
+            "seed=42",
+            "order_by=random&seed=-1",
+        ] {
+            let endpoint = format!("{}?{}", objects_in_class_endpoint(class.id), query);
+            let resp = get_request(&pool, &admin_token, &endpoint).await;
+            assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        }
+
+        cleanup(&classes).await;
+        namespace.delete(&pool).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn get_objects_in_class_as_map() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        let namespace = create_namespace(&pool, "objects_in_class_as_map")
+            .await
+            .unwrap();
+        let classes = create_test_classes("objects_in_class_as_map").await;
+        let class = &classes[0];
+
+        let mut created = vec![];
+        for i in 0..3 {
+            let object = NewHubuumObject {
+                namespace_id: namespace.id,
+                hubuum_class_id: class.id,
+                data: serde_json::json!({}),
+                name: format!("map object {}", i),
+                description: format!("map object description {}", i),
+                external_id: None,
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+            created.push(object);
+        }
+
+        for endpoint in [
+            format!("{}?shape=map", objects_in_class_endpoint(class.id)),
+            format!("/api/v1/objects/?classes={}&shape=map", class.id),
+        ] {
+            let resp = get_request(&pool, &admin_token, &endpoint).await;
+            let resp = assert_response_status(resp, StatusCode::OK).await;
+            let objects: HashMap<String, HubuumObject> = test::read_body_json(resp).await;
+            assert_eq!(objects.len(), created.len());
+            for object in &created {
+                assert_eq!(objects.get(&object.id.to_string()), Some(object));
+            }
+        }
+
+        let endpoint = format!("{}?shape=array", objects_in_class_endpoint(class.id));
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert_eq!(objects.len(), created.len());
+
+        for query in [
+            "shape=list",
+            "shape__not_equals=map",
+            "shape=map&shape=array",
+        ] {
+            let endpoint = format!("{}?{}", objects_in_class_endpoint(class.id), query);
+            let resp = get_request(&pool, &admin_token, &endpoint).await;
+            assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        }
+
+        cleanup(&classes).await;
+        namespace.delete(&pool).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn objects_with_external_id() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let namespaces = [
+            create_namespace(&pool, "objects_with_external_id_1")
+                .await
+                .unwrap(),
+            create_namespace(&pool, "objects_with_external_id_2")
+                .await
+                .unwrap(),
+        ];
+        let classes = create_test_classes("objects_with_external_id").await;
+        let class = &classes[0];
+
+        let new_object = |namespace_id: i32, name: &str| NewHubuumObject {
+            namespace_id,
+            hubuum_class_id: class.id,
+            data: serde_json::json!({}),
+            name: name.to_string(),
+            description: "external id object".to_string(),
+            external_id: Some("ext-1".to_string()),
+        };
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &objects_in_class_endpoint(class.id),
+            new_object(namespaces[0].id, "external id object 1"),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let first: HubuumObject = test::read_body_json(resp).await;
+        assert_eq!(first.external_id, Some("ext-1".to_string()));
+
+        // The same external ID in the same namespace is a conflict
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &objects_in_class_endpoint(class.id),
+            new_object(namespaces[0].id, "external id object 2"),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::CONFLICT).await;
+
+        // ...but it may be reused in another namespace
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &objects_in_class_endpoint(class.id),
+            new_object(namespaces[1].id, "external id object 3"),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let second: HubuumObject = test::read_body_json(resp).await;
+
+        // Ambiguous lookups are conflicts until narrowed down by namespace
+        let resp = get_request(&pool, &admin_token, "/api/v1/objects/by-external-id/ext-1").await;
+        assert_response_status(resp, StatusCode::CONFLICT).await;
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!(
+                "/api/v1/objects/by-external-id/ext-1?namespaces={}",
+                namespaces[1].id
+            ),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let object_from_api: HubuumObject = test::read_body_json(resp).await;
+        assert_eq!(object_from_api, second);
+
+        let resp = get_request(&pool, &admin_token, "/api/v1/objects/by-external-id/nope").await;
+        assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        // The normal user can't see any of the objects
+        let resp = get_request(
+            &pool,
+            &normal_token,
+            &format!(
+                "/api/v1/objects/by-external-id/ext-1?namespaces={}",
+                namespaces[1].id
+            ),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!(
+                "{}?external_id=ext-1&namespaces={}",
+                objects_in_class_endpoint(class.id),
+                namespaces[0].id
+            ),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects_from_api: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert_eq!(objects_from_api, vec![first.clone()]);
+
+        // Leaving the external ID out of a PATCH keeps it, and setting it to null clears it
+        let endpoint = object_in_class_endpoint(class.id, first.id);
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &endpoint,
+            serde_json::json!({"name": "external id object 1 renamed"}),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let renamed: HubuumObject = test::read_body_json(resp).await;
+        assert_eq!(renamed.external_id, Some("ext-1".to_string()));
+
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &endpoint,
+            serde_json::json!({"external_id": null}),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let cleared: HubuumObject = test::read_body_json(resp).await;
+        assert_eq!(cleared.external_id, None);
+        assert_eq!(cleared.name, "external id object 1 renamed");
+
+        for namespace in namespaces {
+            namespace.delete(&pool).await.unwrap();
+        }
+        cleanup(&classes).await;
+    }
+
+    #[actix_rt::test]
+    async fn upsert_object_by_external_id() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let namespace = create_namespace(&pool, "upsert_object_by_external_id")
+            .await
+            .unwrap();
+        let classes = create_test_classes("upsert_object_by_external_id").await;
+        let class = &classes[0];
+
+        let upsert_endpoint = |class_id: i32| {
+            format!(
+                "{}/{}/objects/by-external-id/upsert-1",
+                OBJECT_ENDPOINT, class_id
+            )
+        };
+        let payload = |name: &str| UpsertHubuumObject {
+            name: name.to_string(),
+            namespace_id: namespace.id,
+            data: serde_json::json!({"version": name}),
+            description: "upserted object".to_string(),
+        };
+
+        // The normal user may neither create nor update
+        let resp = put_request(
+            &pool,
+            &normal_token,
+            &upsert_endpoint(class.id),
+            payload("first"),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = put_request(
+            &pool,
+            &admin_token,
+            &upsert_endpoint(class.id),
+            payload("first"),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let headers = resp.headers().clone();
+        let created: HubuumObject = test::read_body_json(resp).await;
+        assert_eq!(created.name, "first");
+        assert_eq!(created.external_id, Some("upsert-1".to_string()));
+        assert_eq!(created.hubuum_class_id, class.id);
+        assert_eq!(
+            headers.get("Location").unwrap().to_str().unwrap(),
+            object_in_class_endpoint(class.id, created.id)
+        );
+
+        let resp = put_request(
+            &pool,
+            &admin_token,
+            &upsert_endpoint(class.id),
+            payload("second"),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let updated: HubuumObject = test::read_body_json(resp).await;
+        assert_eq!(updated.id, created.id);
+        assert_eq!(updated.name, "second");
+        assert_eq!(updated.data, serde_json::json!({"version": "second"}));
+
+        let resp = put_request(
+            &pool,
+            &normal_token,
+            &upsert_endpoint(class.id),
+            payload("third"),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        // The external ID is taken by an object in another class in the namespace
+        let resp = put_request(
+            &pool,
+            &admin_token,
+            &upsert_endpoint(classes[1].id),
+            payload("third"),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::CONFLICT).await;
+
+        // Those who may not update the object are not told about the other class.
+        let resp = put_request(
+            &pool,
+            &normal_token,
+            &upsert_endpoint(classes[1].id),
+            payload("third"),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        // Creating in a namespace that doesn't exist is refused as in POST on the class.
+        let resp = put_request(
+            &pool,
+            &admin_token,
+            &upsert_endpoint(class.id),
+            UpsertHubuumObject {
+                namespace_id: i32::MAX,
+                ..payload("missing")
+            },
+        )
+        .await;
+        assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+
+        namespace.delete(&pool).await.unwrap();
+        cleanup(&classes).await;
+    }
+
+    #[actix_rt::test]
+    async fn delete_object_if_unmodified_since() {
+        use actix_web::http::header::{HttpDate, IF_UNMODIFIED_SINCE};
+        use std::time::SystemTime;
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let http_date =
+            |at: chrono::NaiveDateTime| HttpDate::from(SystemTime::from(at.and_utc())).to_string();
+
+        let namespace = create_namespace(&pool, "delete_object_if_unmodified_since")
+            .await
+            .unwrap();
+        let classes = create_test_classes("delete_object_if_unmodified_since").await;
+        let class = &classes[0];
+
+        let object = NewHubuumObject {
+            namespace_id: namespace.id,
+            hubuum_class_id: class.id,
+            data: serde_json::json!({}),
+            name: "unmodified since object".to_string(),
+            description: "unmodified since object description".to_string(),
+            external_id: None,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+        let seen_at = http_date(object.updated_at);
+
+        // HTTP dates have a resolution of seconds, so make sure the update lands in a later one.
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &object_in_class_endpoint(class.id, object.id),
+            UpdateHubuumObject {
+                namespace_id: None,
+                hubuum_class_id: None,
+                data: None,
+                name: None,
+                description: Some("modified".to_string()),
+                external_id: None,
+            },
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let updated: HubuumObject = test::read_body_json(resp).await;
+
+        let resp = delete_request_with_headers(
+            &pool,
+            &admin_token,
+            &object_in_class_endpoint(class.id, object.id),
+            vec![(IF_UNMODIFIED_SINCE, &seen_at)],
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::PRECONDITION_FAILED).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "precondition_failed");
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &object_in_class_endpoint(class.id, object.id),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::OK).await;
+
+        let resp = delete_request_with_headers(
+            &pool,
+            &admin_token,
+            &object_in_class_endpoint(class.id, object.id),
+            vec![(IF_UNMODIFIED_SINCE, &http_date(updated.updated_at))],
+        )
+        .await;
+        assert_response_status(resp, StatusCode::NO_CONTENT).await;
+
+        // Classes honor the header too.
+        let resp = delete_request_with_headers(
+            &pool,
+            &admin_token,
+            &format!("{}/{}", OBJECT_ENDPOINT, class.id),
+            vec![(IF_UNMODIFIED_SINCE, "Sat, 01 Jan 2000 00:00:00 GMT")],
+        )
+        .await;
+        assert_response_status(resp, StatusCode::PRECONDITION_FAILED).await;
+
+        let resp = delete_request_with_headers(
+            &pool,
+            &admin_token,
+            &format!("{}/{}", OBJECT_ENDPOINT, class.id),
+            vec![(IF_UNMODIFIED_SINCE, &http_date(class.updated_at))],
+        )
+        .await;
+        assert_response_status(resp, StatusCode::NO_CONTENT).await;
+
+        cleanup(&classes).await;
+        namespace.delete(&pool).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn find_objects_by_data() {
+        // Serializes its fields in declaration order, ie with the keys in another order than
+        // the stored data.
+        #[derive(serde::Serialize)]
+        struct Document {
+            zone: &'static str,
+            address: serde_json::Value,
+        }
+
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+        let classes = create_test_classes("find_objects_by_data").await;
+        let class = &classes[0];
+
+        let mut objects = vec![];
+        for (name, zone) in [("first", "north"), ("second", "north"), ("third", "south")] {
+            let object = NewHubuumObject {
+                namespace_id: class.namespace_id,
+                hubuum_class_id: class.id,
+                data: serde_json::json!({"address": {"street": "Main", "number": 1}, "zone": zone}),
+                name: format!("find by data {}", name),
+                description: "find by data".to_string(),
+                external_id: None,
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+            objects.push(object);
+        }
+
+        let endpoint = format!("{}/{}/objects/find-by-data", OBJECT_ENDPOINT, class.id);
+        let document = Document {
+            zone: "north",
+            address: serde_json::json!({"number": 1, "street": "Main"}),
+        };
+
+        let resp = post_request(&pool, &admin_token, &endpoint, &document).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert_eq!(found, objects[..2].to_vec());
+
+        // A subset of the data is not a match.
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &endpoint,
+            serde_json::json!({"zone": "north"}),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert!(found.is_empty());
+
+        // Users only find the objects they may read.
+        let resp = post_request(&pool, &normal_token, &endpoint, &document).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert!(found.is_empty());
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &format!("{}/{}/objects/find-by-data", OBJECT_ENDPOINT, i32::MAX),
+            &document,
+        )
+        .await;
+        assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        cleanup(&classes).await;
+    }
+
+    #[actix_rt::test]
+    async fn diff_objects() {
+        use crate::utilities::json_diff::{JsonDiff, JsonDiffChange, JsonDiffEntry};
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        let namespace = create_namespace(&pool, "diff_objects").await.unwrap();
+        let other_namespace = create_namespace(&pool, "diff_objects_other").await.unwrap();
+        let classes = create_test_classes("diff_objects").await;
+        let class = &classes[0];
+
+        let create = |name: &str, namespace_id: i32, data: serde_json::Value| NewHubuumObject {
+            namespace_id,
+            hubuum_class_id: class.id,
+            data,
+            name: name.to_string(),
+            description: "diff object".to_string(),
+            external_id: None,
+        };
+        let before = create(
+            "diff before",
+            namespace.id,
+            serde_json::json!({"rack": 4, "room": {"floor": 1}, "owner": "ops"}),
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let after = create(
+            "diff after",
+            namespace.id,
+            serde_json::json!({"rack": 5, "room": {"floor": 1, "wing": "east"}}),
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let hidden = create("diff hidden", other_namespace.id, serde_json::json!({}))
+            .save(&pool)
+            .await
+            .unwrap();
+
+        let diff_endpoint = |from: i32, to: i32| format!("/api/v1/objects/{}/diff/{}", from, to);
+
+        let resp = get_request(&pool, &admin_token, &diff_endpoint(before.id, after.id)).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let diff: JsonDiff = test::read_body_json(resp).await;
+        assert_eq!(
+            diff,
+            JsonDiff {
+                added: vec![JsonDiffEntry {
+                    path: vec!["room".to_string(), "wing".to_string()],
+                    value: serde_json::json!("east"),
+                }],
+                removed: vec![JsonDiffEntry {
+                    path: vec!["owner".to_string()],
+                    value: serde_json::json!("ops"),
+                }],
+                changed: vec![JsonDiffChange {
+                    path: vec!["rack".to_string()],
+                    from: serde_json::json!(4),
+                    to: serde_json::json!(5),
+                }],
+            }
+        );
+
+        // A user who may read the objects in one namespace can't diff against the other.
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        namespace
+            .grant_one(&pool, group.id, Permissions::ReadObject)
+            .await
+            .unwrap();
+        NamespaceID(class.namespace_id)
+            .instance(&pool)
+            .await
+            .unwrap()
+            .grant_one(&pool, group.id, Permissions::ReadClass)
+            .await
+            .unwrap();
+        let token = user.create_token(&pool).await.unwrap().get_token();
+
+        let resp = get_request(&pool, &token, &diff_endpoint(after.id, before.id)).await;
+        assert_response_status(resp, StatusCode::OK).await;
+
+        let resp = get_request(&pool, &token, &diff_endpoint(before.id, hidden.id)).await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = get_request(&pool, &admin_token, &diff_endpoint(before.id, i32::MAX)).await;
+        assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        cleanup(&classes).await;
+        namespace.delete(&pool).await.unwrap();
+        other_namespace.delete(&pool).await.unwrap();
+        group.delete(&pool).await.unwrap();
+        user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn objects_with_expanded_relations() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        let namespace = create_namespace(&pool, "expanded_relations").await.unwrap();
+        let other_namespace = create_namespace(&pool, "expanded_relations_other")
+            .await
+            .unwrap();
+        let classes = create_test_classes("expanded_relations").await;
+
+        let class_relation = NewHubuumClassRelation {
+            from_hubuum_class_id: classes[0].id,
+            to_hubuum_class_id: classes[1].id,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let create = |name: &str, namespace_id: i32, class_id: i32| NewHubuumObject {
+            namespace_id,
+            hubuum_class_id: class_id,
+            data: serde_json::json!({}),
+            name: name.to_string(),
+            description: "expanded relations object".to_string(),
+            external_id: None,
+        };
+        let host = create("expand host", namespace.id, classes[0].id)
+            .save(&pool)
+            .await
+            .unwrap();
+        let room = create("expand room", namespace.id, classes[1].id)
+            .save(&pool)
+            .await
+            .unwrap();
+        let hidden = create("expand hidden", other_namespace.id, classes[1].id)
+            .save(&pool)
+            .await
+            .unwrap();
+        let lonely = create("expand lonely", namespace.id, classes[0].id)
+            .save(&pool)
+            .await
+            .unwrap();
+        for to in [&room, &hidden] {
+            NewHubuumObjectRelation {
+                from_hubuum_object_id: host.id,
+                to_hubuum_object_id: to.id,
+                class_relation_id: class_relation.id,
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+        }
+
+        let related = |objects: &serde_json::Value, object: &HubuumObject, key: &str| {
+            objects
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|o| o["id"] == object.id)
+                .unwrap()[key]
+                .clone()
+        };
+
+        let endpoint = format!(
+            "{}?expand=relations",
+            objects_in_class_endpoint(classes[0].id)
+        );
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            related(&objects, &host, "related_object_ids"),
+            serde_json::json!([room.id, hidden.id])
+        );
+        assert_eq!(
+            related(&objects, &lonely, "related_object_ids"),
+            serde_json::json!([])
+        );
+        assert_eq!(
+            related(&objects, &host, "related_object_names"),
+            serde_json::Value::Null
+        );
+        assert_eq!(
+            related(&objects, &host, "name"),
+            serde_json::json!(host.name)
+        );
+
+        // Relations are followed in both directions, and names are included on request.
+        let endpoint = format!(
+            "/api/v1/objects/?classes={}&expand=relation_names&shape=map",
+            classes[1].id
+        );
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects: serde_json::Value = test::read_body_json(resp).await;
+        let expanded_room = &objects[room.id.to_string()];
+        assert_eq!(
+            expanded_room["related_object_ids"],
+            serde_json::json!([host.id])
+        );
+        assert_eq!(
+            expanded_room["related_object_names"],
+            serde_json::json!([host.name])
+        );
+
+        // Related objects the user can't read are left out.
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        for permission in [Permissions::ReadCollection, Permissions::ReadObject] {
+            namespace
+                .grant_one(&pool, group.id, permission)
+                .await
+                .unwrap();
+        }
+        for class in &classes[..2] {
+            NamespaceID(class.namespace_id)
+                .instance(&pool)
+                .await
+                .unwrap()
+                .grant_one(&pool, group.id, Permissions::ReadClass)
+                .await
+                .unwrap();
+        }
+        let token = user.create_token(&pool).await.unwrap().get_token();
+
+        // Without ReadObjectRelation, the user sees the objects but not their relations.
+        let endpoint = format!(
+            "{}?expand=relation_names",
+            objects_in_class_endpoint(classes[0].id)
+        );
+        let resp = get_request(&pool, &token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            related(&objects, &host, "related_object_ids"),
+            serde_json::json!([])
+        );
+
+        namespace
+            .grant_one(&pool, group.id, Permissions::ReadObjectRelation)
+            .await
+            .unwrap();
+        let resp = get_request(&pool, &token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            related(&objects, &host, "related_object_ids"),
+            serde_json::json!([room.id])
+        );
+        assert_eq!(
+            related(&objects, &host, "related_object_names"),
+            serde_json::json!([room.name])
+        );
+
+        for query in ["expand=children", "expand__not_equals=relations"] {
+            let endpoint = format!("{}?{}", objects_in_class_endpoint(classes[0].id), query);
+            let resp = get_request(&pool, &admin_token, &endpoint).await;
+            assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        }
+
+        cleanup(&classes).await;
+        namespace.delete(&pool).await.unwrap();
+        other_namespace.delete(&pool).await.unwrap();
+        group.delete(&pool).await.unwrap();
+        user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn search_without_readable_namespaces() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        let classes = create_test_classes("search_without_readable_namespaces").await;
+        let user = create_test_user(&pool).await;
+        let token = user.create_token(&pool).await.unwrap().get_token();
+
+        for endpoint in [
+            "/api/v1/classes/",
+            "/api/v1/classes/?name__icontains=x&json_schema__equals=type=object",
+            "/api/v1/objects/",
+            "/api/v1/objects/?name__icontains=x&json_data__equals=key=value",
+        ] {
+            let resp = get_request(&pool, &token, endpoint).await;
+            let resp = assert_response_status(resp, StatusCode::OK).await;
+            let found: Vec<serde_json::Value> = test::read_body_json(resp).await;
+            assert!(found.is_empty(), "{} found {:?}", endpoint, found);
+        }
+
+        // Invalid queries are refused whether or not the user can read anything.
+        for endpoint in [
+            "/api/v1/classes/?name__regex=x",
+            "/api/v1/classes/?json_schema__equals=nokeyvalue",
+            "/api/v1/objects/?name__regex=x",
+            "/api/v1/objects/?json_data__equals=nokeyvalue",
+            "/api/v1/objects/?order_by=name",
+        ] {
+            for token in [&token, &admin_token] {
+                let resp = get_request(&pool, token, endpoint).await;
+                assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+            }
+        }
+
+        cleanup(&classes).await;
+        user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn skip_validation_requires_admin() {
+        use crate::schema::validation_audit::dsl::{action, object_id, validation_audit};
+        use diesel::prelude::*;
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        let namespace = create_namespace(&pool, "skip_validation_requires_admin")
+            .await
+            .unwrap();
+        let class = NewHubuumClass {
+            name: "skip_validation_requires_admin".to_string(),
+            namespace_id: namespace.id,
+            json_schema: Some(get_schema(SchemaType::Geo).clone()),
+            validate_schema: Some(true),
+            description: "Validating class".to_string(),
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        for permission in [Permissions::CreateObject, Permissions::UpdateObject] {
+            namespace
+                .grant_one(&pool, group.id, permission)
+                .await
+                .unwrap();
+        }
+        let user_token = user.create_token(&pool).await.unwrap().get_token();
+
+        let upsert_endpoint = |query: &str| {
+            format!(
+                "{}/{}/objects/by-external-id/skip-validation{}",
+                OBJECT_ENDPOINT, class.id, query
+            )
+        };
+        // The geo schema requires latitude and longitude.
+        let payload = UpsertHubuumObject {
+            name: "skip validation object".to_string(),
+            namespace_id: namespace.id,
+            data: serde_json::json!({"test": "data"}),
+            description: "invalid object".to_string(),
+        };
+
+        for token in [&user_token, &admin_token] {
+            let resp = put_request(&pool, token, &upsert_endpoint(""), payload.clone()).await;
+            let resp = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(body["code"], "schema_validation_failed");
+        }
+
+        let resp = put_request(
+            &pool,
+            &user_token,
+            &upsert_endpoint("?skip_validation=true"),
+            payload.clone(),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = put_request(
+            &pool,
+            &admin_token,
+            &upsert_endpoint("?skip_validation=true"),
+            payload.clone(),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let created: HubuumObject = test::read_body_json(resp).await;
+        assert_eq!(created.data, serde_json::json!({"test": "data"}));
+
+        let update = |name: &str, data: Option<serde_json::Value>| UpdateHubuumObject {
+            namespace_id: None,
+            hubuum_class_id: None,
+            data,
+            name: Some(name.to_string()),
+            description: None,
+            external_id: None,
+        };
+        let patch_endpoint = object_in_class_endpoint(class.id, created.id);
+        let patch_skip_endpoint = format!("{}?skip_validation=true", patch_endpoint);
+
+        // Data written with PATCH is validated, even if the object stays in its class.
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &patch_endpoint,
+            update("invalid", Some(serde_json::json!({"still": "invalid"}))),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "schema_validation_failed");
+
+        let resp = patch_request(
+            &pool,
+            &user_token,
+            &patch_skip_endpoint,
+            update("renamed", None),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        // Nothing is validated when the data is left alone, so nothing is skipped either.
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &patch_skip_endpoint,
+            update("renamed", None),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::OK).await;
+
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &patch_skip_endpoint,
+            update("renamed", Some(serde_json::json!({"still": "invalid"}))),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::OK).await;
+
+        // Only the writes by the admin that skipped validation are in the audit trail.
+        let mut conn = pool.get().unwrap();
+        let actions = validation_audit
+            .filter(object_id.eq(created.id))
+            .select(action)
+            .order_by(crate::schema::validation_audit::dsl::id)
+            .load::<String>(&mut conn)
+            .unwrap();
+        assert_eq!(actions, vec!["upsert", "update"]);
+
+        // Creating objects, alone or in batches, validates the data as well.
+        let new_object = |name: &str| NewHubuumObject {
+            name: format!("skip validation {}", name),
+            namespace_id: namespace.id,
+            hubuum_class_id: class.id,
+            data: serde_json::json!({"test": "data"}),
+            description: "invalid object".to_string(),
+            external_id: None,
+        };
+        let create_endpoint = objects_in_class_endpoint(class.id);
+        let batch_endpoint = format!("{}/{}/batch", OBJECT_ENDPOINT, class.id);
+
+        let resp = post_request(&pool, &admin_token, &create_endpoint, new_object("single")).await;
+        let resp = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "schema_validation_failed");
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &batch_endpoint,
+            vec![new_object("batch")],
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "schema_validation_failed");
+
+        let resp = post_request(
+            &pool,
+            &user_token,
+            &format!("{}?skip_validation=true", create_endpoint),
+            new_object("single"),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &format!("{}?skip_validation=true", create_endpoint),
+            new_object("single"),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let single: HubuumObject = test::read_body_json(resp).await;
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &format!("{}?skip_validation=true&atomic=false", batch_endpoint),
+            vec![new_object("batch")],
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::MULTI_STATUS).await;
+        let results: Vec<crate::models::BatchItemResult> = test::read_body_json(resp).await;
+        assert_eq!(results[0].status, 201);
+
+        for id in [single.id, results[0].id.unwrap()] {
+            let actions = validation_audit
+                .filter(object_id.eq(id))
+                .select(action)
+                .load::<String>(&mut conn)
+                .unwrap();
+            assert_eq!(actions, vec!["create"]);
+        }
+
+        namespace.delete(&pool).await.unwrap();
+        group.delete(&pool).await.unwrap();
+        user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn invalid_writes_are_not_rate_limited() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+
+        let namespace = create_namespace(&pool, "invalid_writes_are_not_rate_limited")
+            .await
+            .unwrap();
+        let class = NewHubuumClass {
+            name: "invalid_writes_are_not_rate_limited".to_string(),
+            namespace_id: namespace.id,
+            json_schema: Some(get_schema(SchemaType::Geo).clone()),
+            validate_schema: Some(true),
+            description: "Validating class".to_string(),
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+        let object = NewHubuumObject {
+            name: "invalid writes object".to_string(),
+            namespace_id: namespace.id,
+            hubuum_class_id: class.id,
+            data: serde_json::json!({"latitude": 60.0, "longitude": 10.0}),
+            description: "valid object".to_string(),
+            external_id: None,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &format!("/api/v1/namespaces/{}", namespace.id),
+            &serde_json::json!({"write_rate_limit": 1}),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::ACCEPTED).await;
+
+        let new_object = |name: &str, data: serde_json::Value| NewHubuumObject {
+            name: format!("invalid writes {}", name),
+            namespace_id: namespace.id,
+            hubuum_class_id: class.id,
+            data,
+            description: "new object".to_string(),
+            external_id: None,
+        };
+        let invalid = serde_json::json!({"test": "data"});
+        let valid = serde_json::json!({"latitude": 59.0, "longitude": 11.0});
+        let create_endpoint = objects_in_class_endpoint(class.id);
+
+        // Writes that fail validation are rejected before they are counted.
+        for _ in 0..3 {
+            let resp = post_request(
+                &pool,
+                &admin_token,
+                &create_endpoint,
+                new_object("invalid", invalid.clone()),
+            )
+            .await;
+            assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        }
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &create_endpoint,
+            new_object("first", valid.clone()),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::CREATED).await;
+
+        // With the limit used up, invalid writes still get the validation error.
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &object_in_class_endpoint(class.id, object.id),
+            UpdateHubuumObject {
+                namespace_id: None,
+                hubuum_class_id: None,
+                data: Some(invalid.clone()),
+                name: None,
+                description: None,
+                external_id: None,
+            },
+        )
+        .await;
+        assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        let resp = put_request(
+            &pool,
+            &admin_token,
+            &format!(
+                "{}/{}/objects/by-external-id/invalid-writes",
+                OBJECT_ENDPOINT, class.id
+            ),
+            UpsertHubuumObject {
+                name: "invalid writes upsert".to_string(),
+                namespace_id: namespace.id,
+                data: invalid.clone(),
+                description: "invalid object".to_string(),
+            },
+        )
+        .await;
+        assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &create_endpoint,
+            new_object("second", valid.clone()),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::TOO_MANY_REQUESTS).await;
+
+        namespace.delete(&pool).await.unwrap();
+    }
+
+    // [query, expected object indexes]
+    #[parameterized(
+        bigint_gt = { "json_data__gt=count=5000000000", vec![2, 3] },
+        bigint_lt = { "json_data__lt=count=5000000000", vec![0, 1, 4] },
+        bigint_equals = { "json_data__equals=count=6000000000", vec![2] },
+        numeric_equals = { "json_data__equals=count=123456789012345678901234567890", vec![3] },
+        numeric_gte = { "json_data__gte=count=123456789012345678901234567890", vec![3] },
+        decimal_lt = { "json_data__lt=count=0.5", vec![4] },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn get_objects_in_class_with_large_numbers(query: &str, expected: Vec<usize>) {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let literal = format!("large_numbers_{}", query.replace(['=', '.'], "_"));
+
+        let namespace = create_namespace(&pool, &literal).await.unwrap();
+        let classes = create_test_classes(&literal).await;
+        let class = &classes[0];
+
+        let counts = [
+            serde_json::json!(1),
+            serde_json::json!(3000000000_i64),
+            serde_json::json!(6000000000_i64),
+            serde_json::json!(0), // Set below, serde_json can't represent it exactly
+            serde_json::json!(0.25),
+        ];
+
+        let mut objects = vec![];
+        for (i, count) in counts.iter().enumerate() {
+            let object = NewHubuumObject {
+                namespace_id: namespace.id,
+                hubuum_class_id: class.id,
+                data: serde_json::json!({ "count": count }),
+                name: format!("large number object {}", i),
+                description: "large number object".to_string(),
+                external_id: None,
+            };
+            objects.push(object.save(&pool).await.unwrap());
+        }
+
+        {
+            use diesel::prelude::*;
+            let mut conn = pool.get().unwrap();
+            diesel::sql_query(
+                "UPDATE hubuumobject SET data = '{\"count\": 123456789012345678901234567890}' WHERE id = $1",
+            )
+            .bind::<diesel::sql_types::Integer, _>(objects[3].id)
+            .execute(&mut conn)
+            .unwrap();
+        }
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!("{}?{}", objects_in_class_endpoint(class.id), query),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects_from_api: Vec<HubuumObject> = test::read_body_json(resp).await;
+
+        let mut ids = objects_from_api.iter().map(|o| o.id).collect::<Vec<_>>();
+        ids.sort();
+        let expected_ids = expected.iter().map(|i| objects[*i].id).collect::<Vec<_>>();
+        assert_eq!(ids, expected_ids, "Failed for query {}", query);
+
+        namespace.delete(&pool).await.unwrap();
+        cleanup(&classes).await;
+    }
+
+    #[parameterized(
+        atomic = { true },
+        non_atomic = { false },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn test_create_objects_in_class_batch(atomic: bool) {
+        use crate::models::BatchItemResult;
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let classes = create_test_classes(&format!("objects_batch_{}", atomic)).await;
+        let class = &classes[0];
+
+        let object = |name: &str, class_id: i32| NewHubuumObject {
+            name: format!("batch_{}_{}", atomic, name),
+            namespace_id: class.namespace_id,
+            hubuum_class_id: class_id,
+            data: serde_json::json!({}),
+            description: "Batch object".to_string(),
+            external_id: None,
+        };
+        let endpoint = format!("{}/{}/batch", OBJECT_ENDPOINT, class.id);
+
+        let valid = vec![object("one", class.id), object("two", class.id)];
+        let resp = post_request(&pool, &admin_token, &endpoint, &valid).await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let created: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert_eq!(created.len(), 2);
+
+        // The second element is in the wrong class.
+        let mixed = vec![
+            object("three", class.id),
+            object("four", classes[1].id),
+            object("five", class.id),
+        ];
+        let url = format!("{}?atomic={}", endpoint, atomic);
+        let resp = post_request(&pool, &admin_token, &url, &mixed).await;
+
+        if atomic {
+            let _ = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        } else {
+            let resp = assert_response_status(resp, StatusCode::MULTI_STATUS).await;
+            let results: Vec<BatchItemResult> = test::read_body_json(resp).await;
+            let statuses = results.iter().map(|r| r.status).collect::<Vec<_>>();
+            assert_eq!(statuses, vec![201, 400, 201]);
+            assert_eq!(results[1].index, 1);
+            assert!(results[1].id.is_none());
+            assert_eq!(results[1].error.as_ref().unwrap()["code"], "bad_request");
+        }
+
+        let resp = get_request(&pool, &admin_token, &objects_in_class_endpoint(class.id)).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects: Vec<HubuumObject> = test::read_body_json(resp).await;
+        let expected = if atomic { 2 } else { 4 };
+        assert_eq!(objects.len(), expected);
+
+        cleanup(&classes).await;
+    }
+
+    #[parameterized(
+        atomic = { true },
+        non_atomic = { false },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn test_update_and_delete_objects_in_class_batch(atomic: bool) {
+        use crate::errors::ApiError;
+        use crate::models::{BatchItemResult, HubuumObjectID};
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let classes = create_test_classes(&format!("objects_batch_update_{}", atomic)).await;
+        let class = &classes[0];
+
+        let mut objects = vec![];
+        for (i, class_id) in [class.id, class.id, classes[1].id].into_iter().enumerate() {
+            let object = NewHubuumObject {
+                name: format!("batch_update_{}_{}", atomic, i),
+                namespace_id: class.namespace_id,
+                hubuum_class_id: class_id,
+                data: serde_json::json!({}),
+                description: "Batch object".to_string(),
+                external_id: None,
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+            objects.push(object);
+        }
+        let endpoint = format!("{}/{}/batch", OBJECT_ENDPOINT, class.id);
+        let url = format!("{}?atomic={}", endpoint, atomic);
+
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &endpoint,
+            serde_json::json!([
+                {"id": objects[0].id, "description": "Updated"},
+                {"id": objects[1].id, "description": "Updated", "external_id": "batch"},
+            ]),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let updated: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert_eq!(updated.len(), 2);
+        assert!(updated.iter().all(|o| o.description == "Updated"));
+        assert_eq!(updated[1].external_id.as_deref(), Some("batch"));
+
+        // The last element is an object of another class.
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &url,
+            serde_json::json!([
+                {"id": objects[0].id, "description": "Again"},
+                {"id": objects[2].id, "description": "Again"},
+            ]),
+        )
+        .await;
+        if atomic {
+            assert_response_status(resp, StatusCode::NOT_FOUND).await;
+        } else {
+            let resp = assert_response_status(resp, StatusCode::MULTI_STATUS).await;
+            let results: Vec<BatchItemResult> = test::read_body_json(resp).await;
+            let statuses = results.iter().map(|r| r.status).collect::<Vec<_>>();
+            assert_eq!(statuses, vec![200, 404]);
+            assert_eq!(results[0].id, Some(objects[0].id));
+        }
+        let object = HubuumObjectID(objects[0].id).instance(&pool).await.unwrap();
+        let expected = if atomic { "Updated" } else { "Again" };
+        assert_eq!(object.description, expected);
+
+        let ids = vec![objects[0].id, objects[2].id, objects[1].id];
+        let resp = delete_request_with_json(&pool, &admin_token, &url, &ids).await;
+        if atomic {
+            assert_response_status(resp, StatusCode::NOT_FOUND).await;
+        } else {
+            let resp = assert_response_status(resp, StatusCode::MULTI_STATUS).await;
+            let results: Vec<BatchItemResult> = test::read_body_json(resp).await;
+            let statuses = results.iter().map(|r| r.status).collect::<Vec<_>>();
+            assert_eq!(statuses, vec![204, 404, 204]);
+        }
+
+        let resp = get_request(&pool, &admin_token, &objects_in_class_endpoint(class.id)).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let remaining: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert_eq!(remaining.len(), if atomic { 2 } else { 0 });
+
+        if atomic {
+            let ids = vec![objects[0].id, objects[1].id];
+            let resp = delete_request_with_json(&pool, &admin_token, &endpoint, &ids).await;
+            assert_response_status(resp, StatusCode::NO_CONTENT).await;
+            let deleted = HubuumObjectID(objects[1].id).instance(&pool).await;
+            assert!(matches!(deleted, Err(ApiError::NotFound(_))));
+        }
+
+        cleanup(&classes).await;
+    }
+
+    #[actix_web::test]
+    async fn test_create_objects_in_class_batch_write_rate_limit() {
+        use crate::models::BatchItemResult;
+
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let classes = create_test_classes("objects_batch_write_rate_limit").await;
+        let class = &classes[0];
+
+        let resp = patch_request(
+            &pool,
+            &admin_token,
+            &format!("/api/v1/namespaces/{}", class.namespace_id),
+            &serde_json::json!({"write_rate_limit": 3}),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::ACCEPTED).await;
+
+        let objects = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| NewHubuumObject {
+                    name: format!("batch_write_rate_limit_{}", name),
+                    namespace_id: class.namespace_id,
+                    hubuum_class_id: class.id,
+                    data: serde_json::json!({}),
+                    description: "Batch object".to_string(),
+                    external_id: None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let endpoint = format!("{}/{}/batch", OBJECT_ENDPOINT, class.id);
+
+        // Every object is a write, so a batch larger than the limit is refused as a whole.
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &endpoint,
+            &objects(&["a", "b", "c", "d"]),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::TOO_MANY_REQUESTS).await;
+
+        let resp = post_request(&pool, &admin_token, &endpoint, &objects(&["a", "b"])).await;
+        assert_response_status(resp, StatusCode::CREATED).await;
+
+        // Without atomic, the objects are counted one by one until the limit is reached.
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &format!("{}?atomic=false", endpoint),
+            &objects(&["c", "d"]),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::MULTI_STATUS).await;
+        let results: Vec<BatchItemResult> = test::read_body_json(resp).await;
+        let statuses = results.iter().map(|r| r.status).collect::<Vec<_>>();
+        assert_eq!(statuses, vec![201, 429]);
+
+        let resp = get_request(&pool, &admin_token, &objects_in_class_endpoint(class.id)).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let created: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert_eq!(created.len(), 3);
+
+        cleanup(&classes).await;
+    }
+
+    #[actix_web::test]
+    async fn test_list_objects_with_diagnostics() {
+        use crate::models::NewNamespaceWithAssignee;
+
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+        let readable_ns = create_namespace(&pool, "list_objects_diagnostics_readable")
+            .await
+            .unwrap();
+        let other_group = create_test_group(&pool).await;
+        let hidden_ns = NewNamespaceWithAssignee {
+            name: "list_objects_diagnostics_hidden".to_string(),
+            description: "Not readable by admins".to_string(),
+            group_id: other_group.id,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let mut classes = vec![];
+        let mut objects = vec![];
+        for namespace in [&readable_ns, &hidden_ns] {
+            let class = NewHubuumClass {
+                name: format!("list_objects_diagnostics_{}", namespace.id),
+                namespace_id: namespace.id,
+                json_schema: None,
+                validate_schema: Some(false),
+                description: "Diagnostics class".to_string(),
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+            let object = NewHubuumObject {
+                namespace_id: namespace.id,
+                hubuum_class_id: class.id,
+                data: serde_json::json!({}),
+                name: format!("list_objects_diagnostics_{}", namespace.id),
+                description: "Diagnostics object".to_string(),
+                external_id: None,
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+            classes.push(class);
+            objects.push(object);
+        }
+
+        let url = format!(
+            "/api/v1/objects/?classes={},{}",
+            classes[0].id, classes[1].id
+        );
+
+        let resp = get_request(&pool, &admin_token, &url).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert_eq!(found, vec![objects[0].clone()]);
+
+        let url = format!("{}&diagnostics=true", url);
+        let resp = get_request(&pool, &normal_token, &url).await;
+        let _ = assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = get_request(&pool, &admin_token, &url).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let found: Vec<HubuumObject> = serde_json::from_value(body["objects"].clone()).unwrap();
+        assert_eq!(found, vec![objects[0].clone()]);
+        assert_eq!(
+            body["diagnostics"]["unreadable_classes"],
+            serde_json::json!([classes[1].id])
+        );
+
+        readable_ns.delete(&pool).await.unwrap();
+        hidden_ns.delete(&pool).await.unwrap();
+        other_group.delete(&pool).await.unwrap();
+    }
+
+    // [query, expected object indexes]
+    #[parameterized(
+        literal_dot = { "json_data__equals=a.b=literal", vec![0] },
+        nested = { "json_data__equals=a,b=nested", vec![1] },
+        literal_dot_is_not_nested = { "json_data__equals=a.b=nested", vec![] },
+        deeply_nested = { "json_data__equals=x,y.z,w,v=deep", vec![2] },
+        escaped_comma = { "json_data__equals=k%5C,1=comma", vec![3] },
+        escaped_equals = { "json_data__equals=k%5C=2=equals", vec![3] },
+        spaces = { "json_data__equals=first%20name=ada", vec![3] },
+        null_key = { "json_data__equals=NULL=key", vec![4] },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn get_objects_in_class_with_json_path_escapes(query: &str, expected: Vec<usize>) {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let literal = format!(
+            "json_path_{}",
+            query
+                .bytes()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        );
+
+        let namespace = create_namespace(&pool, &literal).await.unwrap();
+        let classes = create_test_classes(&literal).await;
+        let class = &classes[0];
+
+        let data = [
+            serde_json::json!({ "a.b": "literal" }),
+            serde_json::json!({ "a": { "b": "nested" } }),
+            serde_json::json!({ "x": { "y.z": { "w": { "v": "deep" } } } }),
+            serde_json::json!({ "k,1": "comma", "k=2": "equals", "first name": "ada" }),
+            serde_json::json!({ "NULL": "key" }),
+        ];
+
+        let mut objects = vec![];
+        for (i, data) in data.iter().enumerate() {
+            let object = NewHubuumObject {
+                namespace_id: namespace.id,
+                hubuum_class_id: class.id,
+                data: data.clone(),
+                name: format!("json path object {}", i),
+                description: "json path object".to_string(),
+                external_id: None,
+            };
+            objects.push(object.save(&pool).await.unwrap());
+        }
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!("{}?{}", objects_in_class_endpoint(class.id), query),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects_from_api: Vec<HubuumObject> = test::read_body_json(resp).await;
+
+        let mut ids = objects_from_api.iter().map(|o| o.id).collect::<Vec<_>>();
+        ids.sort();
+        let expected_ids = expected.iter().map(|i| objects[*i].id).collect::<Vec<_>>();
+        assert_eq!(ids, expected_ids, "Failed for query {}", query);
+
+        namespace.delete(&pool).await.unwrap();
+        cleanup(&classes).await;
+    }
+
+    // [query, expected object indexes]
+    #[parameterized(
+        json_null = { "json_data__isnull=owner", vec![0] },
+        not_json_null = { "json_data__not_isnull=owner", vec![2, 3] },
+        string_null = { "json_data__equals=owner=null", vec![2] },
+        not_string_null = { "json_data__not_equals=owner=null", vec![3] },
+        contains_null = { "json_data__contains=owner=null", vec![2] },
+        string_value = { "json_data__equals=owner=alice", vec![3] },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn get_objects_in_class_with_json_null(query: &str, expected: Vec<usize>) {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let literal = format!("json_null_{}", query.replace('=', "_"));
+
+        let namespace = create_namespace(&pool, &literal).await.unwrap();
+        let classes = create_test_classes(&literal).await;
+        let class = &classes[0];
+
+        // A JSON null, a missing key, the string "null", and a regular string.
+        let data = [
+            serde_json::json!({ "owner": null }),
+            serde_json::json!({}),
+            serde_json::json!({ "owner": "null" }),
+            serde_json::json!({ "owner": "alice" }),
+        ];
+
+        let mut objects = vec![];
+        for (i, data) in data.iter().enumerate() {
+            let object = NewHubuumObject {
+                namespace_id: namespace.id,
+                hubuum_class_id: class.id,
+                data: data.clone(),
+                name: format!("json null object {}", i),
+                description: "json null object".to_string(),
+                external_id: None,
+            };
+            objects.push(object.save(&pool).await.unwrap());
+        }
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!("{}?{}", objects_in_class_endpoint(class.id), query),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects_from_api: Vec<HubuumObject> = test::read_body_json(resp).await;
+
+        let mut ids = objects_from_api.iter().map(|o| o.id).collect::<Vec<_>>();
+        ids.sort();
+        let expected_ids = expected.iter().map(|i| objects[*i].id).collect::<Vec<_>>();
+        assert_eq!(ids, expected_ids, "Failed for query {}", query);
+
+        namespace.delete(&pool).await.unwrap();
+        cleanup(&classes).await;
+    }
+
+    #[parameterized(
+        matches = { "namespace_matches_class=true", vec![0] },
+        mismatches = { "namespace_matches_class=false", vec![1] },
+        negated = { "namespace_matches_class__not_equals=true", vec![1] },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn get_objects_by_namespace_matching_class(query: &str, expected: Vec<usize>) {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let literal = format!("ns_matches_class_{}", query.replace(['=', '_'], ""));
+
+        let namespace = create_namespace(&pool, &literal).await.unwrap();
+        let classes = create_test_classes(&literal).await;
+        let class = &classes[0];
+
+        let mut objects = vec![];
+        for (i, namespace_id) in [class.namespace_id, namespace.id].iter().enumerate() {
+            let object = NewHubuumObject {
+                namespace_id: *namespace_id,
+                hubuum_class_id: class.id,
+                data: serde_json::json!({}),
+                name: format!("namespace matching object {}", i),
+                description: "namespace matching object".to_string(),
+                external_id: None,
+            };
+            objects.push(object.save(&pool).await.unwrap());
+        }
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!("{}?{}", objects_in_class_endpoint(class.id), query),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects_from_api: Vec<HubuumObject> = test::read_body_json(resp).await;
+
+        let ids = objects_from_api.iter().map(|o| o.id).collect::<Vec<_>>();
+        let expected_ids = expected.iter().map(|i| objects[*i].id).collect::<Vec<_>>();
+        assert_eq!(ids, expected_ids, "Failed for query {}", query);
+
+        namespace.delete(&pool).await.unwrap();
+        cleanup(&classes).await;
+    }
+
+    #[parameterized(
+        related = { "has_relation=true", vec![0, 1] },
+        isolated = { "has_relation=false", vec![2] },
+        negated = { "has_relation__not_equals=true", vec![2] },
+        combined = { "has_relation=true&name__endswith=1", vec![1] },
+    )]
+    #[test_macro(actix_web::test)]
+    async fn get_objects_by_has_relation(query: &str, expected: Vec<usize>) {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let literal = format!("has_relation_{}", query.replace(['=', '_', '&'], ""));
+
+        let classes = create_test_classes(&literal).await;
+        let class_relation = NewHubuumClassRelation {
+            from_hubuum_class_id: classes[0].id,
+            to_hubuum_class_id: classes[1].id,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let mut objects = vec![];
+        for (i, class) in [&classes[0], &classes[1], &classes[0]].iter().enumerate() {
+            let object = NewHubuumObject {
+                namespace_id: class.namespace_id,
+                hubuum_class_id: class.id,
+                data: serde_json::json!({}),
+                name: format!("has relation object {}", i),
+                description: "has relation object".to_string(),
+                external_id: None,
+            };
+            objects.push(object.save(&pool).await.unwrap());
+        }
+        NewHubuumObjectRelation {
+            from_hubuum_object_id: objects[0].id,
+            to_hubuum_object_id: objects[1].id,
+            class_relation_id: class_relation.id,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!(
+                "/api/v1/objects/?classes={},{}&{}",
+                classes[0].id, classes[1].id, query
+            ),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let objects_from_api: Vec<HubuumObject> = test::read_body_json(resp).await;
+
+        let mut ids = objects_from_api.iter().map(|o| o.id).collect::<Vec<_>>();
+        ids.sort();
+        let expected_ids = expected.iter().map(|i| objects[*i].id).collect::<Vec<_>>();
+        assert_eq!(ids, expected_ids, "Failed for query {}", query);
+
+        cleanup(&classes).await;
+    }
+
+    #[actix_rt::test]
+    async fn has_relation_requires_read_object_relation() {
+        let (pool, _, _) = setup_pool_and_tokens().await;
+
+        let classes = create_test_classes("has_relation_requires_read_object_relation").await;
+        let class_relation = NewHubuumClassRelation {
+            from_hubuum_class_id: classes[0].id,
+            to_hubuum_class_id: classes[1].id,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let mut objects = vec![];
+        for (i, class) in classes[..2].iter().enumerate() {
+            let object = NewHubuumObject {
+                namespace_id: class.namespace_id,
+                hubuum_class_id: class.id,
+                data: serde_json::json!({}),
+                name: format!("has relation permission object {}", i),
+                description: "has relation permission object".to_string(),
+                external_id: None,
+            };
+            objects.push(object.save(&pool).await.unwrap());
+        }
+        NewHubuumObjectRelation {
+            from_hubuum_object_id: objects[0].id,
+            to_hubuum_object_id: objects[1].id,
+            class_relation_id: class_relation.id,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        let namespace = NamespaceID(classes[0].namespace_id)
+            .instance(&pool)
+            .await
+            .unwrap();
+        for permission in [
+            Permissions::ReadCollection,
+            Permissions::ReadObject,
+            Permissions::ReadClass,
+        ] {
+            namespace
+                .grant_one(&pool, group.id, permission)
+                .await
+                .unwrap();
+        }
+        let token = user.create_token(&pool).await.unwrap().get_token();
+
+        let search = |query: &'static str| {
+            let pool = pool.clone();
+            let token = token.clone();
+            let endpoint = format!(
+                "/api/v1/objects/?classes={},{}&{}",
+                classes[0].id, classes[1].id, query
+            );
+            async move {
+                let resp = get_request(&pool, &token, &endpoint).await;
+                let resp = assert_response_status(resp, StatusCode::OK).await;
+                let objects: Vec<HubuumObject> = test::read_body_json(resp).await;
+                let mut ids = objects.into_iter().map(|o| o.id).collect::<Vec<_>>();
+                ids.sort();
+                ids
+            }
+        };
+
+        // Without ReadObjectRelation, the relation doesn't count.
+        assert_eq!(search("has_relation=true").await, Vec::<i32>::new());
+        assert_eq!(
+            search("has_relation=false").await,
+            vec![objects[0].id, objects[1].id]
+        );
+
+        namespace
+            .grant_one(&pool, group.id, Permissions::ReadObjectRelation)
+            .await
+            .unwrap();
+        assert_eq!(
+            search("has_relation=true").await,
+            vec![objects[0].id, objects[1].id]
+        );
+        assert_eq!(search("has_relation=false").await, Vec::<i32>::new());
+
+        cleanup(&classes).await;
+        group.delete(&pool).await.unwrap();
+        user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn search_objects_with_filter_body() {
+        use crate::models::search::MAX_SEARCH_CONDITIONS;
+
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let classes = create_test_classes("search_objects_with_filter_body").await;
+        let class = &classes[0];
+
+        let mut objects = vec![];
+        for (i, name) in ["sowfb-web-1", "sowfb-web-2", "sowfb-db-1", "sowfb-db-2"]
+            .iter()
+            .enumerate()
+        {
+            objects.push(
+                NewHubuumObject {
+                    namespace_id: class.namespace_id,
+                    hubuum_class_id: class.id,
+                    data: serde_json::json!({"count": i}),
+                    name: name.to_string(),
+                    description: format!("search body object {}", i),
+                    external_id: None,
+                }
+                .save(&pool)
+                .await
+                .unwrap(),
+            );
+        }
+
+        let cases = [
+            // Repeating an operator on a field in an explicit AND is an AND, not an OR
+            (
+                serde_json::json!({"and": [
+                    {"field": "name", "operator": "like", "value": "sowfb-web-%"},
+                    {"field": "name", "operator": "like", "value": "%-1"},
+                ]}),
+                vec![0],
+            ),
+            (
+                serde_json::json!({"or": [
+                    {"field": "name", "operator": "like", "value": "sowfb-web-%"},
+                    {"field": "name", "operator": "like", "value": "%-1"},
+                ]}),
+                vec![0, 1, 2],
+            ),
+            (
+                serde_json::json!({"and": [
+                    {"field": "classes", "value": class.id},
+                    {"or": [
+                        {"field": "name", "value": "sowfb-db-2"},
+                        {"field": "json_data", "operator": "lt", "value": "count=1"},
+                    ]},
+                ]}),
+                vec![0, 3],
+            ),
+            (
+                serde_json::json!({"field": "description", "operator": "not_icontains", "value": "search body"}),
+                vec![],
+            ),
+            // An empty AND-group matches everything, also as an alternative
+            (
+                serde_json::json!({"and": [
+                    {"field": "classes", "value": class.id},
+                    {"or": [
+                        {"and": []},
+                        {"field": "name", "value": "sowfb-db-2"},
+                    ]},
+                ]}),
+                vec![0, 1, 2, 3],
+            ),
+            (
+                serde_json::json!({"and": [
+                    {"field": "permissions", "value": "ReadObject"},
+                    {"or": [
+                        {"field": "json_data", "operator": "gte", "value": "count=3"},
+                        {"and": [
+                            {"field": "name", "operator": "startswith", "value": "sowfb-web"},
+                            {"field": "json_data", "operator": "gt", "value": "count=0"},
+                        ]},
+                    ]},
+                ]}),
+                vec![1, 3],
+            ),
+        ];
+
+        for (body, expected) in cases {
+            let resp = post_request(&pool, &admin_token, "/api/v1/objects/search", &body).await;
+            let resp = assert_response_status(resp, StatusCode::OK).await;
+            let found: Vec<HubuumObject> = test::read_body_json(resp).await;
+
+            let ids = found
+                .iter()
+                .map(|o| o.id)
+                .filter(|id| objects.iter().any(|o| o.id == *id))
+                .collect::<Vec<_>>();
+            let expected = expected.iter().map(|i| objects[*i].id).collect::<Vec<_>>();
+            assert_eq!(ids, expected, "Failed for body {}", body);
+        }
+
+        // Users without access to the namespace find nothing
+        let body =
+            serde_json::json!({"field": "name", "operator": "startswith", "value": "sowfb-"});
+        let resp = post_request(&pool, &normal_token, "/api/v1/objects/search", &body).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert!(found.is_empty());
+
+        // Paging and ordering apply to the whole result, not to a condition
+        let too_many = (0..=MAX_SEARCH_CONDITIONS)
+            .map(|i| serde_json::json!({"field": "id", "value": i}))
+            .collect::<Vec<_>>();
+        for body in [
+            serde_json::json!({"field": "name"}),
+            serde_json::json!({"or": []}),
+            serde_json::json!({"field": "nope", "value": "x"}),
+            serde_json::json!({"and": [
+                {"field": "name", "operator": "startswith", "value": "sowfb-"},
+                {"field": "limit", "value": 1},
+            ]}),
+            serde_json::json!({"or": [{"field": "order_by", "value": "name"}]}),
+            serde_json::json!({"or": too_many}),
+        ] {
+            let resp = post_request(&pool, &admin_token, "/api/v1/objects/search", &body).await;
+            assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        }
+
+        cleanup(&classes).await;
+    }
+
+    // object_ns_permissions, class_ns_permissions, visible in search, status for direct get
+    #[parameterized(
+        all = { vec![Permissions::ReadCollection, Permissions::ReadObject], vec![Permissions::ReadClass], true, StatusCode::OK },
+        no_read_class = { vec![Permissions::ReadCollection, Permissions::ReadObject], vec![], false, StatusCode::FORBIDDEN },
+        read_class_in_object_ns = { vec![Permissions::ReadCollection, Permissions::ReadObject, Permissions::ReadClass], vec![], false, StatusCode::FORBIDDEN },
+        no_read_object = { vec![Permissions::ReadCollection], vec![Permissions::ReadClass], false, StatusCode::FORBIDDEN },
+        no_read_collection = { vec![Permissions::ReadObject], vec![Permissions::ReadClass], false, StatusCode::OK },
+    )]
+    #[test_macro(actix_rt::test)]
+    async fn object_visibility_requires_class_read(
+        object_ns_permissions: Vec<Permissions>,
+        class_ns_permissions: Vec<Permissions>,
+        visible_in_search: bool,
+        get_status: StatusCode,
+    ) {
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let suffix = format!("{:?}_{:?}", object_ns_permissions, class_ns_permissions);
+
+        let class_ns = create_namespace(&pool, &format!("ovrcr_class_{}", suffix))
+            .await
+            .unwrap();
+        let object_ns = create_namespace(&pool, &format!("ovrcr_object_{}", suffix))
+            .await
+            .unwrap();
+
+        let class = NewHubuumClass {
+            name: format!("ovrcr_class_{}", suffix),
+            namespace_id: class_ns.id,
+            json_schema: None,
+            validate_schema: Some(false),
+            description: "object visibility class".to_string(),
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let object = NewHubuumObject {
+            name: format!("ovrcr_object_{}", suffix),
+            namespace_id: object_ns.id,
+            hubuum_class_id: class.id,
+            data: serde_json::json!({}),
+            description: "object visibility object".to_string(),
+            external_id: None,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        for permission in object_ns_permissions {
+            object_ns
+                .grant_one(&pool, group.id, permission)
+                .await
+                .unwrap();
+        }
+        for permission in class_ns_permissions {
+            class_ns
+                .grant_one(&pool, group.id, permission)
+                .await
+                .unwrap();
+        }
+        let token = user.create_token(&pool).await.unwrap().get_token();
+
+        let resp = get_request(&pool, &token, &objects_in_class_endpoint(class.id)).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let listed: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert_eq!(listed.iter().any(|o| o.id == object.id), visible_in_search);
+
+        let body = serde_json::json!({"field": "id", "value": object.id});
+        let resp = post_request(&pool, &token, "/api/v1/objects/search", &body).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<HubuumObject> = test::read_body_json(resp).await;
+        assert_eq!(found.iter().any(|o| o.id == object.id), visible_in_search);
+
+        let resp = get_request(
+            &pool,
+            &token,
+            &object_in_class_endpoint(class.id, object.id),
+        )
+        .await;
+        assert_response_status(resp, get_status).await;
+
+        object_ns.delete(&pool).await.unwrap();
+        class_ns.delete(&pool).await.unwrap();
+        group.delete(&pool).await.unwrap();
+        user.delete(&pool).await.unwrap();
+    }
 }
@@ -4,20 +4,21 @@ mod tests {
     use yare::parameterized;
 
     use crate::models::{        
-        HubuumClass, HubuumClassRelation, HubuumClassRelationTransitive, HubuumObject, HubuumObjectRelation, HubuumObjectWithPath, NamespaceID, NewHubuumClassRelation, NewHubuumClassRelationFromClass, NewHubuumObject, NewHubuumObjectRelation, Permissions
+        AnyRelation, HubuumClass, HubuumClassRelation, HubuumClassRelationPath, HubuumClassRelationTransitive, HubuumObject, HubuumObjectRelation, HubuumObjectRelationWithDirection, HubuumObjectWithPath, NamespaceID, NewHubuumClassRelation, NewHubuumClassRelationFromClass, NewHubuumObject, NewHubuumObjectRelation, Permissions
     };
     use crate::traits::{CanSave, PermissionController, SelfAccessors};
     use crate::{assert_contains_all, assert_contains_same_ids};
 
     use crate::tests::api_operations::{delete_request, get_request, post_request};
     use crate::tests::asserts::assert_response_status;
-    use crate::tests::{create_test_group, ensure_normal_user, setup_pool_and_tokens};
+    use crate::tests::{create_test_group, create_test_user, ensure_normal_user, setup_pool_and_tokens};
     // use crate::{assert_contains_all, assert_contains_same_ids};
 
     use crate::tests::api::v1::classes::tests::{cleanup, create_test_classes};
 
     const CLASS_RELATIONS_ENDPOINT: &str = "/api/v1/relations/classes";
     const OBJECT_RELATIONS_ENDPOINT: &str = "/api/v1/relations/objects";
+    const RELATIONS_SEARCH_ENDPOINT: &str = "/api/v1/relations/search";
 
     fn relation_endpoint(relation_id: i32) -> String {
         format!("{}/{}", CLASS_RELATIONS_ENDPOINT, relation_id)
@@ -80,6 +81,7 @@ mod tests {
                 name: format!("object_in_{}", class.name),
                 description: format!("Object in class {}", class.description),
                 data: serde_json::json!({}),
+                external_id: None,
             };
 
             objects.push(object.save(pool).await.unwrap());
@@ -161,6 +163,81 @@ mod tests {
         cleanup(&classes).await;
     }
 
+    #[actix_web::test]
+    async fn test_get_class_relation_paths() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+        let (classes, _) = create_classes_and_relations(&pool, "get_class_relation_paths").await;
+        let unrelated = create_test_classes("get_class_relation_paths_unrelated").await;
+        // A shortcut from 0 to 2, so 0 reaches 3 both through 1 and directly through 2.
+        create_relation(&pool, &classes[0], &classes[2]).await;
+
+        let paths_endpoint = |from: &HubuumClass, to: &HubuumClass, query: &str| {
+            format!(
+                "/api/v1/classes/{}/relations/path-to/{}{}",
+                from.id, to.id, query
+            )
+        };
+        let ids = |indices: &[usize]| indices.iter().map(|i| classes[*i].id).collect::<Vec<_>>();
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &paths_endpoint(&classes[0], &classes[3], ""),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let paths: Vec<HubuumClassRelationPath> = test::read_body_json(resp).await;
+        assert_eq!(
+            paths,
+            vec![
+                HubuumClassRelationPath {
+                    depth: 2,
+                    path: ids(&[0, 2, 3])
+                },
+                HubuumClassRelationPath {
+                    depth: 3,
+                    path: ids(&[0, 1, 2, 3])
+                },
+            ]
+        );
+
+        // Asking the other way around gives the same paths, starting from the other end.
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &paths_endpoint(&classes[3], &classes[0], "?shortest=true"),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let paths: Vec<HubuumClassRelationPath> = test::read_body_json(resp).await;
+        assert_eq!(
+            paths,
+            vec![HubuumClassRelationPath {
+                depth: 2,
+                path: ids(&[3, 2, 0])
+            }]
+        );
+
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &paths_endpoint(&classes[0], &unrelated[0], ""),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        let resp = get_request(
+            &pool,
+            &normal_token,
+            &paths_endpoint(&classes[0], &classes[3], ""),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        cleanup(&classes).await;
+        cleanup(&unrelated).await;
+    }
+
     #[actix_web::test]
     async fn test_get_class_relation() {
         let (pool, admin_token, _) = setup_pool_and_tokens().await;
@@ -262,6 +339,129 @@ mod tests {
         cleanup(&classes).await;
     }
 
+    #[actix_web::test]
+    async fn test_relation_timestamps() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let classes = create_test_classes("relation_timestamps").await;
+        let objects = create_objects_in_classes(&pool, &classes[..2]).await;
+
+        fn timestamps(body: &serde_json::Value) -> (chrono::NaiveDateTime, chrono::NaiveDateTime) {
+            let parse = |field: &str| {
+                serde_json::from_value::<chrono::NaiveDateTime>(body[field].clone())
+                    .unwrap_or_else(|e| panic!("Invalid {} in {}: {}", field, body, e))
+            };
+            (parse("created_at"), parse("updated_at"))
+        }
+
+        let content = NewHubuumClassRelationFromClass {
+            to_hubuum_class_id: classes[1].id,
+        };
+        let endpoint = format!("/api/v1/classes/{}/relations/", classes[0].id);
+        let resp = post_request(&pool, &admin_token, &endpoint, &content).await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let created: serde_json::Value = test::read_body_json(resp).await;
+        let (created_at, updated_at) = timestamps(&created);
+        assert_eq!(created_at, updated_at);
+
+        let class_relation_id = created["id"].as_i64().unwrap() as i32;
+        let resp = get_request(&pool, &admin_token, &relation_endpoint(class_relation_id)).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let fetched: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(timestamps(&fetched), (created_at, updated_at));
+
+        let endpoint = format!(
+            "/api/v1/classes/{}/{}/relations/{}/{}",
+            classes[0].id, objects[0].id, classes[1].id, objects[1].id
+        );
+        let resp = post_request(&pool, &admin_token, &endpoint, serde_json::json!({})).await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let created: serde_json::Value = test::read_body_json(resp).await;
+        let (object_created_at, object_updated_at) = timestamps(&created);
+        assert_eq!(object_created_at, object_updated_at);
+        assert!(object_created_at >= created_at);
+
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let fetched: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(timestamps(&fetched), (object_created_at, object_updated_at));
+
+        cleanup(&classes).await;
+    }
+
+    #[actix_web::test]
+    async fn test_creating_class_relation_across_namespaces() {
+        use crate::models::NewHubuumClass;
+        use crate::tests::create_namespace;
+        use crate::traits::CanDelete;
+
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let user = create_test_user(&pool).await;
+        let token = user.create_token(&pool).await.unwrap().get_token();
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+
+        let mut namespaces = vec![];
+        let mut classes = vec![];
+        for suffix in ["a", "b"] {
+            let name = format!("class_relation_across_namespaces_{}", suffix);
+            let namespace = create_namespace(&pool, &name).await.unwrap();
+            let class = NewHubuumClass {
+                name: name.clone(),
+                description: name.clone(),
+                namespace_id: namespace.id,
+                json_schema: None,
+                validate_schema: Some(false),
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+            namespaces.push(namespace);
+            classes.push(class);
+        }
+
+        let from_class_endpoint = format!("/api/v1/classes/{}/relations/", classes[0].id);
+        let from_class_content = NewHubuumClassRelationFromClass {
+            to_hubuum_class_id: classes[1].id,
+        };
+        let global_content = NewHubuumClassRelation {
+            from_hubuum_class_id: classes[0].id,
+            to_hubuum_class_id: classes[1].id,
+        };
+
+        // Permission on only one of the namespaces is not enough, whichever side it is on.
+        for namespace in &namespaces {
+            namespace
+                .grant_one(&pool, group.id, Permissions::CreateClassRelation)
+                .await
+                .unwrap();
+
+            let resp = post_request(&pool, &token, &from_class_endpoint, &from_class_content).await;
+            assert_response_status(resp, StatusCode::FORBIDDEN).await;
+            let resp = post_request(&pool, &token, CLASS_RELATIONS_ENDPOINT, &global_content).await;
+            assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+            namespace
+                .revoke_one(&pool, group.id, Permissions::CreateClassRelation, None)
+                .await
+                .unwrap();
+        }
+
+        for namespace in &namespaces {
+            namespace
+                .grant_one(&pool, group.id, Permissions::CreateClassRelation)
+                .await
+                .unwrap();
+        }
+        let resp = post_request(&pool, &token, &from_class_endpoint, &from_class_content).await;
+        assert_response_status(resp, StatusCode::CREATED).await;
+
+        for namespace in namespaces {
+            namespace.delete(&pool).await.unwrap();
+        }
+        group.delete(&pool).await.unwrap();
+        user.delete(&pool).await.unwrap();
+    }
+
     #[actix_web::test]
     async fn test_get_class_relation_with_permissions() {
         let (pool, _, _) = setup_pool_and_tokens().await;
@@ -362,7 +562,9 @@ mod tests {
 
         if exists {
             let resp = assert_response_status(resp, StatusCode::OK).await;
-            let relation_response: HubuumObjectRelation = test::read_body_json(resp).await;
+            let relation_response: HubuumObjectRelationWithDirection = test::read_body_json(resp).await;
+            assert_eq!(relation_response.reversed, from_index > to_index);
+            let relation_response = relation_response.relation;
 
             assert_eq!(relation_response.id, relations[relation_index].id, "{}: Relation index: {} ({:?} in {:?})", endpoint, relation_index, relation_response, relations);
             if from_index > to_index {
@@ -449,4 +651,147 @@ mod tests {
         cleanup(&classes).await;
         
     }
+
+    #[actix_web::test]
+    async fn test_search_relations_by_created_at() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let (classes, relations) =
+            create_classes_and_relations(&pool, "search_relations_by_created_at").await;
+        let objects = create_objects_in_classes(&pool, &classes).await;
+        let object_relation =
+            create_object_relation(&pool, &objects[0], &objects[1], &relations[0]).await;
+
+        let format = |date: chrono::NaiveDateTime| date.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string();
+        let first = relations.iter().map(|r| r.created_at).min().unwrap();
+        let last = relations.iter().map(|r| r.created_at).max().unwrap();
+        let before = format(first - chrono::Duration::days(1));
+        let class_ids = classes.iter().map(|c| c.id.to_string()).collect::<Vec<_>>().join(",");
+
+        let endpoint = format!(
+            "{}?from_classes={}&created_at__gte={}&created_at__lte={}",
+            CLASS_RELATIONS_ENDPOINT,
+            class_ids,
+            format(first),
+            format(last)
+        );
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<HubuumClassRelation> = test::read_body_json(resp).await;
+        assert_contains_same_ids!(&relations, &found);
+
+        let endpoint = format!(
+            "{}?from_classes={}&created_at__lte={}",
+            CLASS_RELATIONS_ENDPOINT, class_ids, before
+        );
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<HubuumClassRelation> = test::read_body_json(resp).await;
+        assert!(found.is_empty());
+
+        let endpoint = format!(
+            "{}?from_objects={}&created_at__gte={}",
+            OBJECT_RELATIONS_ENDPOINT,
+            objects[0].id,
+            format(object_relation.created_at)
+        );
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<HubuumObjectRelation> = test::read_body_json(resp).await;
+        assert_eq!(found, vec![object_relation]);
+
+        let endpoint = format!(
+            "{}?from_objects={}&created_at__lte={}",
+            OBJECT_RELATIONS_ENDPOINT, objects[0].id, before
+        );
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<HubuumObjectRelation> = test::read_body_json(resp).await;
+        assert!(found.is_empty());
+
+        let endpoint = format!(
+            "{}?id={},{}&created_at__gte={}",
+            RELATIONS_SEARCH_ENDPOINT,
+            relations[0].id,
+            object_relation.id,
+            before
+        );
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<AnyRelation> = test::read_body_json(resp).await;
+        assert!(found.contains(&AnyRelation::Class(relations[0])));
+        assert!(found.contains(&AnyRelation::Object(object_relation)));
+
+        let endpoint = format!(
+            "{}?id={},{}&created_at__lte={}",
+            RELATIONS_SEARCH_ENDPOINT, relations[0].id, object_relation.id, before
+        );
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<AnyRelation> = test::read_body_json(resp).await;
+        assert!(found.is_empty());
+
+        cleanup(&classes).await;
+    }
+
+    #[actix_web::test]
+    async fn test_search_relations() {
+        let (pool, admin_token, _) = setup_pool_and_tokens().await;
+        let (classes, relations) = create_classes_and_relations(&pool, "search_relations").await;
+        let objects = create_objects_in_classes(&pool, &classes).await;
+        let object_relation =
+            create_object_relation(&pool, &objects[0], &objects[1], &relations[0]).await;
+
+        let class_ids = classes.iter().map(|c| c.id.to_string()).collect::<Vec<_>>().join(",");
+        let endpoint = format!("{}?from_classes={}", RELATIONS_SEARCH_ENDPOINT, class_ids);
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<AnyRelation> = test::read_body_json(resp).await;
+        let expected = relations.iter().map(|r| AnyRelation::Class(*r)).collect::<Vec<_>>();
+        assert_eq!(found.len(), expected.len());
+        assert!(expected.iter().all(|r| found.contains(r)));
+
+        let endpoint = format!("{}?from_objects={}", RELATIONS_SEARCH_ENDPOINT, objects[0].id);
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let body = test::read_body(resp).await;
+        let raw: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(raw[0]["kind"], "object");
+        let found: Vec<AnyRelation> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(found, vec![AnyRelation::Object(object_relation)]);
+
+        // Shared fields search both kinds, each with its own read permission.
+        let endpoint = format!(
+            "{}?id={},{}",
+            RELATIONS_SEARCH_ENDPOINT, relations[0].id, object_relation.id
+        );
+        let user = create_test_user(&pool).await;
+        let token = user.create_token(&pool).await.unwrap().get_token();
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        let namespace = NamespaceID(classes[0].namespace_id)
+            .instance(&pool)
+            .await
+            .unwrap();
+
+        let resp = get_request(&pool, &token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<AnyRelation> = test::read_body_json(resp).await;
+        assert!(found.is_empty());
+
+        namespace
+            .grant_one(&pool, group.id, Permissions::ReadObjectRelation)
+            .await
+            .unwrap();
+        let resp = get_request(&pool, &token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let found: Vec<AnyRelation> = test::read_body_json(resp).await;
+        assert!(found.contains(&AnyRelation::Object(object_relation)));
+        assert!(!found.contains(&AnyRelation::Class(relations[0])));
+
+        let endpoint = format!("{}?name=foo", RELATIONS_SEARCH_ENDPOINT);
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let _ = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+
+        cleanup(&classes).await;
+    }
 }
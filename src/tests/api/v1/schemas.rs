@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::header, http::StatusCode, test};
+
+    use crate::api::v1::handlers::schemas::SCHEMA_EXPORT_MAX_LIMIT;
+    use crate::models::{HubuumClassSchema, NamespaceID};
+    use crate::traits::SelfAccessors;
+
+    use crate::tests::api::v1::classes::tests::{cleanup, create_test_classes};
+    use crate::tests::api_operations::{get_request, get_request_with_headers};
+    use crate::tests::asserts::assert_response_status;
+    use crate::tests::setup_pool_and_tokens;
+
+    const SCHEMAS_ENDPOINT: &str = "/api/v1/schemas";
+
+    #[actix_web::test]
+    async fn test_api_get_schemas() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+        let classes = create_test_classes("get_schemas").await;
+        let namespace = NamespaceID(classes[0].namespace_id)
+            .instance(&pool)
+            .await
+            .unwrap();
+
+        let endpoint = format!("{}?namespaces={}", SCHEMAS_ENDPOINT, namespace.id);
+        let resp = get_request(&pool, &admin_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let vary: Vec<_> = resp.headers().get_all(header::VARY).collect();
+        assert_eq!(vary, ["Authorization", "Accept"]);
+        let schemas: Vec<HubuumClassSchema> = test::read_body_json(resp).await;
+        let expected: Vec<HubuumClassSchema> = classes
+            .iter()
+            .map(|c| HubuumClassSchema {
+                id: c.id,
+                name: c.name.clone(),
+                namespace_id: namespace.id,
+                namespace: namespace.name.clone(),
+                json_schema: c.json_schema.clone(),
+            })
+            .collect();
+        assert_eq!(schemas, expected);
+
+        // Pages follow the class IDs.
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!("{}&limit=2&offset=2", endpoint),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let schemas: Vec<HubuumClassSchema> = test::read_body_json(resp).await;
+        assert_eq!(schemas, expected[2..4].to_vec());
+
+        // Pages are capped.
+        let resp = get_request(
+            &pool,
+            &admin_token,
+            &format!("{}&limit={}", endpoint, SCHEMA_EXPORT_MAX_LIMIT + 1),
+        )
+        .await;
+        assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+
+        let resp = get_request(&pool, &normal_token, &endpoint).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let schemas: Vec<HubuumClassSchema> = test::read_body_json(resp).await;
+        assert!(schemas.is_empty());
+
+        let resp = get_request_with_headers(
+            &pool,
+            &admin_token,
+            &endpoint,
+            vec![(header::ACCEPT, "application/zip")],
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/zip"
+        );
+        let vary: Vec<_> = resp.headers().get_all(header::VARY).collect();
+        assert_eq!(vary, ["Authorization", "Accept"]);
+        assert_eq!(
+            resp.headers().get(header::CACHE_CONTROL).unwrap(),
+            "private"
+        );
+        let body = test::read_body(resp).await;
+        assert_eq!(&body[..4], b"PK\x03\x04");
+
+        let contains = |needle: &[u8]| body.windows(needle.len()).any(|w| w == needle);
+        for class in &classes {
+            let name = format!("{}/{}.schema.json", namespace.name, class.name);
+            assert!(contains(name.as_bytes()), "Missing {} in archive", name);
+
+            let schema = serde_json::to_vec_pretty(class.json_schema.as_ref().unwrap()).unwrap();
+            assert!(contains(&schema), "Missing schema of {} in archive", name);
+        }
+
+        cleanup(&classes).await;
+    }
+}
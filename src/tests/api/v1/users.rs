@@ -1,11 +1,15 @@
 #[cfg(test)]
 mod tests {
-    use crate::models::user::{NewUser, UpdateUser, User};
+    use crate::models::group::Group;
+    use crate::models::token::{NewTokenRequest, UserToken, MAX_TOKEN_LIFETIME_HOURS};
+    use crate::models::user::{NewUser, UpdateUser, User, UserDeletionImpact};
     use actix_web::{http::StatusCode, test};
 
     use crate::tests::api_operations::{delete_request, get_request, patch_request, post_request};
     use crate::tests::asserts::assert_response_status;
-    use crate::tests::{create_test_admin, create_test_user, setup_pool_and_tokens};
+    use crate::tests::{
+        create_test_admin, create_test_group, create_test_user, setup_pool_and_tokens,
+    };
 
     const USERS_ENDPOINT: &str = "/api/v1/iam/users";
 
@@ -52,6 +56,401 @@ mod tests {
         check_show_user_tokens(&test_user, &test_admin_user, StatusCode::OK).await;
     }
 
+    #[actix_web::test]
+    async fn test_issue_user_token() {
+        use diesel::prelude::*;
+
+        let (pool, _, normal_token) = setup_pool_and_tokens().await;
+        let service_user = create_test_user(&pool).await;
+        let admin_user = create_test_admin(&pool).await;
+        let admin_token = admin_user.create_token(&pool).await.unwrap().get_token();
+        let url = format!("{}/{}/tokens", USERS_ENDPOINT, service_user.id);
+
+        let request = NewTokenRequest {
+            name: Some("ci-pipeline".to_string()),
+            ttl_hours: Some(2),
+            namespace_id: None,
+            read_only: false,
+        };
+
+        // Only admins can issue tokens on behalf of others.
+        let resp = post_request(&pool, &normal_token, &url, &request).await;
+        let _ = assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let bad_request = NewTokenRequest {
+            name: None,
+            ttl_hours: Some(0),
+            namespace_id: None,
+            read_only: false,
+        };
+        let resp = post_request(&pool, &admin_token, &url, &bad_request).await;
+        let _ = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+
+        // A lifetime past the maximum would overflow the expiry computation in the database.
+        let too_long_request = NewTokenRequest {
+            ttl_hours: Some(MAX_TOKEN_LIFETIME_HOURS + 1),
+            ..bad_request
+        };
+        let resp = post_request(&pool, &admin_token, &url, &too_long_request).await;
+        let _ = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+
+        let resp = post_request(&pool, &admin_token, &url, &request).await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let issued: UserToken = test::read_body_json(resp).await;
+        assert_eq!(issued.user_id, service_user.id);
+        assert_eq!(issued.name, Some("ci-pipeline".to_string()));
+        assert_eq!(issued.expires(), issued.issued + chrono::Duration::hours(2));
+
+        // The token authenticates as the service user.
+        let resp = get_request(&pool, &issued.token, &url).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let tokens: Vec<UserToken> = test::read_body_json(resp).await;
+        assert!(tokens.iter().any(|t| t.token == issued.token));
+
+        let audit = {
+            use crate::schema::token_audit::dsl::*;
+            token_audit
+                .filter(user_id.eq(service_user.id))
+                .select((issued_by, name, ttl_hours))
+                .load::<(i32, Option<String>, Option<i32>)>(&mut pool.get().unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            audit,
+            vec![(admin_user.id, Some("ci-pipeline".to_string()), Some(2))]
+        );
+
+        service_user.delete(&pool).await.unwrap();
+        admin_user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_reset_user_password() {
+        use crate::models::user::{LoginUser, PasswordReset};
+        use diesel::prelude::*;
+
+        let (pool, _, normal_token) = setup_pool_and_tokens().await;
+        let user = create_test_user(&pool).await;
+        let admin_user = create_test_admin(&pool).await;
+        let admin_token = admin_user.create_token(&pool).await.unwrap().get_token();
+        let user_token = user.create_token(&pool).await.unwrap().get_token();
+        let url = format!("{}/{}/reset-password", USERS_ENDPOINT, user.id);
+
+        let login = |password: &str| LoginUser {
+            username: user.username.clone(),
+            password: password.to_string(),
+        };
+        let reset = |password: &str, revoke_tokens: bool| PasswordReset {
+            password: password.to_string(),
+            revoke_tokens,
+        };
+
+        // Only admins can reset passwords.
+        let resp = post_request(&pool, &normal_token, &url, &reset("newpassword1", false)).await;
+        let _ = assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        // Weak passwords are rejected.
+        for password in ["short", user.username.to_uppercase().as_str()] {
+            let resp = post_request(&pool, &admin_token, &url, &reset(password, false)).await;
+            let _ = assert_response_status(resp, StatusCode::BAD_REQUEST).await;
+        }
+        assert!(login("testpassword").login(&pool).await.is_ok());
+
+        let resp = post_request(&pool, &admin_token, &url, &reset("newpassword1", false)).await;
+        let _ = assert_response_status(resp, StatusCode::NO_CONTENT).await;
+        assert!(login("testpassword").login(&pool).await.is_err());
+        assert!(login("newpassword1").login(&pool).await.is_ok());
+
+        // The tokens of the user are kept unless revoked.
+        let tokens_url = format!("{}/{}/tokens", USERS_ENDPOINT, user.id);
+        let resp = get_request(&pool, &user_token, &tokens_url).await;
+        let _ = assert_response_status(resp, StatusCode::OK).await;
+
+        let resp = post_request(&pool, &admin_token, &url, &reset("newpassword2", true)).await;
+        let _ = assert_response_status(resp, StatusCode::NO_CONTENT).await;
+        assert!(login("newpassword2").login(&pool).await.is_ok());
+        let resp = get_request(&pool, &user_token, &tokens_url).await;
+        let _ = assert_response_status(resp, StatusCode::UNAUTHORIZED).await;
+
+        let audit = {
+            use crate::schema::password_audit::dsl::*;
+            password_audit
+                .filter(user_id.eq(user.id))
+                .order_by(id)
+                .select((reset_by, tokens_revoked))
+                .load::<(i32, bool)>(&mut pool.get().unwrap())
+                .unwrap()
+        };
+        assert_eq!(audit, vec![(admin_user.id, false), (admin_user.id, true)]);
+
+        let resp = post_request(
+            &pool,
+            &admin_token,
+            &format!("{}/{}/reset-password", USERS_ENDPOINT, i32::MAX),
+            &reset("newpassword3", false),
+        )
+        .await;
+        let _ = assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        user.delete(&pool).await.unwrap();
+        admin_user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_delete_user_dry_run() {
+        let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
+
+        let user = create_test_user(&pool).await;
+        let group = create_test_group(&pool).await;
+        group.add_member(&pool, &user).await.unwrap();
+        let token = user.create_token(&pool).await.unwrap().get_token();
+        user.create_token(&pool).await.unwrap();
+
+        let user_url = format!("{}/{}", USERS_ENDPOINT, user.id);
+        let dry_run_url = format!("{}?dry_run=true", user_url);
+
+        let resp = delete_request(&pool, &normal_token, &dry_run_url).await;
+        let _ = assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        let resp = delete_request(&pool, &admin_token, &dry_run_url).await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let impact: UserDeletionImpact = test::read_body_json(resp).await;
+        assert_eq!(
+            impact,
+            UserDeletionImpact {
+                tokens: 2,
+                group_memberships: 1,
+            }
+        );
+
+        // Nothing was deleted, and the tokens of the user still work.
+        let resp = get_request(&pool, &token, &format!("{}/tokens", user_url)).await;
+        let _ = assert_response_status(resp, StatusCode::OK).await;
+        assert_eq!(user.deletion_impact(&pool).await.unwrap(), impact);
+
+        let resp = delete_request(
+            &pool,
+            &admin_token,
+            &format!("{}/{}?dry_run=true", USERS_ENDPOINT, i32::MAX),
+        )
+        .await;
+        let _ = assert_response_status(resp, StatusCode::NOT_FOUND).await;
+
+        let resp = delete_request(&pool, &admin_token, &user_url).await;
+        let _ = assert_response_status(resp, StatusCode::NO_CONTENT).await;
+        assert_eq!(group.members(&pool).await.unwrap().len(), 0);
+
+        group.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_namespace_scoped_token() {
+        use crate::models::{HubuumClassExpanded, Namespace, NewHubuumClass};
+        use crate::tests::create_namespace;
+        use crate::traits::{CanDelete, CanSave};
+
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let admin_user = create_test_admin(&pool).await;
+        let admin_token = admin_user.create_token(&pool).await.unwrap().get_token();
+
+        let mut namespaces = vec![];
+        let mut classes = vec![];
+        for suffix in ["in", "out"] {
+            let name = format!("scoped_token_{}", suffix);
+            let ns = create_namespace(&pool, &name).await.unwrap();
+            let class = NewHubuumClass {
+                name: name.clone(),
+                description: name.clone(),
+                namespace_id: ns.id,
+                json_schema: None,
+                validate_schema: Some(false),
+            }
+            .save(&pool)
+            .await
+            .unwrap();
+            namespaces.push(ns);
+            classes.push(class);
+        }
+
+        let request = NewTokenRequest {
+            name: Some("scoped".to_string()),
+            ttl_hours: None,
+            namespace_id: Some(namespaces[0].id),
+            read_only: false,
+        };
+        let url = format!("{}/{}/tokens", USERS_ENDPOINT, admin_user.id);
+        let resp = post_request(&pool, &admin_token, &url, &request).await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let scoped: UserToken = test::read_body_json(resp).await;
+        assert_eq!(scoped.namespace_id, Some(namespaces[0].id));
+
+        // The user can read both classes, but the token only reaches the one in its namespace.
+        let resp = get_request(&pool, &scoped.token, "/api/v1/classes").await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let visible: Vec<HubuumClassExpanded> = test::read_body_json(resp).await;
+        assert!(visible.iter().any(|c| c.id == classes[0].id));
+        assert!(!visible.iter().any(|c| c.id == classes[1].id));
+
+        let class_url = |id: i32| format!("/api/v1/classes/{}", id);
+        let resp = get_request(&pool, &scoped.token, &class_url(classes[0].id)).await;
+        assert_response_status(resp, StatusCode::OK).await;
+        let resp = get_request(&pool, &scoped.token, &class_url(classes[1].id)).await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+        let resp = get_request(&pool, &admin_token, &class_url(classes[1].id)).await;
+        assert_response_status(resp, StatusCode::OK).await;
+
+        let resp = get_request(&pool, &scoped.token, "/api/v1/namespaces").await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let visible: Vec<Namespace> = test::read_body_json(resp).await;
+        assert_eq!(
+            visible.iter().map(|n| n.id).collect::<Vec<_>>(),
+            vec![namespaces[0].id]
+        );
+
+        // Admin operations are out of reach for a scoped token, including the admin-only parts
+        // of endpoints other users may use as well.
+        let other_user = create_test_user(&pool).await;
+        let objects_url = format!("/api/v1/classes/{}/", classes[0].id);
+        for endpoint in [
+            "/api/v1/admin/tokens".to_string(),
+            format!("{}/{}/tokens", USERS_ENDPOINT, other_user.id),
+            "/api/v1/objects/?diagnostics=true".to_string(),
+        ] {
+            let resp = get_request(&pool, &scoped.token, &endpoint).await;
+            assert_response_status(resp, StatusCode::FORBIDDEN).await;
+        }
+        let object = serde_json::json!({
+            "name": "scoped_token_object",
+            "namespace_id": namespaces[0].id,
+            "hubuum_class_id": classes[0].id,
+            "data": {},
+            "description": "scoped token object",
+        });
+        let resp = post_request(
+            &pool,
+            &scoped.token,
+            &format!("{}?skip_validation=true", objects_url),
+            &object,
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+        let resp = post_request(&pool, &scoped.token, &objects_url, &object).await;
+        assert_response_status(resp, StatusCode::CREATED).await;
+        other_user.delete(&pool).await.unwrap();
+
+        for ns in namespaces {
+            ns.delete(&pool).await.unwrap();
+        }
+        admin_user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_read_only_token() {
+        use crate::models::{Namespace, NewNamespaceWithAssignee};
+        use crate::tests::ensure_admin_group;
+        use crate::traits::CanDelete;
+
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let admin_user = create_test_admin(&pool).await;
+        let admin_token = admin_user.create_token(&pool).await.unwrap().get_token();
+
+        let request = NewTokenRequest {
+            name: Some("read-only".to_string()),
+            ttl_hours: None,
+            namespace_id: None,
+            read_only: true,
+        };
+        let url = format!("{}/{}/tokens", USERS_ENDPOINT, admin_user.id);
+        let resp = post_request(&pool, &admin_token, &url, &request).await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let read_only: UserToken = test::read_body_json(resp).await;
+        assert!(read_only.read_only);
+
+        let resp = get_request(&pool, &read_only.token, "/api/v1/namespaces").await;
+        assert_response_status(resp, StatusCode::OK).await;
+
+        let new_namespace = NewNamespaceWithAssignee {
+            name: "test_read_only_token".to_string(),
+            description: "test_read_only_token".to_string(),
+            group_id: ensure_admin_group(&pool).await.id,
+        };
+        let resp = post_request(
+            &pool,
+            &read_only.token,
+            "/api/v1/namespaces",
+            &new_namespace,
+        )
+        .await;
+        assert_response_status(resp, StatusCode::FORBIDDEN).await;
+
+        // Logging out other tokens is a write as well, even if it is a GET.
+        for endpoint in [
+            "/api/v0/auth/logout_all".to_string(),
+            format!("/api/v0/auth/logout/uid/{}", admin_user.id),
+            format!("/api/v0/auth/logout/token/{}", admin_token),
+        ] {
+            let resp = get_request(&pool, &read_only.token, &endpoint).await;
+            assert_response_status(resp, StatusCode::FORBIDDEN).await;
+        }
+
+        // The same request goes through with a regular token for the same user, which is still
+        // valid.
+        let resp = post_request(&pool, &admin_token, "/api/v1/namespaces", &new_namespace).await;
+        let resp = assert_response_status(resp, StatusCode::CREATED).await;
+        let ns: Namespace = test::read_body_json(resp).await;
+
+        ns.delete(&pool).await.unwrap();
+        admin_user.delete(&pool).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_my_groups() {
+        let (pool, _, _) = setup_pool_and_tokens().await;
+        let test_user = create_test_user(&pool).await;
+        let other_user = create_test_user(&pool).await;
+        let groups = [
+            create_test_group(&pool).await,
+            create_test_group(&pool).await,
+        ];
+
+        for group in &groups {
+            group.add_member(&pool, &test_user).await.unwrap();
+        }
+
+        let token = test_user.create_token(&pool).await.unwrap().get_token();
+        let resp = get_request(&pool, &token, "/api/v1/me/groups").await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let my_groups: Vec<Group> = test::read_body_json(resp).await;
+        assert_eq!(my_groups.len(), 2);
+        assert!(groups.iter().all(|g| my_groups.contains(g)));
+
+        // Same shape as the per-user listing
+        let resp = get_request(
+            &pool,
+            &token,
+            &format!("{}/{}/groups", USERS_ENDPOINT, test_user.id),
+        )
+        .await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let user_groups: Vec<Group> = test::read_body_json(resp).await;
+        assert_eq!(user_groups, my_groups);
+
+        let other_token = other_user.create_token(&pool).await.unwrap().get_token();
+        let resp = get_request(&pool, &other_token, "/api/v1/me/groups").await;
+        let resp = assert_response_status(resp, StatusCode::OK).await;
+        let other_groups: Vec<Group> = test::read_body_json(resp).await;
+        assert!(other_groups.is_empty());
+
+        let resp = get_request(&pool, "", "/api/v1/me/groups").await;
+        let _ = assert_response_status(resp, StatusCode::UNAUTHORIZED).await;
+
+        for group in groups {
+            group.delete(&pool).await.unwrap();
+        }
+        test_user.delete(&pool).await.unwrap();
+        other_user.delete(&pool).await.unwrap();
+    }
+
     #[actix_web::test]
     async fn test_create_and_delete_user() {
         let (pool, admin_token, normal_token) = setup_pool_and_tokens().await;
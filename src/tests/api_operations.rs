@@ -26,6 +26,48 @@ pub async fn get_request(
         .await
 }
 
+pub async fn get_request_with_headers(
+    pool: &DbPool,
+    token: &str,
+    endpoint: &str,
+    headers: Vec<(http::header::HeaderName, &str)>,
+) -> actix_web::dev::ServiceResponse {
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(pool.clone()))
+            .configure(prod_api::config),
+    )
+    .await;
+
+    let mut request = test::TestRequest::get()
+        .insert_header(create_token_header(token))
+        .uri(endpoint);
+    for header in headers {
+        request = request.insert_header(header);
+    }
+    request.send_request(&app).await
+}
+
+pub async fn head_request(
+    pool: &DbPool,
+    token: &str,
+    endpoint: &str,
+) -> actix_web::dev::ServiceResponse {
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(pool.clone()))
+            .configure(prod_api::config),
+    )
+    .await;
+
+    test::TestRequest::default()
+        .method(http::Method::HEAD)
+        .insert_header(create_token_header(token))
+        .uri(endpoint)
+        .send_request(&app)
+        .await
+}
+
 pub async fn post_request<T>(
     pool: &DbPool,
     token: &str,
@@ -69,6 +111,52 @@ pub async fn delete_request(
         .await
 }
 
+pub async fn delete_request_with_json<T>(
+    pool: &DbPool,
+    token: &str,
+    endpoint: &str,
+    content: T,
+) -> actix_web::dev::ServiceResponse
+where
+    T: Serialize,
+{
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(pool.clone()))
+            .configure(prod_api::config),
+    )
+    .await;
+
+    test::TestRequest::delete()
+        .insert_header(create_token_header(token))
+        .uri(endpoint)
+        .set_json(&content)
+        .send_request(&app)
+        .await
+}
+
+pub async fn delete_request_with_headers(
+    pool: &DbPool,
+    token: &str,
+    endpoint: &str,
+    headers: Vec<(http::header::HeaderName, &str)>,
+) -> actix_web::dev::ServiceResponse {
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(pool.clone()))
+            .configure(prod_api::config),
+    )
+    .await;
+
+    let mut request = test::TestRequest::delete()
+        .insert_header(create_token_header(token))
+        .uri(endpoint);
+    for header in headers {
+        request = request.insert_header(header);
+    }
+    request.send_request(&app).await
+}
+
 pub async fn patch_request<T>(
     pool: &DbPool,
     token: &str,
@@ -92,3 +180,27 @@ where
         .send_request(&app)
         .await
 }
+
+pub async fn put_request<T>(
+    pool: &DbPool,
+    token: &str,
+    endpoint: &str,
+    content: T,
+) -> actix_web::dev::ServiceResponse
+where
+    T: Serialize,
+{
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(pool.clone()))
+            .configure(prod_api::config),
+    )
+    .await;
+
+    test::TestRequest::put()
+        .insert_header(create_token_header(token))
+        .uri(endpoint)
+        .set_json(&content) // Make sure to reference content
+        .send_request(&app)
+        .await
+}
@@ -1,5 +1,4 @@
 #[cfg(test)]
-
 mod test {
     use yare::parameterized;
 
@@ -276,6 +275,102 @@ mod test {
         cleanup(namespaces).await;
     }
 
+    #[actix_rt::test]
+    async fn test_search_same_field_string_alternatives() {
+        let prefix = "test_user_class_string_alternatives";
+        let (namespaces, _) = setup_test_structure(prefix).await;
+
+        let like = |value: String| {
+            ParsedQueryParam::new(
+                "name",
+                Some(SearchOperator::Like { is_negated: false }),
+                &value,
+            )
+            .unwrap()
+        };
+
+        let testcases = vec![
+            // Two like patterns on the same field are OR'ed
+            TestCase {
+                query: vec![
+                    like(format!("{}_class_01", prefix)),
+                    like(format!("{}_class_02", prefix)),
+                ],
+                expected: 2,
+            },
+            TestCase {
+                query: vec![
+                    like(format!("{}_class_0%", prefix)),
+                    like(format!("{}_nomatch_%", prefix)),
+                ],
+                expected: 10,
+            },
+            // Different operators on the same field are still AND'ed
+            TestCase {
+                query: vec![
+                    like(format!("{}_class_0%", prefix)),
+                    like(format!("{}_class_1%", prefix)),
+                    ParsedQueryParam::new(
+                        "name",
+                        Some(SearchOperator::EndsWith { is_negated: false }),
+                        "class_03",
+                    )
+                    .unwrap(),
+                ],
+                expected: 1,
+            },
+            // Other fields are still AND'ed with the OR-group
+            TestCase {
+                query: vec![
+                    like(format!("{}_class_01", prefix)),
+                    like(format!("{}_class_07", prefix)),
+                    ParsedQueryParam::new(
+                        "namespaces",
+                        Some(SearchOperator::Equals { is_negated: false }),
+                        &namespaces[1].id.to_string(),
+                    )
+                    .unwrap(),
+                ],
+                expected: 1,
+            },
+            TestCase {
+                query: vec![
+                    like(format!("{}_class_0%", prefix)),
+                    like(format!("{}_class_1%", prefix)),
+                    ParsedQueryParam::new(
+                        "description",
+                        Some(SearchOperator::EndsWith { is_negated: false }),
+                        "class 09",
+                    )
+                    .unwrap(),
+                ],
+                expected: 1,
+            },
+            // Negated operators are not part of the OR-group
+            TestCase {
+                query: vec![
+                    like(format!("{}_class_0%", prefix)),
+                    ParsedQueryParam::new(
+                        "name",
+                        Some(SearchOperator::Like { is_negated: true }),
+                        &format!("%{}_class_01", prefix),
+                    )
+                    .unwrap(),
+                    ParsedQueryParam::new(
+                        "name",
+                        Some(SearchOperator::EndsWith { is_negated: true }),
+                        "class_02",
+                    )
+                    .unwrap(),
+                ],
+                expected: 8,
+            },
+        ];
+
+        check_test_cases(testcases).await;
+        cleanup(namespaces).await;
+    }
+
     #[actix_rt::test]
     async fn test_search_int_ranges() {
         let (namespaces, _) = setup_test_structure("test_user_class_int_ranges").await;
@@ -3,9 +3,10 @@ use serde::Serialize;
 
 use crate::db::DbPool;
 use crate::errors::ApiError;
+use crate::db::scope::scope_permissions;
 use crate::models::{
-    HubuumClass, HubuumObject, Namespace, NewPermission, Permission, PermissionFilter, Permissions,
-    PermissionsList, UpdatePermission, User,
+    HubuumClass, HubuumObject, Namespace, NewPermission, NewPermissionAudit, Permission,
+    PermissionFilter, Permissions, PermissionsList, UpdatePermission, User,
 };
 
 #[allow(unused_imports)]
@@ -166,8 +167,7 @@ pub trait PermissionController: Serialize + NamespaceAccessors {
 
         // Note that self.namespace_id(pool).await? is only a query if the caller is a HubuumClassID, otherwise
         // it's a simple field access (which ignores the passed pool).
-        let mut base_query = lookup_table
-            .into_boxed()
+        let mut base_query = scope_permissions(lookup_table.into_boxed())
             .filter(namespace_id_field.eq(self.namespace_id(pool).await?))
             .filter(group_id_field.eq_any(group_id_subquery));
 
@@ -350,6 +350,7 @@ pub trait PermissionController: Serialize + NamespaceAccessors {
     /// - `pool` - A connection pool to the database.
     /// - `group_identifier` - The group ID to revoke the permissions from.
     /// - `permission_list` - A list of permissions to revoke, wrapped in a PermissionsList.
+    /// - `reason` - An optional reason, recorded in the permission audit trail.
     ///
     /// ## Returns
     ///
@@ -360,7 +361,9 @@ pub trait PermissionController: Serialize + NamespaceAccessors {
         pool: &DbPool,
         group_id_for_revoke: i32,
         permission_list: PermissionsList<Permissions>,
+        reason: Option<&str>,
     ) -> Result<Permission, ApiError> {
+        use crate::schema::permission_audit::dsl::permission_audit;
         use crate::schema::permissions::dsl::*;
 
         let mut conn = pool.get()?;
@@ -373,6 +376,15 @@ pub trait PermissionController: Serialize + NamespaceAccessors {
                 .filter(group_id.eq(group_id_for_revoke))
                 .first::<Permission>(conn)?;
 
+            diesel::insert_into(permission_audit)
+                .values(NewPermissionAudit::revocation(
+                    nid,
+                    group_id_for_revoke,
+                    Some(&permission_list),
+                    reason,
+                ))
+                .execute(conn)?;
+
             let mut update_perm = UpdatePermission::default();
             for permission in permission_list.into_iter() {
                 match permission {
@@ -494,6 +506,7 @@ pub trait PermissionController: Serialize + NamespaceAccessors {
     /// - `pool` - A connection pool to the database.
     /// - `group_identifier` - The group ID to revoke the permission from.
     /// - `permission` - The permission to revoke.
+    /// - `reason` - An optional reason, recorded in the permission audit trail.
     ///
     /// ## Returns
     ///
@@ -504,11 +517,13 @@ pub trait PermissionController: Serialize + NamespaceAccessors {
         pool: &DbPool,
         group_identifier: i32,
         permission: Permissions,
+        reason: Option<&str>,
     ) -> Result<Permission, ApiError> {
         self.revoke(
             pool,
             group_identifier,
             PermissionsList::new(vec![permission]),
+            reason,
         )
         .await
     }
@@ -626,20 +641,42 @@ pub trait PermissionController: Serialize + NamespaceAccessors {
     ///
     /// - `pool` - A connection pool to the database.
     /// - `group_identifier` - The group ID to revoke the permissions from.
+    /// - `reason` - An optional reason, recorded in the permission audit trail if any
+    ///   permissions were removed.
     ///
     /// ## Returns
     ///
     /// An empty result.
-    async fn revoke_all(&self, pool: &DbPool, group_id_for_revoke: i32) -> Result<(), ApiError> {
+    async fn revoke_all(
+        &self,
+        pool: &DbPool,
+        group_id_for_revoke: i32,
+        reason: Option<&str>,
+    ) -> Result<(), ApiError> {
+        use crate::schema::permission_audit::dsl::permission_audit;
         use crate::schema::permissions::dsl::*;
 
         let mut conn = pool.get()?;
+        let nid = self.namespace_id(pool).await?;
 
-        diesel::delete(permissions)
-            .filter(namespace_id.eq(self.namespace_id(pool).await?))
-            .filter(group_id.eq(group_id_for_revoke))
-            .execute(&mut conn)?;
+        conn.transaction::<_, ApiError, _>(|conn| {
+            let removed = diesel::delete(permissions)
+                .filter(namespace_id.eq(nid))
+                .filter(group_id.eq(group_id_for_revoke))
+                .execute(conn)?;
+
+            if removed > 0 {
+                diesel::insert_into(permission_audit)
+                    .values(NewPermissionAudit::revocation(
+                        nid,
+                        group_id_for_revoke,
+                        None,
+                        reason,
+                    ))
+                    .execute(conn)?;
+            }
 
-        Ok(())
+            Ok(())
+        })
     }
 }
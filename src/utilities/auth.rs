@@ -1,5 +1,6 @@
 extern crate argon2;
 
+use crate::errors::ApiError;
 use crate::models::token::Token;
 
 use argon2::{
@@ -57,9 +58,33 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool, argon2::Error
         }
     };
 
-    return Ok(Argon2::default()
+    Ok(Argon2::default()
         .verify_password(password.as_bytes(), &parsed_hash)
-        .is_ok());
+        .is_ok())
+}
+
+/// The shortest password an admin may set for a user.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Check a new plaintext password against the password policy.
+///
+/// The password must be at least [`MIN_PASSWORD_LENGTH`] characters long and must not be the
+/// username, ignoring case.
+pub fn validate_password_strength(password: &str, username: &str) -> Result<(), ApiError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(ApiError::BadRequest(format!(
+            "Password must be at least {} characters long",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+
+    if password.to_lowercase() == username.to_lowercase() {
+        return Err(ApiError::BadRequest(
+            "Password must not be the username".to_string(),
+        ));
+    }
+
+    Ok(())
 }
 
 pub fn generate_random_password(length: usize) -> String {
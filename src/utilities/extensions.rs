@@ -3,16 +3,6 @@ use crate::models::permissions::Permissions;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 
 pub trait CustomStringExtensions {
-    /// ## Check if the value is a valid json key for hubuum
-    ///
-    /// We support only lowercase alphanumeric characters and underscores, as
-    /// well as the comma character for nested keys. No spaces are allowed.
-    ///
-    /// ### Returns
-    ///
-    /// * A boolean
-    fn is_valid_jsonb_search_key(&self) -> bool;
-
     /// ## Coerce the value into a boolean
     ///
     /// Accepted values are "true" and "false" (case insensitive)
@@ -74,12 +64,6 @@ impl<T: AsRef<str>> CustomStringExtensions for T {
         result
     }
 
-    fn is_valid_jsonb_search_key(&self) -> bool {
-        self.as_ref()
-            .chars()
-            .all(|c| c.is_alphanumeric() || c == '_' || c == ',' || c == '$')
-    }
-
     fn as_integer(&self) -> Result<Vec<i32>, ApiError> {
         parse_integer_list(self.as_ref())
     }
@@ -121,7 +105,6 @@ impl<T: AsRef<str>> CustomStringExtensions for T {
                             .map(|date| date.and_hms_opt(0, 0, 0).unwrap())
                             .map_err(Into::<ApiError>::into)
                     })
-                    .map_err(Into::<ApiError>::into)
             })
             .collect()
     }
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A key that is only present on one side of a diff.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JsonDiffEntry {
+    pub path: Vec<String>,
+    pub value: Value,
+}
+
+/// A key that is present on both sides of a diff, with different values.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JsonDiffChange {
+    pub path: Vec<String>,
+    pub from: Value,
+    pub to: Value,
+}
+
+/// The difference between two JSON values, see [`diff`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct JsonDiff {
+    pub added: Vec<JsonDiffEntry>,
+    pub removed: Vec<JsonDiffEntry>,
+    pub changed: Vec<JsonDiffChange>,
+}
+
+/// Diff two JSON values.
+///
+/// Objects are compared key by key, recursively, and every difference is reported with the path
+/// of keys leading to it. Any other values, including arrays, are compared as a whole, so a
+/// changed element of an array is reported as a change of the array. Differing values at the
+/// top level are reported as a change with an empty path.
+///
+/// The entries are sorted by path.
+pub fn diff(from: &Value, to: &Value) -> JsonDiff {
+    let mut result = JsonDiff::default();
+    diff_at(&mut Vec::new(), from, to, &mut result);
+
+    result.added.sort_by(|a, b| a.path.cmp(&b.path));
+    result.removed.sort_by(|a, b| a.path.cmp(&b.path));
+    result.changed.sort_by(|a, b| a.path.cmp(&b.path));
+    result
+}
+
+fn diff_at(path: &mut Vec<String>, from: &Value, to: &Value, result: &mut JsonDiff) {
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            for (key, from_value) in from {
+                path.push(key.clone());
+                match to.get(key) {
+                    Some(to_value) => diff_at(path, from_value, to_value, result),
+                    None => result.removed.push(JsonDiffEntry {
+                        path: path.clone(),
+                        value: from_value.clone(),
+                    }),
+                }
+                path.pop();
+            }
+
+            for (key, to_value) in to.iter().filter(|(key, _)| !from.contains_key(*key)) {
+                let mut added_path = path.clone();
+                added_path.push(key.clone());
+                result.added.push(JsonDiffEntry {
+                    path: added_path,
+                    value: to_value.clone(),
+                });
+            }
+        }
+        (from, to) if from != to => result.changed.push(JsonDiffChange {
+            path: path.clone(),
+            from: from.clone(),
+            to: to.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|k| k.to_string()).collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let from = json!({
+            "name": "web-1",
+            "owner": null,
+            "tags": ["a", "b"],
+            "location": {"rack": 4, "row": "A", "room": {"floor": 1}},
+            "retired": false,
+        });
+        let to = json!({
+            "name": "web-1",
+            "owner": "ops",
+            "tags": ["a", "c"],
+            "location": {"rack": 5, "room": {"floor": 1, "wing": "east"}},
+            "serial": "XYZ",
+        });
+
+        assert_eq!(
+            diff(&from, &to),
+            JsonDiff {
+                added: vec![
+                    JsonDiffEntry {
+                        path: path(&["location", "room", "wing"]),
+                        value: json!("east"),
+                    },
+                    JsonDiffEntry {
+                        path: path(&["serial"]),
+                        value: json!("XYZ"),
+                    },
+                ],
+                removed: vec![
+                    JsonDiffEntry {
+                        path: path(&["location", "row"]),
+                        value: json!("A"),
+                    },
+                    JsonDiffEntry {
+                        path: path(&["retired"]),
+                        value: json!(false),
+                    },
+                ],
+                changed: vec![
+                    JsonDiffChange {
+                        path: path(&["location", "rack"]),
+                        from: json!(4),
+                        to: json!(5),
+                    },
+                    JsonDiffChange {
+                        path: path(&["owner"]),
+                        from: json!(null),
+                        to: json!("ops"),
+                    },
+                    JsonDiffChange {
+                        path: path(&["tags"]),
+                        from: json!(["a", "b"]),
+                        to: json!(["a", "c"]),
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_diff_identical_and_top_level() {
+        let value = json!({"a": {"b": [1, 2]}});
+        assert_eq!(diff(&value, &value), JsonDiff::default());
+
+        // An object replaced by another type is a single change, not a list of removed keys.
+        let result = diff(&json!({"a": {"b": 1}}), &json!({"a": 1}));
+        assert_eq!(
+            result.changed,
+            vec![JsonDiffChange {
+                path: path(&["a"]),
+                from: json!({"b": 1}),
+                to: json!(1),
+            }]
+        );
+
+        let result = diff(&json!([1]), &json!({}));
+        assert_eq!(result.changed[0].path, Vec::<String>::new());
+    }
+}
@@ -3,7 +3,10 @@ pub mod db;
 pub mod extensions;
 pub mod iam;
 pub mod init;
+pub mod json_diff;
+pub mod rate_limit;
 pub mod response;
+pub mod zip;
 
 pub fn is_valid_log_level(level: &str) -> bool {
     matches!(level, "error" | "warn" | "info" | "debug" | "trace")
@@ -0,0 +1,128 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::get_config;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::models::NamespaceID;
+use crate::traits::SelfAccessors;
+
+/// The window the write rate limits of namespaces are counted over.
+pub const WRITE_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    // The start of the current window and the number of writes in it, by namespace ID.
+    static ref WRITE_COUNTERS: Mutex<HashMap<i32, (Instant, u32)>> = Mutex::new(HashMap::new());
+}
+
+/// Count a write to a namespace against its write rate limit.
+///
+/// The limit is the `write_rate_limit` of the namespace if set, and otherwise
+/// `HUBUUM_NAMESPACE_WRITE_RATE_LIMIT`. A limit of 0 means no limit. The counters are kept in
+/// memory, so the limit is per instance and is reset on restart.
+///
+/// ## Returns
+/// * Ok(()) - The write is within the limit and has been counted.
+/// * Err(ApiError::TooManyRequests) - The limit is exceeded, with the seconds until the window
+///   resets.
+pub async fn check_namespace_write_rate(pool: &DbPool, namespace_id: i32) -> Result<(), ApiError> {
+    check_namespace_writes(pool, namespace_id, 1).await
+}
+
+/// Count several writes to a namespace against its write rate limit, eg for a batch.
+///
+/// Either all the writes fit within the limit and are counted, or none are. See
+/// [`check_namespace_write_rate`] for the limits.
+pub async fn check_namespace_writes(
+    pool: &DbPool,
+    namespace_id: i32,
+    writes: u32,
+) -> Result<(), ApiError> {
+    let namespace = NamespaceID(namespace_id).instance(pool).await?;
+    let limit = match namespace.write_rate_limit {
+        Some(limit) => limit.max(0) as u32,
+        None => get_config().await.namespace_write_rate_limit,
+    };
+
+    if limit == 0 {
+        return Ok(());
+    }
+
+    count_writes(namespace_id, writes, limit, Instant::now())
+}
+
+fn count_writes(namespace_id: i32, writes: u32, limit: u32, now: Instant) -> Result<(), ApiError> {
+    let mut counters = WRITE_COUNTERS.lock().unwrap();
+    let (window_start, count) = counters.entry(namespace_id).or_insert((now, 0));
+
+    let elapsed = now.saturating_duration_since(*window_start);
+    if elapsed >= WRITE_RATE_WINDOW {
+        *window_start = now;
+        *count = 0;
+    }
+
+    if count.saturating_add(writes) > limit {
+        let remaining = WRITE_RATE_WINDOW.saturating_sub(elapsed);
+        let message = if writes == 1 {
+            format!(
+                "Write rate limit of {} per minute exceeded for namespace {}",
+                limit, namespace_id
+            )
+        } else {
+            format!(
+                "Write rate limit of {} per minute exceeded for namespace {}, {} writes requested with {} left",
+                limit,
+                namespace_id,
+                writes,
+                limit.saturating_sub(*count)
+            )
+        };
+        return Err(ApiError::TooManyRequests(
+            message,
+            remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0),
+        ));
+    }
+
+    *count += writes;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_writes() {
+        // Namespace IDs are never negative, so these can't collide with the API tests.
+        let start = Instant::now();
+        assert!(count_writes(-1, 1, 2, start).is_ok());
+        assert!(count_writes(-1, 1, 2, start).is_ok());
+
+        match count_writes(-1, 1, 2, start + Duration::from_millis(500)) {
+            Err(ApiError::TooManyRequests(_, retry_after)) => assert_eq!(retry_after, 60),
+            other => panic!("Expected too many requests, got {:?}", other),
+        }
+        match count_writes(-1, 1, 2, start + Duration::from_secs(59)) {
+            Err(ApiError::TooManyRequests(_, retry_after)) => assert_eq!(retry_after, 1),
+            other => panic!("Expected too many requests, got {:?}", other),
+        }
+
+        // The other namespace has its own counter, and the window resets after a minute.
+        assert!(count_writes(-2, 1, 1, start).is_ok());
+        assert!(count_writes(-1, 1, 2, start + WRITE_RATE_WINDOW).is_ok());
+
+        // Several writes are counted together, and not at all if they don't all fit.
+        assert!(matches!(
+            count_writes(-3, 4, 3, start),
+            Err(ApiError::TooManyRequests(_, _))
+        ));
+        assert!(count_writes(-3, 2, 3, start).is_ok());
+        assert!(matches!(
+            count_writes(-3, 2, 3, start),
+            Err(ApiError::TooManyRequests(_, _))
+        ));
+        assert!(count_writes(-3, 1, 3, start).is_ok());
+    }
+}
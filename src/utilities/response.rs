@@ -1,9 +1,14 @@
-use actix_web::{http::StatusCode, HttpResponse};
+use actix_web::http::header::{self, EntityTag, IfUnmodifiedSince};
+use actix_web::{http::StatusCode, HttpResponse, HttpResponseBuilder};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::Serialize;
 
 use std::collections::HashMap;
+use std::time::SystemTime;
 use tracing::debug;
 
+use crate::errors::ApiError;
+
 use lazy_static::lazy_static;
 use std::collections::HashSet;
 
@@ -18,6 +23,20 @@ lazy_static! {
     };
 }
 
+/// Start a response that only the client itself may cache.
+///
+/// What a response holds depends on the token it was requested with, so a shared cache must
+/// neither store it nor hand it to another user. This sets `Vary: Authorization` and
+/// `Cache-Control: private`. Further `Vary` values must be added with `append_header`, as
+/// `insert_header` replaces the existing ones.
+pub fn private_response(status: StatusCode) -> HttpResponseBuilder {
+    let mut response_builder = HttpResponse::build(status);
+    response_builder
+        .append_header((header::VARY, "Authorization"))
+        .insert_header((header::CACHE_CONTROL, "private"));
+    response_builder
+}
+
 /// Create a JSON response with the given data and status code
 ///
 /// ## Arguments
@@ -34,12 +53,16 @@ pub fn json_response_with_header<T: Serialize>(
     status: StatusCode,
     headers: Option<HashMap<&str, &str>>,
 ) -> HttpResponse {
-    let mut response_builder = HttpResponse::build(status);
+    let mut response_builder = private_response(status);
 
     if let Some(hdrs) = headers {
         for (key, value) in hdrs {
             debug!(message = "Adding response header", key = key, value = value);
-            response_builder.insert_header((key, value));
+            if key.eq_ignore_ascii_case(header::VARY.as_str()) {
+                response_builder.append_header((key, value));
+            } else {
+                response_builder.insert_header((key, value));
+            }
         }
     }
 
@@ -57,3 +80,86 @@ pub fn json_response_created<T: Serialize>(object: T, location: &str) -> HttpRes
 
     json_response_with_header(object, StatusCode::CREATED, Some(headers))
 }
+
+/// Check an `If-Unmodified-Since` precondition against when an entity was last updated.
+///
+/// HTTP dates only have a resolution of seconds, so an entity updated within the second given
+/// by the header counts as unmodified.
+///
+/// ## Returns
+///
+/// * Nothing if there is no precondition or it holds, or an [`ApiError::PreconditionFailed`] if
+///   the entity was updated after the given date.
+pub fn check_unmodified_since(
+    if_unmodified_since: Option<&IfUnmodifiedSince>,
+    updated_at: NaiveDateTime,
+) -> Result<(), ApiError> {
+    let Some(IfUnmodifiedSince(since)) = if_unmodified_since else {
+        return Ok(());
+    };
+
+    let since: DateTime<Utc> = SystemTime::from(*since).into();
+    if updated_at.and_utc().timestamp() > since.timestamp() {
+        return Err(ApiError::PreconditionFailed(format!(
+            "Modified at {}, after {}",
+            updated_at.and_utc().to_rfc3339(),
+            since.to_rfc3339()
+        )));
+    }
+
+    Ok(())
+}
+
+/// A strong entity tag for an entity, derived from its ID and when it was last updated.
+///
+/// Every update bumps `updated_at`, so the tag changes whenever the entity does.
+pub fn entity_tag(id: i32, updated_at: NaiveDateTime) -> EntityTag {
+    EntityTag::new_strong(format!(
+        "{}-{}",
+        id,
+        updated_at.and_utc().timestamp_micros()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::ResponseError;
+    use serde_json::json;
+
+    fn header_values(response: &HttpResponse, name: header::HeaderName) -> Vec<&str> {
+        response
+            .headers()
+            .get_all(name)
+            .map(|value| value.to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_responses_are_private() {
+        for response in [
+            json_response(json!({"id": 1}), StatusCode::OK),
+            json_response((), StatusCode::NO_CONTENT),
+            json_response_created(json!({"id": 1}), "/api/v1/namespaces/1"),
+            ApiError::Forbidden("No".to_string()).error_response(),
+        ] {
+            assert_eq!(header_values(&response, header::VARY), ["Authorization"]);
+            assert_eq!(header_values(&response, header::CACHE_CONTROL), ["private"]);
+        }
+    }
+
+    #[test]
+    fn test_response_headers_extend_vary() {
+        let headers = HashMap::from([("Vary", "Accept"), ("Cache-Control", "no-store")]);
+        let response = json_response_with_header(json!([]), StatusCode::OK, Some(headers));
+
+        assert_eq!(
+            header_values(&response, header::VARY),
+            ["Authorization", "Accept"]
+        );
+        assert_eq!(
+            header_values(&response, header::CACHE_CONTROL),
+            ["no-store"]
+        );
+    }
+}
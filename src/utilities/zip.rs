@@ -0,0 +1,173 @@
+use std::collections::HashSet;
+
+use crate::errors::ApiError;
+
+/// Build an uncompressed (stored) zip archive of the given files.
+///
+/// This only covers what we need to hand out small sets of text files: no compression, no
+/// directory entries, no zip64 and a fixed timestamp of 1980-01-01. The names are used as is,
+/// so they should be relative paths using `/` as the separator.
+///
+/// ## Returns
+/// * The archive, or ApiError::InternalServerError if the names are not unique, or the files
+///   don't fit in a zip archive without zip64, ie more than 65535 files, names longer than 65535
+///   bytes, or an archive of 4 GiB or more.
+pub fn zip_stored(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>, ApiError> {
+    // The DOS date for 1980-01-01, the earliest date zip can represent.
+    const DOS_DATE: u16 = (1 << 5) | 1;
+
+    let too_large = |what: &str| {
+        ApiError::InternalServerError(format!("Zip archive has too many or too large {}", what))
+    };
+
+    let file_count = u16::try_from(files.len()).map_err(|_| too_large("files"))?;
+    let mut names = HashSet::new();
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+
+    for (name, content) in files {
+        if !names.insert(name) {
+            return Err(ApiError::InternalServerError(format!(
+                "Zip archive has more than one file named '{}'",
+                name
+            )));
+        }
+
+        let offset = u32::try_from(archive.len()).map_err(|_| too_large("files"))?;
+        let crc = crc32(content);
+        let size = u32::try_from(content.len()).map_err(|_| too_large("files"))?;
+        let name_length = u16::try_from(name.len()).map_err(|_| too_large("file names"))?;
+
+        // Local file header
+        archive.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        archive.extend_from_slice(&20u16.to_le_bytes()); // Version needed to extract
+        archive.extend_from_slice(&0x0800u16.to_le_bytes()); // Flags, names are UTF-8
+        archive.extend_from_slice(&0u16.to_le_bytes()); // Compression, stored
+        archive.extend_from_slice(&0u16.to_le_bytes()); // Modification time
+        archive.extend_from_slice(&DOS_DATE.to_le_bytes());
+        archive.extend_from_slice(&crc.to_le_bytes());
+        archive.extend_from_slice(&size.to_le_bytes()); // Compressed size
+        archive.extend_from_slice(&size.to_le_bytes()); // Uncompressed size
+        archive.extend_from_slice(&name_length.to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes()); // Extra field length
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(content);
+
+        // Central directory header
+        central_directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes()); // Version made by
+        central_directory.extend_from_slice(&20u16.to_le_bytes()); // Version needed to extract
+        central_directory.extend_from_slice(&0x0800u16.to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes());
+        central_directory.extend_from_slice(&DOS_DATE.to_le_bytes());
+        central_directory.extend_from_slice(&crc.to_le_bytes());
+        central_directory.extend_from_slice(&size.to_le_bytes());
+        central_directory.extend_from_slice(&size.to_le_bytes());
+        central_directory.extend_from_slice(&name_length.to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Extra field length
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Comment length
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Disk number
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Internal attributes
+        central_directory.extend_from_slice(&0u32.to_le_bytes()); // External attributes
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let central_directory_offset = u32::try_from(archive.len()).map_err(|_| too_large("files"))?;
+    let central_directory_size =
+        u32::try_from(central_directory.len()).map_err(|_| too_large("files"))?;
+    archive.extend_from_slice(&central_directory);
+
+    // End of central directory record
+    archive.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // Number of this disk
+    archive.extend_from_slice(&0u16.to_le_bytes()); // Disk with the central directory
+    archive.extend_from_slice(&file_count.to_le_bytes());
+    archive.extend_from_slice(&file_count.to_le_bytes());
+    archive.extend_from_slice(&central_directory_size.to_le_bytes());
+    archive.extend_from_slice(&central_directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // Comment length
+
+    Ok(archive)
+}
+
+/// The CRC-32 (IEEE) checksum zip uses for file contents.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(data: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([data[offset], data[offset + 1]])
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_zip_stored() {
+        let files = vec![
+            ("a.json".to_string(), b"{}".to_vec()),
+            ("dir/b.json".to_string(), b"[1, 2]".to_vec()),
+        ];
+        let archive = zip_stored(&files).unwrap();
+
+        // The first entry starts the archive, and its content follows the name.
+        assert_eq!(u32_at(&archive, 0), 0x04034b50);
+        assert_eq!(u32_at(&archive, 14), crc32(b"{}"));
+        assert_eq!(u16_at(&archive, 26), 6);
+        assert_eq!(&archive[30..36], b"a.json");
+        assert_eq!(&archive[36..38], b"{}");
+
+        // The end record points at the central directory, which lists both entries.
+        let end = archive.len() - 22;
+        assert_eq!(u32_at(&archive, end), 0x06054b50);
+        assert_eq!(u16_at(&archive, end + 10), 2);
+        let central_directory = u32_at(&archive, end + 16) as usize;
+        assert_eq!(u32_at(&archive, central_directory), 0x02014b50);
+        assert_eq!(central_directory + u32_at(&archive, end + 12) as usize, end);
+
+        let second = u32_at(&archive, central_directory + 46 + 6 + 42) as usize;
+        assert_eq!(u32_at(&archive, second), 0x04034b50);
+        assert_eq!(&archive[second + 30..second + 40], b"dir/b.json");
+        assert_eq!(&archive[second + 40..second + 46], b"[1, 2]");
+
+        assert_eq!(zip_stored(&[]).unwrap().len(), 22);
+    }
+
+    #[test]
+    fn test_zip_stored_limits() {
+        let file = |name: String| (name, b"{}".to_vec());
+
+        let duplicates = vec![file("a.json".to_string()), file("a.json".to_string())];
+        assert!(zip_stored(&duplicates).is_err());
+
+        assert!(zip_stored(&[file("a".repeat(u16::MAX as usize))]).is_ok());
+        assert!(zip_stored(&[file("a".repeat(u16::MAX as usize + 1))]).is_err());
+
+        let files = (0..=u16::MAX as usize)
+            .map(|i| file(i.to_string()))
+            .collect::<Vec<_>>();
+        assert!(zip_stored(&files[1..]).is_ok());
+        assert!(zip_stored(&files).is_err());
+    }
+}